@@ -1,9 +1,12 @@
-use chess_core::{Color, GameState, PieceType, Square};
+use chess_core::{BitBoard, Color, File, GameState, PieceType, Square};
 
 /// Extension trait to add evaluation methods to GameState
 pub trait Evaluatable {
     fn evaluate(&self) -> i32;
     fn evaluate_absolute(&self) -> i32;
+    fn evaluate_from(&self, color: Color) -> i32;
+    fn evaluate_explained(&self) -> EvalBreakdown;
+    fn material_phase_scaled_eval(&self) -> i32;
 }
 
 impl Evaluatable for GameState {
@@ -14,43 +17,253 @@ impl Evaluatable for GameState {
     fn evaluate_absolute(&self) -> i32 {
         evaluate_absolute(self)
     }
+
+    fn evaluate_from(&self, color: Color) -> i32 {
+        evaluate_from(self, color)
+    }
+
+    fn evaluate_explained(&self) -> EvalBreakdown {
+        evaluate_explained(self)
+    }
+
+    fn material_phase_scaled_eval(&self) -> i32 {
+        material_phase_scaled_eval(self)
+    }
+}
+
+/// A static evaluation the search can run on, so alternatives such as
+/// learned models can replace the hand-crafted one. Scores are centipawns
+/// from the side to move's perspective, like `evaluate`.
+pub trait Evaluator: Send + Sync {
+    fn eval(&self, state: &GameState) -> i32;
+}
+
+impl std::fmt::Debug for dyn Evaluator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("dyn Evaluator")
+    }
+}
+
+/// The hand-crafted evaluation in this module, used by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HandCraftedEvaluator;
+
+impl Evaluator for HandCraftedEvaluator {
+    fn eval(&self, state: &GameState) -> i32 {
+        evaluate(state)
+    }
+}
+
+/// One evaluation term, scored separately for each side.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvalTerm {
+    pub white: i32,
+    pub black: i32,
+}
+
+impl EvalTerm {
+    fn for_both(mut score: impl FnMut(Color) -> i32) -> Self {
+        Self {
+            white: score(Color::White),
+            black: score(Color::Black),
+        }
+    }
+
+    /// Blends a middlegame and an endgame score for each side by `phase`,
+    /// from all middlegame at `MAX_PHASE` to all endgame at 0.
+    fn tapered(
+        phase: i32,
+        mut middlegame: impl FnMut(Color) -> i32,
+        mut endgame: impl FnMut(Color) -> i32,
+    ) -> Self {
+        Self::for_both(|color| {
+            (middlegame(color) * phase + endgame(color) * (MAX_PHASE - phase)) / MAX_PHASE
+        })
+    }
+
+    /// The term's contribution from White's perspective.
+    pub fn net(self) -> i32 {
+        self.white - self.black
+    }
+}
+
+/// Per-term contributions to `evaluate_absolute`, for tuning and debugging.
+/// Every term except `draw` is already blended by game phase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvalBreakdown {
+    pub material: EvalTerm,
+    pub piece_square: EvalTerm,
+    pub center: EvalTerm,
+    /// Pulls the stronger side's score toward zero in a recognised draw
+    pub draw: EvalTerm,
+}
+
+impl EvalBreakdown {
+    /// Each term with a display name, in evaluation order.
+    pub fn terms(&self) -> [(&'static str, EvalTerm); 4] {
+        [
+            ("Material", self.material),
+            ("Piece-square", self.piece_square),
+            ("Center", self.center),
+            ("Draw", self.draw),
+        ]
+    }
+
+    /// Sum of all terms from White's perspective; equals `evaluate_absolute`.
+    pub fn total(&self) -> i32 {
+        self.terms().iter().map(|(_, term)| term.net()).sum()
+    }
 }
 
 /// Evaluates a chess position from the perspective of the side to move.
 /// Returns a score in centipawns where positive values favor the side to move.
 pub fn evaluate(state: &GameState) -> i32 {
-    let white_eval = evaluate_color(state, Color::White);
-    let black_eval = evaluate_color(state, Color::Black);
-
-    let raw_eval = white_eval - black_eval;
+    let eval = evaluate_absolute(state);
 
     // Return from perspective of side to move
     match state.turn {
-        Color::White => raw_eval,
-        Color::Black => -raw_eval,
+        Color::White => eval,
+        Color::Black => -eval,
     }
 }
 
 /// Evaluates a position from White's perspective.
 /// Positive scores favor White, negative favor Black.
 pub fn evaluate_absolute(state: &GameState) -> i32 {
-    let white_eval = evaluate_color(state, Color::White);
-    let black_eval = evaluate_color(state, Color::Black);
+    evaluate_explained(state).total()
+}
 
-    white_eval - black_eval
+/// Evaluates a position from White's perspective, term by term.
+pub fn evaluate_explained(state: &GameState) -> EvalBreakdown {
+    let mut breakdown = terms_at_phase(state, phase(state));
+    breakdown.draw = draw_scaling(state, breakdown.total());
+    breakdown
 }
 
-/// Evaluates all factors for a single color.
-fn evaluate_color(state: &GameState, color: Color) -> i32 {
-    let mut score = 0;
+/// Game phase when every piece is on the board
+pub const MAX_PHASE: i32 = 24;
+
+/// Game phase from the pieces left, not counting pawns and kings:
+/// `MAX_PHASE` in the opening, falling to 0 with only kings and pawns.
+/// Minor pieces count 1, rooks 2 and queens 4; promotions cannot raise it
+/// past `MAX_PHASE`.
+pub fn phase(state: &GameState) -> i32 {
+    let count = |piece_type| {
+        [Color::White, Color::Black]
+            .iter()
+            .map(|&color| state.board.bitboards.pieces(piece_type, color).count() as i32)
+            .sum::<i32>()
+    };
+    let phase = count(PieceType::Knight)
+        + count(PieceType::Bishop)
+        + 2 * count(PieceType::Rook)
+        + 4 * count(PieceType::Queen);
+    phase.min(MAX_PHASE)
+}
+
+/// The evaluation from White's perspective as if every piece were still on
+/// the board, before draw scaling.
+pub fn middlegame_eval(state: &GameState) -> i32 {
+    terms_at_phase(state, MAX_PHASE).total()
+}
 
-    // Material evaluation
-    score += evaluate_material(state, color);
+/// The evaluation from White's perspective as if only kings and pawns were
+/// left, before draw scaling.
+pub fn endgame_eval(state: &GameState) -> i32 {
+    terms_at_phase(state, 0).total()
+}
 
-    // Positional evaluation
-    score += evaluate_position(state, color);
+/// The tapered evaluation from White's perspective, before draw scaling:
+/// material, piece-square and center terms each blended between their
+/// middlegame and endgame scores by `phase`, so none of them jumps as
+/// pieces come off.
+pub fn material_phase_scaled_eval(state: &GameState) -> i32 {
+    terms_at_phase(state, phase(state)).total()
+}
 
-    score
+fn terms_at_phase(state: &GameState, phase: i32) -> EvalBreakdown {
+    EvalBreakdown {
+        material: EvalTerm::tapered(
+            phase,
+            |color| evaluate_material(state, color),
+            |color| evaluate_endgame_material(state, color),
+        ),
+        piece_square: EvalTerm::tapered(
+            phase,
+            |color| evaluate_piece_positions(state, color, piece_square_value),
+            |color| evaluate_piece_positions(state, color, endgame_piece_square_value),
+        ),
+        // Central pieces matter less once the board opens up
+        center: EvalTerm::tapered(phase, |color| evaluate_center_control(state, color), |_| 0),
+        draw: EvalTerm::default(),
+    }
+}
+
+/// Evaluates a position from `color`'s perspective, regardless of whose turn it is.
+/// Positive scores favor `color`.
+pub fn evaluate_from(state: &GameState, color: Color) -> i32 {
+    match color {
+        Color::White => evaluate_absolute(state),
+        Color::Black => -evaluate_absolute(state),
+    }
+}
+
+/// How much of the stronger side's advantage is kept in a recognised draw
+const DRAWN_SCALE: i32 = 16;
+
+/// Cuts the stronger side's advantage to a small fraction in a recognised
+/// draw, so the search does not chase a win that cannot be forced. `raw` is
+/// the rest of the evaluation from White's perspective.
+fn draw_scaling(state: &GameState, raw: i32) -> EvalTerm {
+    let Some(strong) = wrong_bishop_side(state) else {
+        return EvalTerm::default();
+    };
+    let advantage = match strong {
+        Color::White => raw,
+        Color::Black => -raw,
+    };
+    let correction = advantage / DRAWN_SCALE - advantage;
+    EvalTerm::for_both(|color| if color == strong { correction } else { 0 })
+}
+
+/// The side that cannot win a wrong rook-pawn ending: king, one bishop and
+/// pawns all on the a- or h-file against a bare king, where the bishop never
+/// covers the promotion square and the defending king stands on or next to
+/// it. The defender cannot be driven out of that corner.
+fn wrong_bishop_side(state: &GameState) -> Option<Color> {
+    let bitboards = &state.board.bitboards;
+
+    for strong in [Color::White, Color::Black] {
+        let weak = strong.opponent();
+        let pawns = bitboards.pieces(PieceType::Pawn, strong);
+        let bishops = bitboards.pieces(PieceType::Bishop, strong);
+        let king = bitboards.pieces(PieceType::King, strong);
+        if bitboards.color_occupancy(weak).count() != 1
+            || bishops.count() != 1
+            || pawns.is_empty()
+            || bitboards.color_occupancy(strong) != pawns.union(bishops).union(king)
+        {
+            continue;
+        }
+
+        let rook_file = [File::new(0), File::new(7)]
+            .into_iter()
+            .flatten()
+            .find(|&file| pawns.intersection(BitBoard::file_mask(file)) == pawns);
+        let Some(file) = rook_file else {
+            continue;
+        };
+        let promotion_square = Square::new(file, strong.promotion_rank());
+        let bishop_square = bishops.lsb()?;
+        let defending_king = bitboards.pieces(PieceType::King, weak).lsb()?;
+        if bishop_square.color() != promotion_square.color()
+            && defending_king.distance(promotion_square) <= 1
+        {
+            return Some(strong);
+        }
+    }
+
+    None
 }
 
 /// Counts material value for a color.
@@ -70,17 +283,14 @@ fn evaluate_material(state: &GameState, color: Color) -> i32 {
     material
 }
 
-/// Evaluates positional factors for a color.
-fn evaluate_position(state: &GameState, color: Color) -> i32 {
-    let mut score = 0;
+/// Pawn value once the board empties and promotion comes closer
+const ENDGAME_PAWN_VALUE: i32 = 120;
 
-    // Center control bonus
-    score += evaluate_center_control(state, color);
-
-    // Piece-specific positional bonuses
-    score += evaluate_piece_positions(state, color);
-
-    score
+/// Counts endgame material value for a color.
+fn evaluate_endgame_material(state: &GameState, color: Color) -> i32 {
+    let pawns = state.board.bitboards.pieces(PieceType::Pawn, color).count() as i32;
+    let pawn_value = i32::from(PieceType::Pawn.value());
+    evaluate_material(state, color) + pawns * (ENDGAME_PAWN_VALUE - pawn_value)
 }
 
 /// Awards bonuses for controlling central squares.
@@ -128,15 +338,19 @@ fn evaluate_center_control(state: &GameState, color: Color) -> i32 {
     score
 }
 
-/// Piece-square tables for positional evaluation.
-fn evaluate_piece_positions(state: &GameState, color: Color) -> i32 {
+/// Piece-square tables for positional evaluation, read through `value`.
+fn evaluate_piece_positions(
+    state: &GameState,
+    color: Color,
+    value: fn(PieceType, Square, Color) -> i32,
+) -> i32 {
     let mut score = 0;
 
     for i in 0..64 {
         if let Some(square) = Square::from_index(i) {
             if let Some(piece) = state.board.piece_at(square) {
                 if piece.color == color {
-                    score += piece_square_value(piece.piece_type, square, color);
+                    score += value(piece.piece_type, square, color);
                 }
             }
         }
@@ -147,15 +361,11 @@ fn evaluate_piece_positions(state: &GameState, color: Color) -> i32 {
 
 /// Returns positional value for a piece on a given square.
 fn piece_square_value(piece_type: PieceType, square: Square, color: Color) -> i32 {
-    let rank = square.rank().index();
+    // Tables are from White's side; Black reads them mirrored
+    let square = square.mirror_for(color);
+    let rank_idx = square.rank().index();
     let file = square.file().index();
 
-    // Mirror the rank for black pieces
-    let rank_idx = match color {
-        Color::White => rank,
-        Color::Black => 7 - rank,
-    };
-
     match piece_type {
         PieceType::Pawn => PAWN_TABLE[rank_idx as usize][file as usize],
         PieceType::Knight => KNIGHT_TABLE[rank_idx as usize][file as usize],
@@ -164,12 +374,25 @@ fn piece_square_value(piece_type: PieceType, square: Square, color: Color) -> i3
         PieceType::Queen => QUEEN_TABLE[rank_idx as usize][file as usize],
         PieceType::King => {
             // Simple king safety: prefer corners in middlegame
-            // TODO: Separate endgame king table
             KING_TABLE[rank_idx as usize][file as usize]
         }
     }
 }
 
+/// Returns the endgame positional value for a piece on a given square:
+/// pawns are pushed toward promotion and the king toward the center.
+fn endgame_piece_square_value(piece_type: PieceType, square: Square, color: Color) -> i32 {
+    let relative = square.mirror_for(color);
+    let rank_idx = relative.rank().index() as usize;
+    let file = relative.file().index() as usize;
+
+    match piece_type {
+        PieceType::Pawn => PAWN_ENDGAME_RANKS[rank_idx],
+        PieceType::King => KING_ENDGAME_TABLE[rank_idx][file],
+        _ => piece_square_value(piece_type, square, color),
+    }
+}
+
 // Piece-square tables (from White's perspective, rank 0 = 1st rank)
 // Values are in centipawns
 
@@ -239,10 +462,25 @@ const KING_TABLE: [[i32; 8]; 8] = [
     [20, 30, 10, 0, 0, 10, 30, 20],
 ];
 
+// Endgame pawn bonus by rank, 1st to 8th from the pawn's own side
+const PAWN_ENDGAME_RANKS: [i32; 8] = [0, 0, 5, 10, 20, 35, 60, 0];
+
+// Symmetric top to bottom, so it reads the same from either side
+const KING_ENDGAME_TABLE: [[i32; 8]; 8] = [
+    [-50, -40, -30, -20, -20, -30, -40, -50],
+    [-30, -20, -10, 0, 0, -10, -20, -30],
+    [-30, -10, 20, 30, 30, 20, -10, -30],
+    [-30, -10, 30, 40, 40, 30, -10, -30],
+    [-30, -10, 30, 40, 40, 30, -10, -30],
+    [-30, -10, 20, 30, 30, 20, -10, -30],
+    [-30, -20, -10, 0, 0, -10, -20, -30],
+    [-50, -40, -30, -20, -20, -30, -40, -50],
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chess_core::{File, Piece, Rank, Square};
+    use chess_core::{CastlingRights, File, Piece, PositionBuilder, Rank, Square};
 
     #[test]
     fn test_material_count() {
@@ -275,6 +513,24 @@ mod tests {
         assert!(eval.abs() < 50, "Starting position eval: {}", eval);
     }
 
+    #[test]
+    fn test_evaluate_from_is_antisymmetric() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ];
+
+        for fen in fens {
+            let state = GameState::from_fen(fen).unwrap();
+            let white = state.evaluate_from(Color::White);
+            assert_eq!(white, -state.evaluate_from(Color::Black), "{fen}");
+            assert_eq!(white, state.evaluate_absolute(), "{fen}");
+            assert_eq!(state.evaluate_from(state.turn), state.evaluate(), "{fen}");
+        }
+    }
+
     #[test]
     fn test_perspective_evaluation() {
         let state = GameState::new();
@@ -376,4 +632,150 @@ mod tests {
         let eval = evaluate_absolute(&state);
         assert!(eval > 800, "K+Q vs K eval: {}", eval);
     }
+
+    /// The position with the board flipped top to bottom and the colors
+    /// swapped, so White's pieces become Black's mirrored ones.
+    fn color_flipped(state: &GameState) -> GameState {
+        let mut builder = PositionBuilder::new()
+            .turn(state.turn.opponent())
+            .castling(CastlingRights {
+                white: state.castling.black,
+                black: state.castling.white,
+            })
+            .en_passant(state.en_passant.map(|sq| sq.mirror_for(Color::Black)));
+        for index in 0..64 {
+            let square = Square::from_index(index).unwrap();
+            if let Some(piece) = state.board.piece_at(square) {
+                builder = builder.place_at(
+                    Piece::new(piece.piece_type, piece.color.opponent()),
+                    square.mirror_for(Color::Black),
+                );
+            }
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_evaluation_is_color_symmetric() {
+        for fen in [
+            chess_core::positions::STARTING,
+            chess_core::positions::KIWIPETE,
+            // Italian game
+            "r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+            // Unbalanced middlegame with castled kings and passed pawns
+            "2r2rk1/pp3ppp/2n1b3/3p4/3P4/2PB1N2/P4PPP/R4RK1 b - - 0 18",
+            "8/5k2/3p4/1p1Pp2p/pP2Pp1P/P4P1K/8/8 b - - 99 50",
+        ] {
+            let state = GameState::from_fen(fen).unwrap();
+            let mirror = color_flipped(&state);
+            assert_eq!(
+                evaluate_absolute(&state),
+                -evaluate_absolute(&mirror),
+                "{fen} vs {}",
+                mirror.to_fen()
+            );
+            assert_eq!(evaluate(&state), evaluate(&mirror), "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_phase() {
+        assert_eq!(phase(&GameState::new()), MAX_PHASE);
+        assert_eq!(
+            phase(&GameState::from_fen("4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1").unwrap()),
+            0
+        );
+        // Extra queens from promotion do not push past the opening phase
+        let promoted =
+            GameState::from_fen("QQQQkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1");
+        assert_eq!(phase(&promoted.unwrap()), MAX_PHASE);
+    }
+
+    #[test]
+    fn test_eval_tapers_as_material_comes_off() {
+        // Mirrored armies cancel out, leaving only the kings: White's is
+        // central, which the endgame rewards and the middlegame does not
+        let mut state =
+            GameState::from_fen("r1bq1bkr/pppppppp/8/8/4K3/8/PPPPPPPP/R1BQ1B1R w - - 0 1").unwrap();
+        let (middlegame, endgame) = (middlegame_eval(&state), endgame_eval(&state));
+        assert!(endgame > middlegame + 50, "{middlegame} vs {endgame}");
+
+        let mut previous = (phase(&state), evaluate_absolute(&state));
+        for square in ["d1", "c1", "f1", "a1", "h1"] {
+            let white = square.parse::<Square>().unwrap();
+            state = state.with_squares_cleared(&[white, white.mirror_for(Color::Black)]);
+            let current = (phase(&state), evaluate_absolute(&state));
+
+            // Both kings' terms blend linearly, so each step moves the eval
+            // toward the endgame score by the phase it dropped
+            let step = (endgame - middlegame) * (previous.0 - current.0) / MAX_PHASE;
+            assert!(current.0 < previous.0);
+            assert!(current.1 >= previous.1, "{previous:?} -> {current:?}");
+            assert!(
+                (current.1 - previous.1 - step).abs() <= 2,
+                "{previous:?} -> {current:?}"
+            );
+            previous = current;
+        }
+        assert_eq!(previous, (0, endgame));
+    }
+
+    #[test]
+    fn test_wrong_bishop_rook_pawn_is_drawish() {
+        // The light-squared bishop never covers h8, where Black's king sits
+        let drawn = GameState::from_fen("7k/8/8/8/7P/8/8/3BK3 w - - 0 1").unwrap();
+        assert_eq!(wrong_bishop_side(&drawn), Some(Color::White));
+        assert!(
+            evaluate_absolute(&drawn).abs() < 50,
+            "{}",
+            evaluate_absolute(&drawn)
+        );
+        let breakdown = drawn.evaluate_explained();
+        assert!(breakdown.material.net() > 400);
+        assert_eq!(breakdown.total(), evaluate_absolute(&drawn));
+
+        // Black's version of the same draw. Flipping mirrors ranks only, so
+        // the pawn stays on the h-file and White's king sits on h1, a light
+        // square the now dark-squared bishop never covers
+        let flipped = color_flipped(&drawn);
+        assert_eq!(wrong_bishop_side(&flipped), Some(Color::Black));
+        assert_eq!(evaluate_absolute(&flipped), -evaluate_absolute(&drawn));
+
+        // The dark-squared bishop covers h8, and a king far from the corner
+        // can be kept out: both keep their winning scores
+        for fen in [
+            "7k/8/8/8/7P/8/8/2B1K3 w - - 0 1",
+            "k7/8/8/8/7P/8/8/3BK3 w - - 0 1",
+            "7k/8/8/8/6PP/8/8/3BK3 w - - 0 1",
+        ] {
+            let state = GameState::from_fen(fen).unwrap();
+            assert_eq!(wrong_bishop_side(&state), None, "{fen}");
+            assert!(evaluate_absolute(&state) > 300, "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_breakdown_sums_to_evaluation() {
+        for fen in [
+            chess_core::positions::STARTING,
+            chess_core::positions::KIWIPETE,
+            "r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+            "4k3/8/8/8/8/8/4Q3/4K3 w - - 0 1",
+        ] {
+            let state = GameState::from_fen(fen).unwrap();
+            let breakdown = state.evaluate_explained();
+            assert_eq!(breakdown.total(), evaluate_absolute(&state), "{fen}");
+            assert_eq!(
+                breakdown.material.white,
+                evaluate_material(&state, Color::White)
+            );
+        }
+
+        // The starting position is balanced term by term
+        let breakdown = GameState::new().evaluate_explained();
+        for (name, term) in breakdown.terms() {
+            assert_eq!(term.net(), 0, "{name}");
+        }
+        assert_eq!(breakdown.material.white, 4000);
+    }
 }
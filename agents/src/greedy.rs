@@ -0,0 +1,99 @@
+use crate::evaluation::evaluate_from;
+use crate::Agent;
+use chess_core::{generate_legal_moves, GameState, Move, PieceType};
+
+/// Plays the move that wins the most material right now, ignoring any reply.
+///
+/// Ties, including every quiet move, are broken by the static evaluation of
+/// the resulting position and then by move generation order, so the agent
+/// is deterministic. Useful as a fast, weak baseline opponent.
+pub struct GreedyAgent {
+    name: String,
+}
+
+impl GreedyAgent {
+    pub fn new() -> Self {
+        GreedyAgent {
+            name: "Greedy".to_string(),
+        }
+    }
+}
+
+impl Default for GreedyAgent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Material gained by `mv` before the opponent replies, in centipawns.
+fn material_gain(state: &GameState, mv: Move) -> i32 {
+    let captured = match state.board.piece_at(mv.to) {
+        Some(piece) => piece.piece_type.value(),
+        None => {
+            let is_pawn = state
+                .board
+                .piece_at(mv.from)
+                .is_some_and(|piece| piece.piece_type == PieceType::Pawn);
+            if is_pawn && Some(mv.to) == state.en_passant {
+                PieceType::Pawn.value()
+            } else {
+                0
+            }
+        }
+    };
+    let promotion = mv
+        .promotion
+        .map_or(0, |piece| piece.value() - PieceType::Pawn.value());
+
+    i32::from(captured) + i32::from(promotion)
+}
+
+impl Agent for GreedyAgent {
+    fn best_move(&mut self, state: &GameState) -> Option<Move> {
+        let mut best: Option<(Move, i32, i32)> = None;
+
+        for &mv in generate_legal_moves(state).iter() {
+            let gain = material_gain(state, mv);
+            let eval = evaluate_from(&state.apply_move(mv), state.turn);
+
+            let better = match best {
+                Some((_, best_gain, best_eval)) => (gain, eval) > (best_gain, best_eval),
+                None => true,
+            };
+            if better {
+                best = Some((mv, gain, eval));
+            }
+        }
+
+        best.map(|(mv, _, _)| mv)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess_core::{File, Rank, Square};
+
+    fn sq(file: u8, rank: u8) -> Square {
+        Square::new(File::new(file).unwrap(), Rank::new(rank).unwrap())
+    }
+
+    #[test]
+    fn test_captures_hanging_queen() {
+        // The black queen on d5 is attacked by the knight on c3; a pawn on a7
+        // is also en prise to the rook, but the queen is worth more
+        let state = GameState::from_fen("4k3/p7/8/3q4/8/2N5/8/R3K3 w - - 0 1").expect("valid FEN");
+        let mv = GreedyAgent::new().best_move(&state).unwrap();
+        assert_eq!(mv, Move::new(sq(2, 2), sq(3, 4)));
+    }
+
+    #[test]
+    fn test_no_moves_returns_none() {
+        let state = GameState::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").expect("valid FEN");
+        assert_eq!(GreedyAgent::new().best_move(&state), None);
+    }
+}
@@ -1,4 +1,6 @@
 pub mod evaluation;
+pub mod greedy;
+pub mod mate;
 pub mod minimax;
 pub mod random;
 pub mod search;
@@ -23,6 +25,8 @@ pub trait Position {
 }
 
 pub use evaluation::*;
+pub use greedy::GreedyAgent;
+pub use mate::{find_mate, MateSearch};
 pub use minimax::MinimaxAgent;
 pub use random::RandomAgent;
 pub use search::*;
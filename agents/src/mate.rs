@@ -0,0 +1,119 @@
+use chess_core::{generate_legal_moves, GameState, Move};
+
+const MATE_SCORE: i32 = 30_000;
+
+/// Extension trait to add forced-mate detection to GameState
+pub trait MateSearch {
+    fn find_mate(&self, max_moves: u8) -> Option<Vec<Move>>;
+}
+
+impl MateSearch for GameState {
+    fn find_mate(&self, max_moves: u8) -> Option<Vec<Move>> {
+        find_mate(self, max_moves)
+    }
+}
+
+/// Finds a forced mate for the side to move in at most `max_moves` moves.
+/// Returns the mating line (attacker and defender moves, ending in mate),
+/// or `None` if no forced mate exists within the bound.
+pub fn find_mate(state: &GameState, max_moves: u8) -> Option<Vec<Move>> {
+    // Try the shortest mates first so the returned line is the fastest one
+    for moves in 1..=u32::from(max_moves) {
+        let depth = moves * 2 - 1;
+        let (score, line) = mate_search(state, depth, 0, -MATE_SCORE, MATE_SCORE);
+        if score > 0 {
+            return Some(line);
+        }
+    }
+
+    None
+}
+
+/// Alpha-beta search that only scores mates; every other leaf is 0.
+/// Mate scores are adjusted by ply so shorter mates are preferred.
+fn mate_search(
+    state: &GameState,
+    depth: u32,
+    ply: i32,
+    mut alpha: i32,
+    mut beta: i32,
+) -> (i32, Vec<Move>) {
+    // Mate-distance pruning: no line from here can beat a mate already found
+    alpha = alpha.max(-MATE_SCORE + ply);
+    beta = beta.min(MATE_SCORE - ply - 1);
+    if alpha >= beta {
+        return (alpha, vec![]);
+    }
+
+    let moves = generate_legal_moves(state);
+    if moves.is_empty() {
+        if state.is_in_check() {
+            return (-MATE_SCORE + ply, vec![]);
+        }
+        return (0, vec![]);
+    }
+
+    if depth == 0 {
+        return (0, vec![]);
+    }
+
+    let mut best_score = -MATE_SCORE - 1;
+    let mut best_line = vec![];
+
+    for &mv in moves.iter() {
+        let new_state = state.apply_move(mv);
+        let (score, mut line) = mate_search(&new_state, depth - 1, ply + 1, -beta, -alpha);
+        let score = -score;
+
+        if score > best_score {
+            best_score = score;
+            best_line = vec![mv];
+            best_line.append(&mut line);
+        }
+
+        if score > alpha {
+            alpha = score;
+        }
+
+        if alpha >= beta {
+            break;
+        }
+    }
+
+    (best_score, best_line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess_core::is_checkmate;
+
+    fn play_line(state: &GameState, line: &[Move]) -> GameState {
+        line.iter().fold(state.clone(), |s, &mv| s.apply_move(mv))
+    }
+
+    #[test]
+    fn test_mate_in_one() {
+        let state = GameState::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let line = state.find_mate(1).expect("mate in one");
+
+        assert_eq!(line.len(), 1);
+        assert!(is_checkmate(&play_line(&state, &line)));
+    }
+
+    #[test]
+    fn test_mate_in_two() {
+        let state = GameState::from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        assert!(state.find_mate(1).is_none());
+
+        let line = state.find_mate(2).expect("mate in two");
+        assert_eq!(line.len(), 3);
+        assert!(is_checkmate(&play_line(&state, &line)));
+    }
+
+    #[test]
+    fn test_no_mate() {
+        let state = GameState::new();
+        assert!(find_mate(&state, 2).is_none());
+    }
+}
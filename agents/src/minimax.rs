@@ -1,35 +1,91 @@
 use crate::{
-    search::{search_with_limits, SearchLimits},
+    search::{root_move_scores, search_with_limits, SearchLimits},
     Agent,
 };
-use chess_core::{GameState, Move};
+use chess_core::{Color, GameState, Move};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+/// Depth used to score root moves when picking a random opening move
+const OPENING_SCORE_DEPTH: u8 = 4;
 
 pub struct MinimaxAgent {
     name: String,
     depth: u8,
     time_limit_ms: Option<u64>,
+    opening: Option<OpeningRandomness>,
+}
+
+/// Random choice among near-best moves for the first plies of a game
+struct OpeningRandomness {
+    plies: u32,
+    margin: i32,
+    rng: StdRng,
 }
 
 impl MinimaxAgent {
     pub fn new(depth: u8) -> Self {
+        Self::with_depth(depth)
+    }
+
+    /// Searches every move to exactly `depth` plies, so the agent plays the
+    /// same way on any machine.
+    pub fn with_depth(depth: u8) -> Self {
         MinimaxAgent {
-            name: format!("Minimax(depth={})", depth),
+            name: format!("Minimax(depth {})", depth),
             depth,
             time_limit_ms: None,
+            opening: None,
         }
     }
 
     pub fn with_time_limit(time_ms: u64) -> Self {
         MinimaxAgent {
-            name: format!("Minimax(time={}ms)", time_ms),
+            name: format!("Minimax({}ms)", time_ms),
             depth: 99, // Will be limited by time
             time_limit_ms: Some(time_ms),
+            opening: None,
+        }
+    }
+
+    /// For the first `plies` plies of the game, play a random move among
+    /// those scoring within `margin` centipawns of the best. The choice is
+    /// reproducible for a given `seed`.
+    pub fn with_opening_randomness(mut self, plies: u32, margin: i32, seed: u64) -> Self {
+        self.opening = Some(OpeningRandomness {
+            plies,
+            margin,
+            rng: StdRng::seed_from_u64(seed),
+        });
+        self
+    }
+
+    fn random_opening_move(&mut self, state: &GameState) -> Option<Move> {
+        let opening = self.opening.as_mut()?;
+        let ply = u32::from(state.fullmove_number).saturating_sub(1) * 2
+            + u32::from(state.turn == Color::Black);
+        if ply >= opening.plies {
+            return None;
         }
+
+        let scores = root_move_scores(state, self.depth.min(OPENING_SCORE_DEPTH));
+        let best = scores.iter().map(|&(_, score)| score).max()?;
+        let candidates: Vec<Move> = scores
+            .iter()
+            .filter(|&&(_, score)| score >= best - opening.margin)
+            .map(|&(mv, _)| mv)
+            .collect();
+        candidates.choose(&mut opening.rng).copied()
     }
 }
 
 impl Agent for MinimaxAgent {
     fn best_move(&mut self, state: &GameState) -> Option<Move> {
+        if let Some(mv) = self.random_opening_move(state) {
+            return Some(mv);
+        }
+
         let limits = if let Some(time_ms) = self.time_limit_ms {
             SearchLimits::move_time(time_ms)
         } else {
@@ -44,3 +100,48 @@ impl Agent for MinimaxAgent {
         &self.name
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_move(agent: &mut MinimaxAgent) -> Move {
+        agent.best_move(&GameState::new()).unwrap()
+    }
+
+    #[test]
+    fn test_opening_randomness_varies_with_seed() {
+        let moves: Vec<Move> = (0..8)
+            .map(|seed| first_move(&mut MinimaxAgent::new(2).with_opening_randomness(4, 30, seed)))
+            .collect();
+        assert!(moves.iter().any(|&mv| mv != moves[0]), "{moves:?}");
+
+        // Same seed, same choice
+        let again = first_move(&mut MinimaxAgent::new(2).with_opening_randomness(4, 30, 0));
+        assert_eq!(again, moves[0]);
+    }
+
+    #[test]
+    fn test_without_randomness_is_deterministic() {
+        let expected = first_move(&mut MinimaxAgent::new(2));
+        for _ in 0..3 {
+            assert_eq!(first_move(&mut MinimaxAgent::new(2)), expected);
+        }
+    }
+
+    #[test]
+    fn test_depth_mode_is_deterministic_and_named() {
+        let state = GameState::from_fen(chess_core::positions::KIWIPETE).unwrap();
+        let expected = MinimaxAgent::with_depth(4).best_move(&state);
+        assert!(expected.is_some());
+        for _ in 0..2 {
+            assert_eq!(MinimaxAgent::with_depth(4).best_move(&state), expected);
+        }
+
+        assert_eq!(MinimaxAgent::with_depth(4).name(), "Minimax(depth 4)");
+        assert_eq!(
+            MinimaxAgent::with_time_limit(2000).name(),
+            "Minimax(2000ms)"
+        );
+    }
+}
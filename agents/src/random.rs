@@ -29,7 +29,7 @@ impl Agent for RandomAgent {
             None
         } else {
             let mut rng = thread_rng();
-            let move_vec: Vec<Move> = moves.iter().copied().collect();
+            let move_vec = Vec::from(&moves);
             move_vec.choose(&mut rng).copied()
         }
     }
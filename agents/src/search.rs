@@ -1,22 +1,101 @@
-use crate::evaluation::Evaluatable;
+use crate::evaluation::{Evaluator, HandCraftedEvaluator};
 use crate::transposition::{NodeType, TranspositionTable};
-use chess_core::{generate_legal_moves, GameState, Move};
+use chess_core::{generate_legal_moves, Color, GameState, Move, PieceType};
+use std::cmp::Reverse;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::{Duration, Instant};
 
 const INFINITY: i32 = 1_000_000;
-const CHECKMATE_SCORE: i32 = 100_000;
+pub(crate) const CHECKMATE_SCORE: i32 = 100_000;
 const TIME_CHECK_INTERVAL: u64 = 1000; // Check time every 1000 nodes
 const QUIESCENCE_DEPTH: i8 = 4; // Maximum depth for quiescence search
+const DELTA_MARGIN: i32 = 200; // Safety margin for quiescence delta pruning
+const ENDGAME_MATERIAL: i32 = 1300; // Non-pawn material at or below which delta pruning is off
+const MAX_CHECK_EXTENSIONS: u8 = 4; // Check extensions allowed along one line
+const MAX_PV_LENGTH: usize = 64; // Longest line reported by `complete_pv`
+
+/// History score at which the whole table is halved. Well below
+/// `u32::MAX`, so no entry saturates and every one keeps its rank.
+const HISTORY_MAX: u32 = 1 << 20;
+
+/// History heuristic scores indexed by from and to square.
+///
+/// Credit from earlier iterations is halved before each new one (`age`),
+/// and the whole table is halved whenever an entry passes `HISTORY_MAX`, so
+/// recent cutoffs weigh more than stale ones and scores never overflow.
+struct History {
+    scores: Box<[[u32; 64]; 64]>,
+}
+
+impl History {
+    fn new() -> Self {
+        Self {
+            scores: Box::new([[0; 64]; 64]),
+        }
+    }
+
+    fn score(&self, mv: Move) -> u32 {
+        self.scores[mv.from.index() as usize][mv.to.index() as usize]
+    }
+
+    /// Credits a quiet move that caused a cutoff at `depth`, weighted by
+    /// depth squared.
+    fn record_cutoff(&mut self, mv: Move, depth: u8) {
+        let entry = &mut self.scores[mv.from.index() as usize][mv.to.index() as usize];
+        *entry += u32::from(depth) * u32::from(depth);
+        if *entry > HISTORY_MAX {
+            self.age();
+        }
+    }
+
+    /// Halves every entry, keeping their order.
+    fn age(&mut self) {
+        for score in self.scores.iter_mut().flatten() {
+            *score /= 2;
+        }
+    }
+}
+
+/// Transposition table size used when the caller doesn't pick one.
+pub const DEFAULT_TT_SIZE_MB: usize = 16;
 
 #[derive(Debug, Clone)]
 pub struct SearchResult {
     pub best_move: Option<Move>,
     pub score: i32,
+    /// Principal variation from the deepest completed iteration, starting
+    /// with `best_move`
+    pub pv: Vec<Move>,
     pub depth: u8,
     pub nodes: u64,
     pub stopped: bool,
+    pub stats: SearchStats,
+}
+
+/// Counters for checking that move ordering and windowing behave, summed
+/// over every iteration of a search. The root counters only move when the
+/// root window is narrowed, as by `search_window`; there are no aspiration
+/// windows yet.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchStats {
+    /// Interior nodes that failed high
+    pub beta_cutoffs: u64,
+    /// Of those, nodes where the first move searched caused the cutoff
+    pub first_move_cutoffs: u64,
+    /// Root searches cut short because a move reached the root beta
+    pub root_cutoffs: u64,
+    /// Root searches whose best score stayed at or below the root alpha
+    pub root_fail_lows: u64,
+}
+
+impl SearchStats {
+    /// Share of beta cutoffs made by the first move, or `None` before any
+    /// cutoff. Good ordering keeps this high, above 0.8 or so.
+    pub fn first_move_cutoff_rate(&self) -> Option<f64> {
+        (self.beta_cutoffs > 0).then(|| self.first_move_cutoffs as f64 / self.beta_cutoffs as f64)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -26,10 +105,26 @@ pub struct SearchProgress {
     pub nodes: u64,
     pub pv: Vec<Move>,
     pub time_ms: u64,
+    /// Transposition table fill in permille
+    pub hashfull: u32,
 }
 
 pub type InfoCallback = Box<dyn Fn(&SearchProgress) + Send>;
 
+/// Slot holding the best move found so far, for `search_with_shared_best`.
+pub type SharedBestMove = Arc<Mutex<Option<Move>>>;
+
+/// The root move about to be searched, for UCI `currmove` reporting.
+#[derive(Debug, Clone)]
+pub struct RootMoveProgress {
+    pub depth: u8,
+    pub currmove: Move,
+    /// 1-based position of `currmove` in the root move ordering
+    pub currmovenumber: usize,
+}
+
+pub type CurrMoveCallback = Box<dyn Fn(&RootMoveProgress) + Send>;
+
 #[derive(Debug, Clone)]
 pub struct SearchLimits {
     pub max_depth: Option<u8>,
@@ -40,6 +135,9 @@ pub struct SearchLimits {
     pub white_increment: Option<Duration>,
     pub black_increment: Option<Duration>,
     pub moves_to_go: Option<u32>,
+    /// Minimum time between progress callbacks. Depths that finish sooner
+    /// are skipped, but the last completed depth is always reported.
+    pub info_interval: Option<Duration>,
 }
 
 impl SearchLimits {
@@ -53,6 +151,7 @@ impl SearchLimits {
             white_increment: None,
             black_increment: None,
             moves_to_go: None,
+            info_interval: None,
         }
     }
 
@@ -66,6 +165,36 @@ impl SearchLimits {
             white_increment: None,
             black_increment: None,
             moves_to_go: None,
+            info_interval: None,
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        Self {
+            max_depth: None,
+            move_time: None,
+            nodes: Some(nodes),
+            white_time: None,
+            black_time: None,
+            white_increment: None,
+            black_increment: None,
+            moves_to_go: None,
+            info_interval: None,
+        }
+    }
+
+    /// No limits at all: deepen until stopped through the stop flag.
+    pub fn infinite() -> Self {
+        Self {
+            max_depth: None,
+            move_time: None,
+            nodes: None,
+            white_time: None,
+            black_time: None,
+            white_increment: None,
+            black_increment: None,
+            moves_to_go: None,
+            info_interval: None,
         }
     }
 
@@ -85,6 +214,42 @@ impl SearchLimits {
             white_increment: Some(white_inc),
             black_increment: Some(black_inc),
             moves_to_go,
+            info_interval: None,
+        }
+    }
+}
+
+/// Tunable search parameters for `search_with_options`.
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub tt_size_mb: usize,
+    /// Resolve captures at the horizon. When off, leaves return the raw
+    /// static eval, which isolates evaluation bugs from search effects.
+    pub quiescence: bool,
+    /// Plies of captures searched past the horizon. 0 still stands pat
+    /// and scores mate or stalemate, unlike turning `quiescence` off.
+    pub quiescence_depth: i8,
+    /// Margin for quiescence delta pruning; `None` disables it.
+    pub delta_margin: Option<i32>,
+    /// Number of quiescence plies, counted from the horizon, that also try
+    /// quiet checking moves. 0 searches captures and promotions only.
+    pub quiescence_checks: u8,
+    /// Search moves that give check one ply deeper.
+    pub check_extensions: bool,
+    /// Static evaluation used at the leaves and for standing pat.
+    pub evaluator: Arc<dyn Evaluator>,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            tt_size_mb: DEFAULT_TT_SIZE_MB,
+            quiescence: true,
+            quiescence_depth: QUIESCENCE_DEPTH,
+            delta_margin: Some(DELTA_MARGIN),
+            quiescence_checks: 0,
+            check_extensions: true,
+            evaluator: Arc::new(HandCraftedEvaluator),
         }
     }
 }
@@ -93,28 +258,29 @@ struct SearchInfo {
     start_time: Instant,
     limits: SearchLimits,
     nodes: u64,
+    stats: SearchStats,
+    // Quiet moves that caused cutoffs, by from and to square, weighted by depth
+    history: History,
     stopped: bool,
     stop_flag: Arc<AtomicBool>,
     info_callback: Option<InfoCallback>,
+    currmove_callback: Option<CurrMoveCallback>,
+    shared_best: Option<SharedBestMove>,
     tt: Arc<TranspositionTable>,
+    quiescence: bool,
     quiescence_depth: i8,
+    delta_margin: Option<i32>,
+    quiescence_checks: u8,
+    check_extensions: bool,
+    evaluator: Arc<dyn Evaluator>,
 }
 
 impl SearchInfo {
     fn new(limits: SearchLimits, tt: Arc<TranspositionTable>) -> Self {
-        Self {
-            start_time: Instant::now(),
-            limits,
-            nodes: 0,
-            stopped: false,
-            stop_flag: Arc::new(AtomicBool::new(false)),
-            info_callback: None,
-            tt,
-            quiescence_depth: QUIESCENCE_DEPTH,
-        }
+        Self::new_with_stop_flag(limits, tt, Arc::new(AtomicBool::new(false)))
     }
 
-    #[allow(dead_code)]
+    /// The one constructor that lists every field; the others build on it.
     fn new_with_stop_flag(
         limits: SearchLimits,
         tt: Arc<TranspositionTable>,
@@ -124,11 +290,20 @@ impl SearchInfo {
             start_time: Instant::now(),
             limits,
             nodes: 0,
+            stats: SearchStats::default(),
+            history: History::new(),
             stopped: false,
             stop_flag,
             info_callback: None,
+            currmove_callback: None,
+            shared_best: None,
             tt,
+            quiescence: true,
             quiescence_depth: QUIESCENCE_DEPTH,
+            delta_margin: Some(DELTA_MARGIN),
+            quiescence_checks: 0,
+            check_extensions: true,
+            evaluator: Arc::new(HandCraftedEvaluator),
         }
     }
 
@@ -138,14 +313,8 @@ impl SearchInfo {
         tt: Arc<TranspositionTable>,
     ) -> Self {
         Self {
-            start_time: Instant::now(),
-            limits,
-            nodes: 0,
-            stopped: false,
-            stop_flag: Arc::new(AtomicBool::new(false)),
             info_callback: Some(callback),
-            tt,
-            quiescence_depth: QUIESCENCE_DEPTH,
+            ..Self::new(limits, tt)
         }
     }
 
@@ -156,14 +325,21 @@ impl SearchInfo {
         stop_flag: Arc<AtomicBool>,
     ) -> Self {
         Self {
-            start_time: Instant::now(),
-            limits,
-            nodes: 0,
-            stopped: false,
-            stop_flag,
             info_callback: Some(callback),
-            tt,
-            quiescence_depth: QUIESCENCE_DEPTH,
+            ..Self::new_with_stop_flag(limits, tt, stop_flag)
+        }
+    }
+
+    /// Stores the best move of a completed iteration for external polling.
+    fn publish_best(&self, best_move: Option<Move>) {
+        if let Some(shared) = &self.shared_best {
+            *shared.lock().unwrap_or_else(|e| e.into_inner()) = best_move;
+        }
+    }
+
+    fn report(&self, progress: &SearchProgress) {
+        if let Some(ref callback) = self.info_callback {
+            callback(progress);
         }
     }
 
@@ -178,7 +354,7 @@ impl SearchInfo {
             return true;
         }
 
-        // Check node limit
+        // Check node limit on every node so node-limited searches are reproducible
         if let Some(max_nodes) = self.limits.nodes {
             if self.nodes >= max_nodes {
                 self.stopped = true;
@@ -204,6 +380,56 @@ pub fn search(state: &GameState, depth: u8) -> SearchResult {
     search_with_limits(state, SearchLimits::depth(depth))
 }
 
+/// Fixed-depth search with caller-supplied root bounds, for probing questions
+/// like "is this position better than +200?". The score is fail-soft: at or
+/// below `alpha` means the true score is at most that value (fail low), at or
+/// above `beta` means it is at least that value (fail high), and anything in
+/// between is exact.
+pub fn search_window(state: &GameState, depth: u8, alpha: i32, beta: i32) -> SearchResult {
+    let depth = depth.max(1);
+    let tt = Arc::new(TranspositionTable::new(DEFAULT_TT_SIZE_MB));
+    let mut info = SearchInfo::new(SearchLimits::depth(depth), tt);
+    let (score, best_move, pv) = alpha_beta_root(state, depth, alpha, beta, &mut info);
+
+    SearchResult {
+        best_move,
+        score,
+        pv,
+        depth,
+        nodes: info.nodes,
+        stopped: info.stopped,
+        stats: info.stats,
+    }
+}
+
+/// Scores every legal root move with a full-window search to `depth`,
+/// from the side to move's perspective. Unlike `search`, no root move is
+/// cut off, so the scores can be compared with each other.
+pub fn root_move_scores(state: &GameState, depth: u8) -> Vec<(Move, i32)> {
+    let depth = depth.max(1);
+    let tt = Arc::new(TranspositionTable::new(DEFAULT_TT_SIZE_MB));
+    let mut info = SearchInfo::new(SearchLimits::depth(depth), tt);
+    let root_hash = state.zobrist_hash();
+
+    generate_legal_moves(state)
+        .iter()
+        .map(|&mv| {
+            let (new_state, child_hash) = state.apply_move_with_hash(mv, root_hash);
+            let (child_depth, extensions) = extend_for_check(&info, &new_state, depth, 0);
+            let (score, _, _) = alpha_beta(
+                &new_state,
+                child_hash,
+                child_depth,
+                extensions,
+                -INFINITY,
+                INFINITY,
+                &mut info,
+            );
+            (mv, -score)
+        })
+        .collect()
+}
+
 pub fn search_with_tt_size(
     state: &GameState,
     limits: SearchLimits,
@@ -215,17 +441,55 @@ pub fn search_with_tt_size(
 }
 
 pub fn search_with_limits(state: &GameState, limits: SearchLimits) -> SearchResult {
-    let tt = Arc::new(TranspositionTable::new(16)); // 16 MB default
+    let tt = Arc::new(TranspositionTable::new(DEFAULT_TT_SIZE_MB));
     let mut info = SearchInfo::new(limits, tt);
     search_internal(state, &mut info)
 }
 
+/// Searches with `threads` workers sharing one transposition table (Lazy SMP).
+/// The first worker's result is returned once it finishes; helpers only
+/// contribute through the shared table and are stopped afterwards.
+pub fn search_parallel(
+    state: &GameState,
+    limits: SearchLimits,
+    tt_size_mb: usize,
+    threads: usize,
+) -> SearchResult {
+    let tt = Arc::new(TranspositionTable::new(tt_size_mb));
+    let stop_flag = Arc::new(AtomicBool::new(false));
+
+    let helpers: Vec<_> = (1..threads.max(1))
+        .map(|_| {
+            let state = state.clone();
+            let limits = limits.clone();
+            let tt = Arc::clone(&tt);
+            let stop_flag = Arc::clone(&stop_flag);
+            thread::spawn(move || {
+                let mut info = SearchInfo::new_with_stop_flag(limits, tt, stop_flag);
+                search_internal(&state, &mut info)
+            })
+        })
+        .collect();
+
+    let mut info = SearchInfo::new_with_stop_flag(limits, tt, Arc::clone(&stop_flag));
+    let mut result = search_internal(state, &mut info);
+
+    stop_flag.store(true, Ordering::Relaxed);
+    for helper in helpers {
+        if let Ok(helper_result) = helper.join() {
+            result.nodes += helper_result.nodes;
+        }
+    }
+
+    result
+}
+
 pub fn search_with_callback(
     state: &GameState,
     limits: SearchLimits,
     callback: InfoCallback,
 ) -> SearchResult {
-    let tt = Arc::new(TranspositionTable::new(16)); // 16 MB default
+    let tt = Arc::new(TranspositionTable::new(DEFAULT_TT_SIZE_MB));
     let mut info = SearchInfo::with_callback(limits, callback, tt);
     search_internal(state, &mut info)
 }
@@ -236,23 +500,93 @@ pub fn search_with_callback_and_stop(
     callback: InfoCallback,
     stop_flag: Arc<AtomicBool>,
 ) -> SearchResult {
-    let tt = Arc::new(TranspositionTable::new(16)); // 16 MB default
+    let tt = Arc::new(TranspositionTable::new(DEFAULT_TT_SIZE_MB));
     let mut info = SearchInfo::with_callback_and_stop_flag(limits, callback, tt, stop_flag);
     search_internal(state, &mut info)
 }
 
+/// Like `search_with_callback_and_stop`, additionally calling
+/// `currmove_callback` before each root move is searched.
+pub fn search_with_callbacks_and_stop(
+    state: &GameState,
+    limits: SearchLimits,
+    callback: InfoCallback,
+    currmove_callback: CurrMoveCallback,
+    stop_flag: Arc<AtomicBool>,
+) -> SearchResult {
+    let tt = Arc::new(TranspositionTable::new(DEFAULT_TT_SIZE_MB));
+    search_with_tt(state, limits, tt, callback, currmove_callback, stop_flag)
+}
+
+/// Like `search_with_callbacks_and_stop`, but on a caller-owned table, so
+/// entries from earlier searches of the same game are reused.
+pub fn search_with_tt(
+    state: &GameState,
+    limits: SearchLimits,
+    tt: Arc<TranspositionTable>,
+    callback: InfoCallback,
+    currmove_callback: CurrMoveCallback,
+    stop_flag: Arc<AtomicBool>,
+) -> SearchResult {
+    let mut info = SearchInfo::with_callback_and_stop_flag(limits, callback, tt, stop_flag);
+    info.currmove_callback = Some(currmove_callback);
+    search_internal(state, &mut info)
+}
+
+/// Like `search_with_limits`, but stores the best move of every completed
+/// iteration in `shared`, so another thread can poll it without an
+/// `InfoCallback`. The slot is cleared when the search starts and ends
+/// holding `SearchResult::best_move`.
+pub fn search_with_shared_best(
+    state: &GameState,
+    limits: SearchLimits,
+    shared: SharedBestMove,
+) -> SearchResult {
+    let tt = Arc::new(TranspositionTable::new(DEFAULT_TT_SIZE_MB));
+    let mut info = SearchInfo::new(limits, tt);
+    info.shared_best = Some(shared);
+    info.publish_best(None);
+    let result = search_internal(state, &mut info);
+    // Fixed-depth and single-move searches have no iterations to publish
+    info.publish_best(result.best_move);
+    result
+}
+
 pub fn search_with_options(
     state: &GameState,
     limits: SearchLimits,
-    tt_size_mb: usize,
-    quiescence_depth: i8,
+    options: SearchOptions,
 ) -> SearchResult {
-    let tt = Arc::new(TranspositionTable::new(tt_size_mb));
+    let tt = Arc::new(TranspositionTable::new(options.tt_size_mb));
     let mut info = SearchInfo::new(limits, tt);
-    info.quiescence_depth = quiescence_depth;
+    info.quiescence = options.quiescence;
+    info.quiescence_depth = options.quiescence_depth;
+    info.delta_margin = options.delta_margin;
+    info.quiescence_checks = options.quiescence_checks;
+    info.check_extensions = options.check_extensions;
+    info.evaluator = options.evaluator;
     search_internal(state, &mut info)
 }
 
+/// Converts a search score into the number of moves to mate from `state`.
+/// Positive values mean the side to move mates, negative that it gets mated;
+/// returns `None` for ordinary centipawn scores.
+pub fn mate_in(score: i32, state: &GameState) -> Option<i32> {
+    if score.abs() < CHECKMATE_SCORE / 2 {
+        return None;
+    }
+
+    // Mate scores are offset by the full move number of the mated position
+    let root_move = i32::from(state.fullmove_number);
+    let mated_move = CHECKMATE_SCORE - score.abs();
+    if score > 0 {
+        let white_bonus = i32::from(state.side_to_move() == chess_core::Color::White);
+        Some(mated_move - root_move + white_bonus)
+    } else {
+        Some(-(mated_move - root_move))
+    }
+}
+
 fn allocate_time(limits: &SearchLimits, state: &GameState) -> Option<Duration> {
     // If explicit move time is set, use it
     if let Some(move_time) = limits.move_time {
@@ -309,22 +643,43 @@ fn search_internal(state: &GameState, info: &mut SearchInfo) -> SearchResult {
             info.limits.move_time = Some(allocated_time);
         }
     }
+
+    // With a single legal move there is nothing to decide, and a depth 1
+    // search only supplies a score. Just timed searches take the shortcut;
+    // depth and node limits still search the position fully
+    if info.limits.move_time.is_some() && generate_legal_moves(state).len() == 1 {
+        let (score, best_move, pv) = alpha_beta_root(state, 1, -INFINITY, INFINITY, info);
+        return SearchResult {
+            best_move,
+            score,
+            pv,
+            depth: 1,
+            nodes: info.nodes,
+            stopped: info.stopped,
+            stats: info.stats,
+        };
+    }
+
     if let Some(max_depth) = info.limits.max_depth {
         // Fixed depth search
         let mut result = SearchResult {
             best_move: None,
             score: 0,
+            pv: Vec::new(),
             depth: max_depth,
             nodes: 0,
             stopped: false,
+            stats: SearchStats::default(),
         };
 
-        let (score, best_move, _) = alpha_beta_root(state, max_depth, -INFINITY, INFINITY, info);
+        let (score, best_move, pv) = alpha_beta_root(state, max_depth, -INFINITY, INFINITY, info);
 
         result.score = score;
         result.best_move = best_move;
+        result.pv = pv;
         result.nodes = info.nodes;
         result.stopped = info.stopped;
+        result.stats = info.stats;
         result
     } else {
         // Iterative deepening with time control
@@ -351,16 +706,41 @@ fn alpha_beta_root(
         return (0, None, vec![]);
     }
 
-    let mut moves_vec: Vec<Move> = moves.iter().copied().collect();
+    let mut moves_vec = Vec::from(&moves);
+
+    // Neither side can mate, so every move draws; play any of them at once
+    if state.is_insufficient_material() {
+        return (0, Some(moves_vec[0]), vec![moves_vec[0]]);
+    }
+
     order_moves(state, &mut moves_vec);
 
+    let root_alpha = alpha;
+    let root_hash = state.zobrist_hash();
     let mut best_move = None;
     let mut best_score = -INFINITY;
     let mut best_pv = vec![];
 
-    for mv in &moves_vec {
-        let new_state = state.apply_move(*mv);
-        let (score, _, mut pv) = alpha_beta(&new_state, depth - 1, -beta, -alpha, info);
+    for (index, mv) in moves_vec.iter().enumerate() {
+        if let Some(ref callback) = info.currmove_callback {
+            callback(&RootMoveProgress {
+                depth,
+                currmove: *mv,
+                currmovenumber: index + 1,
+            });
+        }
+
+        let (new_state, child_hash) = state.apply_move_with_hash(*mv, root_hash);
+        let (child_depth, extensions) = extend_for_check(info, &new_state, depth, 0);
+        let (score, _, mut pv) = alpha_beta(
+            &new_state,
+            child_hash,
+            child_depth,
+            extensions,
+            -beta,
+            -alpha,
+            info,
+        );
         let score = -score;
 
         if info.stopped {
@@ -379,16 +759,90 @@ fn alpha_beta_root(
         }
 
         if alpha >= beta {
+            info.stats.root_cutoffs += 1;
             break;
         }
     }
 
-    (best_score, best_move, best_pv)
+    if !info.stopped && best_score <= root_alpha {
+        info.stats.root_fail_lows += 1;
+    }
+
+    (best_score, best_move, complete_pv(state, best_pv, &info.tt))
+}
+
+/// Checks a searched principal variation move by move and extends it with
+/// `extract_pv`. Lines collected by `alpha_beta` stop short at table
+/// cutoffs, which only report the stored move.
+fn complete_pv(state: &GameState, pv: Vec<Move>, tt: &TranspositionTable) -> Vec<Move> {
+    let mut line = Vec::new();
+    let mut position = state.clone();
+
+    for mv in pv {
+        if line.len() == MAX_PV_LENGTH || !is_legal(&position, mv) {
+            return line;
+        }
+        line.push(mv);
+        position = position.apply_move(mv);
+    }
+
+    let tail = extract_pv(&position, tt, MAX_PV_LENGTH - line.len());
+    line.extend(tail);
+    line
+}
+
+/// Follows stored best moves from `state` through the transposition table.
+/// The walk ends at the first missing or illegal move, after a repeated
+/// position, or at `max_len` moves.
+fn extract_pv(state: &GameState, tt: &TranspositionTable, max_len: usize) -> Vec<Move> {
+    let mut line = Vec::new();
+    let mut position = state.clone();
+    let mut seen = vec![position.zobrist_hash()];
+
+    while line.len() < max_len {
+        let Some(mv) = tt
+            .probe(position.zobrist_hash())
+            .and_then(|entry| entry.best_move)
+            .filter(|&mv| is_legal(&position, mv))
+        else {
+            break;
+        };
+
+        line.push(mv);
+        position = position.apply_move(mv);
+        let hash = position.zobrist_hash();
+        if seen.contains(&hash) {
+            break;
+        }
+        seen.push(hash);
+    }
+
+    line
+}
+
+fn is_legal(state: &GameState, mv: Move) -> bool {
+    generate_legal_moves(state).iter().any(|&legal| legal == mv)
 }
 
+/// Depth and extension count for a child node: a move that gives check is
+/// searched one ply deeper, at most `MAX_CHECK_EXTENSIONS` times per line.
+fn extend_for_check(info: &SearchInfo, child: &GameState, depth: u8, extensions: u8) -> (u8, u8) {
+    // The child's side to move being in check is exactly `gives_check`,
+    // without applying the move a second time
+    if info.check_extensions && extensions < MAX_CHECK_EXTENSIONS && child.is_in_check() {
+        (depth, extensions + 1)
+    } else {
+        (depth - 1, extensions)
+    }
+}
+
+/// Searches `state`, whose Zobrist hash is `hash`. Children's hashes are
+/// updated incrementally as moves are made rather than recomputed.
 fn alpha_beta(
     state: &GameState,
+    hash: u64,
     depth: u8,
+    extensions: u8,
     mut alpha: i32,
     beta: i32,
     info: &mut SearchInfo,
@@ -400,8 +854,14 @@ fn alpha_beta(
         return (0, None, vec![]);
     }
 
+    // Dead draw. Material only drops on captures, which reset the halfmove
+    // clock, so the (slow) material count is skipped everywhere else
+    if state.halfmove_clock == 0 && state.is_insufficient_material() {
+        return (0, None, vec![]);
+    }
+
     let original_alpha = alpha;
-    let hash = state.zobrist_hash();
+    debug_assert_eq!(hash, state.zobrist_hash());
     let mut tt_move = None;
 
     // Probe transposition table
@@ -410,14 +870,12 @@ fn alpha_beta(
             // Can we use the stored score?
             match entry.node_type {
                 NodeType::Exact => {
-                    // Exact score - we can return immediately
+                    // Exact score - we can return immediately. The line
+                    // stops at the stored move; `complete_pv` extends it
                     return (
                         entry.score,
                         entry.best_move,
-                        vec![entry.best_move.unwrap_or(Move::new(
-                            chess_core::Square::from_index(0).unwrap(),
-                            chess_core::Square::from_index(0).unwrap(),
-                        ))],
+                        entry.best_move.into_iter().collect(),
                     );
                 }
                 NodeType::LowerBound => {
@@ -442,10 +900,17 @@ fn alpha_beta(
         tt_move = entry.best_move;
     }
 
-    // Terminal node - enter quiescence search
+    // Terminal node - enter quiescence search, or take the raw eval without it
     if depth == 0 {
-        let score = quiescence(state, info.quiescence_depth, alpha, beta, info);
-        info.tt.store(hash, None, score, 0, NodeType::Exact);
+        let (score, node_type) = if info.quiescence {
+            // Quiescence scores outside the window are only bounds
+            let score = quiescence(state, info.quiescence_depth, alpha, beta, info);
+            (score, node_type_for(score, original_alpha, beta))
+        } else {
+            // This node was already counted on entry
+            (info.evaluator.eval(state), NodeType::Exact)
+        };
+        info.tt.store(hash, None, score, 0, node_type);
         return (score, None, vec![]);
     }
 
@@ -467,21 +932,30 @@ fn alpha_beta(
     }
 
     // Convert to vector for sorting
-    let mut moves_vec: Vec<Move> = moves.iter().copied().collect();
+    let mut moves_vec = Vec::from(&moves);
 
     // Order moves for better pruning (TT move first, then captures)
-    order_moves_with_tt(state, &mut moves_vec, tt_move);
+    order_moves_with_tt(state, &mut moves_vec, tt_move, Some(&info.history));
 
     let mut best_move = None;
     let mut best_score = -INFINITY;
     let mut best_pv = vec![];
 
-    for mv in &moves_vec {
+    for (index, mv) in moves_vec.iter().enumerate() {
         // Make move
-        let new_state = state.apply_move(*mv);
+        let (new_state, child_hash) = state.apply_move_with_hash(*mv, hash);
 
         // Recursive search with negamax
-        let (score, _, mut pv) = alpha_beta(&new_state, depth - 1, -beta, -alpha, info);
+        let (child_depth, extensions) = extend_for_check(info, &new_state, depth, extensions);
+        let (score, _, mut pv) = alpha_beta(
+            &new_state,
+            child_hash,
+            child_depth,
+            extensions,
+            -beta,
+            -alpha,
+            info,
+        );
         let score = -score;
 
         // If search was stopped, return current best
@@ -502,22 +976,34 @@ fn alpha_beta(
 
         // Beta cutoff
         if alpha >= beta {
+            info.stats.beta_cutoffs += 1;
+            if index == 0 {
+                info.stats.first_move_cutoffs += 1;
+            }
+            if tactical_score(state, *mv) == 0 {
+                info.history.record_cutoff(*mv, depth);
+            }
             break;
         }
     }
 
     // Store in transposition table
-    let node_type = if best_score <= original_alpha {
+    let node_type = node_type_for(best_score, original_alpha, beta);
+    info.tt.store(hash, best_move, best_score, depth, node_type);
+
+    (best_score, best_move, best_pv)
+}
+
+/// How a fail-soft score searched with the window (`alpha`, `beta`) can be
+/// reused from the transposition table.
+fn node_type_for(score: i32, alpha: i32, beta: i32) -> NodeType {
+    if score <= alpha {
         NodeType::UpperBound
-    } else if best_score >= beta {
+    } else if score >= beta {
         NodeType::LowerBound
     } else {
         NodeType::Exact
-    };
-
-    info.tt.store(hash, best_move, best_score, depth, node_type);
-
-    (best_score, best_move, best_pv)
+    }
 }
 
 fn quiescence(
@@ -534,15 +1020,21 @@ fn quiescence(
         return 0;
     }
 
+    // With checks in quiescence, a side in check must answer the check
+    // rather than stand pat, or quiet checks could never win anything
+    let in_check = info.quiescence_checks > 0 && state.is_in_check();
+
     // Stand pat evaluation - can we beat alpha without searching?
-    let stand_pat = state.evaluate();
+    let stand_pat = info.evaluator.eval(state);
 
-    if stand_pat >= beta {
-        return beta;
-    }
+    if !in_check {
+        if stand_pat >= beta {
+            return beta;
+        }
 
-    if alpha < stand_pat {
-        alpha = stand_pat;
+        if alpha < stand_pat {
+            alpha = stand_pat;
+        }
     }
 
     // Depth limit for quiescence search
@@ -553,19 +1045,33 @@ fn quiescence(
     // Generate all legal moves
     let moves = generate_legal_moves(state);
 
-    // Filter to only captures and promotions
+    // No legal moves - score mate or stalemate instead of the static eval,
+    // so a losing side can see a stalemate save on the horizon
+    if moves.is_empty() {
+        if state.is_in_check() {
+            return -CHECKMATE_SCORE + i32::from(state.fullmove_number);
+        }
+        return 0;
+    }
+
+    let is_tactical = |mv: &Move| {
+        // Is this a capture?
+        state.board.piece_at(mv.to).is_some() ||
+        // Is this a promotion?
+        mv.promotion.is_some() ||
+        // Is this an en passant capture?
+        (state.board.piece_at(mv.from).map(|p| p.piece_type == chess_core::PieceType::Pawn).unwrap_or(false) &&
+         Some(mv.to) == state.en_passant)
+    };
+
+    // Quiet checks only in the first plies after the horizon, so they can't explode
+    let search_checks = info.quiescence_depth - depth < info.quiescence_checks as i8;
+
+    // Filter to captures and promotions, plus evasions and early quiet checks
     let mut capture_moves: Vec<Move> = moves
         .iter()
         .copied()
-        .filter(|mv| {
-            // Is this a capture?
-            state.board.piece_at(mv.to).is_some() ||
-            // Is this a promotion?
-            mv.promotion.is_some() ||
-            // Is this an en passant capture?
-            (state.board.piece_at(mv.from).map(|p| p.piece_type == chess_core::PieceType::Pawn).unwrap_or(false) &&
-             Some(mv.to) == state.en_passant)
-        })
+        .filter(|mv| in_check || is_tactical(mv) || (search_checks && state.gives_check(*mv)))
         .collect();
 
     // If no captures, return stand pat
@@ -576,7 +1082,19 @@ fn quiescence(
     // Order captures by MVV-LVA (Most Valuable Victim - Least Valuable Attacker)
     order_captures(state, &mut capture_moves);
 
+    // Delta pruning is unsafe in check and in bare endgames where every capture matters
+    let delta_margin = info
+        .delta_margin
+        .filter(|_| !state.is_in_check() && !is_late_endgame(state));
+
     for mv in capture_moves {
+        // Delta pruning: skip captures that can't raise alpha even with a margin
+        if let Some(margin) = delta_margin.filter(|_| is_tactical(&mv)) {
+            if stand_pat + capture_gain(state, mv) + margin < alpha {
+                continue;
+            }
+        }
+
         let new_state = state.apply_move(mv);
         let score = -quiescence(&new_state, depth - 1, -beta, -alpha, info);
 
@@ -596,54 +1114,115 @@ fn quiescence(
     alpha
 }
 
-fn order_captures(state: &GameState, moves: &mut [Move]) {
-    moves.sort_by_cached_key(|mv| {
-        let mut score = 0;
+/// Material won by a capture or promotion, in centipawns.
+fn capture_gain(state: &GameState, mv: Move) -> i32 {
+    let victim = match state.board.piece_at(mv.to) {
+        Some(piece) => i32::from(piece.piece_type.value()),
+        None if Some(mv.to) == state.en_passant => i32::from(PieceType::Pawn.value()),
+        None => 0,
+    };
 
-        // Get victim value (what we're capturing)
-        if let Some(victim) = state.board.piece_at(mv.to) {
-            score -= victim.piece_type.value() as i32 * 10;
-        }
+    let promotion = mv.promotion.map_or(0, |promo| {
+        i32::from(promo.value()) - i32::from(PieceType::Pawn.value())
+    });
 
-        // Get attacker value (prefer capturing with less valuable pieces)
-        if let Some(attacker) = state.board.piece_at(mv.from) {
-            score += attacker.piece_type.value() as i32;
+    victim + promotion
+}
+
+/// True when little non-pawn material is left on the board.
+fn is_late_endgame(state: &GameState) -> bool {
+    let non_pawn_material: i32 = [Color::White, Color::Black]
+        .iter()
+        .flat_map(|&color| {
+            [
+                PieceType::Knight,
+                PieceType::Bishop,
+                PieceType::Rook,
+                PieceType::Queen,
+            ]
+            .map(|piece_type| {
+                state.board.bitboards.pieces(piece_type, color).count() as i32
+                    * i32::from(piece_type.value())
+            })
+        })
+        .sum();
+
+    non_pawn_material <= ENDGAME_MATERIAL
+}
+
+/// Piece types in `PieceType::index` order.
+const PIECE_TYPES: [PieceType; 6] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+    PieceType::King,
+];
+
+/// MVV-LVA capture priority indexed `[victim][attacker]`: the most valuable
+/// victim first, then the least valuable attacker. Every entry is positive,
+/// so any capture outranks a quiet move.
+const MVV_LVA: [[i32; 6]; 6] = {
+    let mut table = [[0; 6]; 6];
+    let mut victim = 0;
+    while victim < 6 {
+        let mut attacker = 0;
+        while attacker < 6 {
+            table[victim][attacker] = PIECE_TYPES[victim].value() as i32 * 10 - attacker as i32;
+            attacker += 1;
         }
+        victim += 1;
+    }
+    table
+};
 
-        // Promotions are also valuable
-        if let Some(promo) = mv.promotion {
-            score -= promo.value() as i32 * 10;
+/// Ordering priority of a capture or promotion; 0 for a quiet move.
+fn tactical_score(state: &GameState, mv: Move) -> i32 {
+    let Some(attacker) = state.board.piece_at(mv.from) else {
+        return 0;
+    };
+
+    let victim = match state.board.piece_at(mv.to) {
+        Some(piece) => Some(piece.piece_type),
+        None if attacker.piece_type == PieceType::Pawn && Some(mv.to) == state.en_passant => {
+            Some(PieceType::Pawn)
         }
+        None => None,
+    };
 
-        score
+    let capture = victim.map_or(0, |victim| {
+        MVV_LVA[victim.index()][attacker.piece_type.index()]
     });
+    let promotion = mv.promotion.map_or(0, |promo| promo.value() as i32 * 10);
+
+    capture + promotion
 }
 
+fn order_captures(state: &GameState, moves: &mut [Move]) {
+    // Most valuable victim / least valuable attacker first
+    moves.sort_by_cached_key(|mv| -tactical_score(state, *mv));
+}
+
+/// Orders root moves by capture value alone, without history scores.
 fn order_moves(state: &GameState, moves: &mut [Move]) {
-    order_moves_with_tt(state, moves, None);
+    order_moves_with_tt(state, moves, None, None);
 }
 
-fn order_moves_with_tt(state: &GameState, moves: &mut [Move], tt_move: Option<Move>) {
-    // Move ordering: TT move first, then captures, then promotions
+fn order_moves_with_tt(
+    state: &GameState,
+    moves: &mut [Move],
+    tt_move: Option<Move>,
+    history: Option<&History>,
+) {
+    // Move ordering: TT move first, then captures and promotions by MVV-LVA,
+    // then quiet moves by how often they caused cutoffs elsewhere
     moves.sort_by_cached_key(|mv| {
-        let mut score = 0;
-
-        // TT move gets highest priority
         if tt_move == Some(*mv) {
-            return -10000;
+            return (i32::MIN, Reverse(0));
         }
-
-        // Prioritize captures
-        if state.board.piece_at(mv.to).is_some() {
-            score -= 1000;
-        }
-
-        // Prioritize promotions
-        if mv.promotion.is_some() {
-            score -= 900;
-        }
-
-        score
+        let history_score = history.map_or(0, |history| history.score(*mv));
+        (-tactical_score(state, *mv), Reverse(history_score))
     });
 }
 
@@ -655,47 +1234,655 @@ fn iterative_deepening_limits(state: &GameState, info: &mut SearchInfo) -> Searc
     let mut best_result = SearchResult {
         best_move: None,
         score: 0,
+        pv: Vec::new(),
         depth: 0,
         nodes: 0,
         stopped: false,
+        stats: SearchStats::default(),
     };
 
+    // Progress held back by the throttle, and when progress was last sent
+    let mut unreported = None;
+    let mut last_report: Option<Instant> = None;
+
     // Search to increasing depths until time runs out
     for depth in 1..=100 {
-        let saved_nodes = info.nodes;
         let _depth_start = info.start_time.elapsed();
+        if depth > 1 {
+            info.history.age();
+        }
         let (score, best_move, pv) = alpha_beta_root(state, depth, -INFINITY, INFINITY, info);
 
         // Only update result if we completed this depth
         if !info.stopped && best_move.is_some() {
             best_result.best_move = best_move;
             best_result.score = score;
+            best_result.pv = pv.clone();
             best_result.depth = depth;
             best_result.nodes = info.nodes;
+            info.publish_best(best_move);
 
             // Send info to callback if present
-            if let Some(ref callback) = info.info_callback {
+            if info.info_callback.is_some() {
                 let progress = SearchProgress {
                     depth,
                     score,
                     nodes: info.nodes,
                     pv: pv.clone(),
                     time_ms: info.start_time.elapsed().as_millis() as u64,
+                    hashfull: info.tt.hashfull(),
+                };
+                let now = Instant::now();
+                let due = match (info.limits.info_interval, last_report) {
+                    (Some(interval), Some(last)) => now.duration_since(last) >= interval,
+                    _ => true,
                 };
-                callback(&progress);
+                if due {
+                    info.report(&progress);
+                    last_report = Some(now);
+                    unreported = None;
+                } else {
+                    unreported = Some(progress);
+                }
             }
 
-            // Stop if we found checkmate
-            if score.abs() >= CHECKMATE_SCORE - 100 {
+            // Stop if we found checkmate, or if no depth can change a dead draw
+            if score.abs() >= CHECKMATE_SCORE - 100 || state.is_insufficient_material() {
                 break;
             }
         } else {
-            // Restore node count if search was interrupted
-            info.nodes = saved_nodes;
             break;
         }
     }
 
+    if let Some(progress) = unreported {
+        info.report(&progress);
+    }
+
+    // Report every node searched, including the interrupted iteration
+    best_result.nodes = info.nodes;
     best_result.stopped = info.stopped;
+    best_result.stats = info.stats;
     best_result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::Evaluatable;
+
+    #[test]
+    fn test_node_limit_is_exact_and_reproducible() {
+        let state = GameState::new();
+
+        let first = search_with_limits(&state, SearchLimits::nodes(50_000));
+        let second = search_with_limits(&state, SearchLimits::nodes(50_000));
+
+        assert!(first.nodes <= 50_000, "searched {} nodes", first.nodes);
+        assert_eq!(first.nodes, second.nodes);
+        assert_eq!(first.best_move, second.best_move);
+        assert_eq!(first.depth, second.depth);
+        assert!(first.best_move.is_some());
+    }
+
+    #[test]
+    fn test_history_stays_bounded_and_ages() {
+        let sq = |name| chess_core::Square::from_algebraic(name).unwrap();
+        let (nf3, nc3, e4) = (
+            Move::new(sq("g1"), sq("f3")),
+            Move::new(sq("b1"), sq("c3")),
+            Move::new(sq("e2"), sq("e4")),
+        );
+
+        // Deep cutoffs would overflow a raw counter many times over
+        let mut history = History::new();
+        for _ in 0..100_000 {
+            history.record_cutoff(nf3, 60);
+            history.record_cutoff(nf3, 60);
+            history.record_cutoff(nc3, 60);
+        }
+        assert!(history.scores.iter().flatten().all(|&s| s <= HISTORY_MAX));
+        assert!(history.score(nf3) > history.score(nc3));
+        assert!(history.score(nc3) > 0);
+
+        // Credit from earlier iterations fades behind fresh cutoffs
+        let mut history = History::new();
+        for _ in 0..10 {
+            history.record_cutoff(nf3, 4);
+        }
+        for _ in 0..3 {
+            history.age();
+        }
+        history.record_cutoff(e4, 5);
+        history.record_cutoff(nc3, 3);
+        assert!(history.score(e4) > history.score(nf3));
+        assert!(history.score(e4) > history.score(nc3));
+        assert!(history.score(nf3) < 10 * 16);
+    }
+
+    #[test]
+    fn test_first_move_cutoff_rate_on_quiet_position() {
+        // Iterative deepening, so table moves from earlier depths count too
+        let state = GameState::new();
+        let result = search_with_limits(&state, SearchLimits::nodes(100_000));
+        let stats = result.stats;
+        assert!(stats.beta_cutoffs > 100);
+        let rate = stats.first_move_cutoff_rate().unwrap();
+        assert!(rate > 0.8, "first move cutoff rate {rate:.2}");
+        assert_eq!(stats.root_cutoffs, 0);
+        assert_eq!(stats.root_fail_lows, 0);
+    }
+
+    #[test]
+    fn test_search_window_fails_high_and_low() {
+        let state =
+            GameState::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap();
+        let exact = search(&state, 3).score;
+
+        let full = search_window(&state, 3, -INFINITY, INFINITY);
+        assert_eq!(full.score, exact);
+
+        let around = search_window(&state, 3, exact - 1, exact + 1);
+        assert_eq!(around.score, exact);
+
+        let above = search_window(&state, 3, exact + 50, exact + 100);
+        assert!(
+            above.score <= exact + 50,
+            "expected fail low, got {}",
+            above.score
+        );
+        assert_eq!(above.stats.root_fail_lows, 1);
+
+        let below = search_window(&state, 3, exact - 100, exact - 50);
+        assert!(
+            below.score >= exact - 50,
+            "expected fail high, got {}",
+            below.score
+        );
+        assert!(below.nodes < full.nodes);
+        assert_eq!(below.stats.root_cutoffs, 1);
+        assert_eq!((full.stats.root_cutoffs, full.stats.root_fail_lows), (0, 0));
+    }
+
+    #[test]
+    fn test_finds_stalemate_save() {
+        // White is a queen and rook down with a smothered king: Qg7+ forces
+        // Kxg7 or Rxg7, and either capture leaves White stalemated
+        let state = GameState::from_fen("7k/3Q4/6r1/8/8/7p/q6P/7K w - - 0 1").unwrap();
+        let stalemate_save = Move::new(
+            chess_core::Square::from_index(51).unwrap(), // d7
+            chess_core::Square::from_index(54).unwrap(), // g7
+        );
+
+        // Check extensions also find the slower Qd8+ perpetual, so look for
+        // this particular save without them
+        let without_extensions = SearchOptions {
+            check_extensions: false,
+            ..SearchOptions::default()
+        };
+        for depth in 2..=5 {
+            let result = search_with_options(
+                &state,
+                SearchLimits::depth(depth),
+                without_extensions.clone(),
+            );
+            assert_eq!(result.best_move, Some(stalemate_save), "depth {depth}");
+            assert_eq!(result.score, 0, "depth {depth}");
+
+            assert_eq!(search(&state, depth).score, 0, "depth {depth}");
+        }
+    }
+
+    #[test]
+    fn test_insufficient_material_returns_draw_without_searching() {
+        let state = GameState::from_fen("8/8/3k4/8/8/4K3/8/8 w - - 0 1").unwrap();
+        let legal = generate_legal_moves(&state);
+
+        for result in [
+            search(&state, 6),
+            search_with_limits(&state, SearchLimits::move_time(1000)),
+        ] {
+            assert_eq!(result.score, 0);
+            assert_eq!(result.nodes, 0);
+            assert!(legal.iter().any(|&mv| Some(mv) == result.best_move));
+        }
+
+        // Dead draws inside the tree score as draws too: Kxc3 leaves K+N vs
+        // K, which is worth 0 rather than a knight, and anything else keeps
+        // White a rook down
+        let state = GameState::from_fen("8/8/8/8/8/2r5/1K6/5kN1 w - - 0 1").unwrap();
+        let result = search(&state, 2);
+        assert_eq!(
+            result.best_move,
+            Some(Move::new(
+                chess_core::Square::from_algebraic("b2").unwrap(),
+                chess_core::Square::from_algebraic("c3").unwrap(),
+            ))
+        );
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn test_only_move_returns_without_searching() {
+        // Ra1+ leaves Kg2 as the only legal move
+        let state = GameState::from_fen("k7/8/8/8/8/8/7P/r6K w - - 0 1").unwrap();
+        let only_move = Move::new(
+            chess_core::Square::from_algebraic("h1").unwrap(),
+            chess_core::Square::from_algebraic("g2").unwrap(),
+        );
+
+        // The 5 s budget goes unused: the search stops after depth 1
+        let result = search_with_limits(&state, SearchLimits::move_time(5_000));
+        assert_eq!(result.best_move, Some(only_move));
+        assert_eq!(result.depth, 1);
+        assert_eq!(result.nodes, search(&state, 1).nodes);
+        assert!(!result.stopped);
+
+        // Depth limits still search the single move properly
+        assert_eq!(search(&state, 4).depth, 4);
+    }
+
+    #[test]
+    fn test_disabled_quiescence_returns_static_eval() {
+        // Qxd5 grabs a pawn defended by c6, which only quiescence notices
+        let state = GameState::from_fen("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+        let grab = Move::new(
+            chess_core::Square::from_algebraic("d1").unwrap(),
+            chess_core::Square::from_algebraic("d5").unwrap(),
+        );
+
+        let raw = search_with_options(
+            &state,
+            SearchLimits::depth(1),
+            SearchOptions {
+                quiescence: false,
+                check_extensions: false,
+                ..SearchOptions::default()
+            },
+        );
+        assert_eq!(raw.best_move, Some(grab));
+        assert_eq!(raw.score, -state.apply_move(grab).evaluate());
+        // Each leaf is one node, counted once
+        assert_eq!(raw.nodes, generate_legal_moves(&state).len() as u64);
+
+        let resolved =
+            search_with_options(&state, SearchLimits::depth(1), SearchOptions::default());
+        assert_ne!(resolved.best_move, Some(grab));
+    }
+
+    /// Material only, from the side to move's perspective, counting calls.
+    #[derive(Default)]
+    struct MaterialEvaluator {
+        calls: std::sync::atomic::AtomicU64,
+    }
+
+    impl Evaluator for MaterialEvaluator {
+        fn eval(&self, state: &GameState) -> i32 {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            let material = crate::evaluation::evaluate_explained(state).material.net();
+            match state.turn {
+                Color::White => material,
+                Color::Black => -material,
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_uses_injected_evaluator() {
+        let state = GameState::new();
+        let evaluator = Arc::new(MaterialEvaluator::default());
+        let result = search_with_options(
+            &state,
+            SearchLimits::depth(3),
+            SearchOptions {
+                evaluator: evaluator.clone(),
+                ..SearchOptions::default()
+            },
+        );
+
+        // Nothing can be won by force, and the hand-crafted positional
+        // terms that would otherwise break the tie are gone
+        assert!(evaluator.calls.load(Ordering::Relaxed) > 0);
+        assert_eq!(result.score, 0);
+        assert_ne!(search(&state, 3).score, 0);
+    }
+
+    #[test]
+    fn test_check_extensions_find_smothered_mate() {
+        // Nf7+ Kg8 Nh6+ Kh8 Qg8+ Rxg8 Nf7#: every White move is a check
+        let state = GameState::from_fen("r6k/6pp/8/4N3/8/1Q6/6PP/6K1 w - - 0 1").unwrap();
+        let search_at = |check_extensions| {
+            search_with_options(
+                &state,
+                SearchLimits::depth(4),
+                SearchOptions {
+                    check_extensions,
+                    ..SearchOptions::default()
+                },
+            )
+        };
+
+        let with = search_at(true);
+        assert_eq!(mate_in(with.score, &state), Some(4));
+        assert_eq!(with.best_move.map(|mv| mv.to_string()), Some("e5f7".into()));
+
+        let without = search_at(false);
+        assert_eq!(mate_in(without.score, &state), None);
+    }
+
+    #[test]
+    fn test_mvv_lva_ordering() {
+        // exd5 wins a queen with a pawn; Qxd7 and Rxa7 win pawns with heavier pieces
+        let state = GameState::from_fen("4k3/p2p4/8/3q4/4P3/7Q/8/R3K3 w - - 0 1").unwrap();
+        let sq = |name| chess_core::Square::from_algebraic(name).unwrap();
+        let pawn_takes_queen = Move::new(sq("e4"), sq("d5"));
+        let queen_takes_pawn = Move::new(sq("h3"), sq("d7"));
+        let rook_takes_pawn = Move::new(sq("a1"), sq("a7"));
+
+        let mut captures = vec![rook_takes_pawn, queen_takes_pawn, pawn_takes_queen];
+        order_captures(&state, &mut captures);
+        assert_eq!(
+            captures,
+            vec![pawn_takes_queen, rook_takes_pawn, queen_takes_pawn]
+        );
+
+        // The main-search orderer agrees on the relative order of captures
+        let mut all_moves = Vec::from(&generate_legal_moves(&state));
+        order_moves(&state, &mut all_moves);
+        let ordered_captures: Vec<Move> = all_moves
+            .into_iter()
+            .filter(|mv| captures.contains(mv))
+            .collect();
+        assert_eq!(ordered_captures, captures);
+        assert!(
+            MVV_LVA[PieceType::Queen.index()][PieceType::Pawn.index()]
+                > MVV_LVA[PieceType::Pawn.index()][PieceType::Queen.index()]
+        );
+    }
+
+    #[test]
+    fn test_pv_plays_out_forced_mate() {
+        // 1. Kc7 Ka7 2. Ra1#
+        let state = GameState::from_fen("k7/8/2K5/8/8/8/8/1R6 w - - 0 1").unwrap();
+
+        for result in [
+            search(&state, 5),
+            search_with_limits(&state, SearchLimits::nodes(200_000)),
+        ] {
+            assert_eq!(mate_in(result.score, &state), Some(2));
+            assert_eq!(result.pv.len(), 3);
+            assert_eq!(result.pv.first().copied(), result.best_move);
+
+            let mut position = state.clone();
+            for &mv in &result.pv {
+                assert!(generate_legal_moves(&position).iter().any(|&m| m == mv));
+                position = position.apply_move(mv);
+            }
+            assert!(chess_core::is_checkmate(&position));
+        }
+    }
+
+    #[test]
+    fn test_extract_pv_follows_table_until_cycle_or_illegal_move() {
+        let sq = |name: &str| name.parse::<chess_core::Square>().unwrap();
+        let tt = TranspositionTable::new(1);
+        let start = GameState::new();
+
+        // 1. Nf3 Nf6 2. Ng1 Ng8 comes back to the start
+        let dance = [("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")];
+        let mut position = start.clone();
+        for (from, to) in dance {
+            let mv = Move::new(sq(from), sq(to));
+            tt.store(position.zobrist_hash(), Some(mv), 0, 1, NodeType::Exact);
+            position = position.apply_move(mv);
+        }
+        assert_eq!(extract_pv(&start, &tt, MAX_PV_LENGTH).len(), 4);
+        assert_eq!(extract_pv(&start, &tt, 2).len(), 2);
+
+        // A stored move that is illegal here ends the line
+        let after_nf3 = start.apply_move(Move::new(sq("g1"), sq("f3")));
+        tt.store(
+            after_nf3.zobrist_hash(),
+            Some(Move::new(sq("e2"), sq("e4"))),
+            0,
+            1,
+            NodeType::Exact,
+        );
+        assert_eq!(extract_pv(&start, &tt, MAX_PV_LENGTH).len(), 1);
+    }
+
+    #[test]
+    fn test_pv_survives_table_cutoffs() {
+        // 1. Kc7 Ka7 2. Ra1#
+        let state = GameState::from_fen("k7/8/2K5/8/8/8/8/1R6 w - - 0 1").unwrap();
+        let tt = Arc::new(TranspositionTable::new(1));
+        let mut info = SearchInfo::new(SearchLimits::depth(5), tt);
+        let (_, _, first) = alpha_beta_root(&state, 5, -INFINITY, INFINITY, &mut info);
+        assert_eq!(first.len(), 3);
+
+        // Searching again, the reply is an exact table hit that reports only
+        // its stored move instead of the rest of the line
+        let child = state.apply_move(first[0]);
+        let (_, _, cut) = alpha_beta(
+            &child,
+            child.zobrist_hash(),
+            4,
+            0,
+            -INFINITY,
+            INFINITY,
+            &mut info,
+        );
+        assert_eq!(cut, vec![first[1]]);
+
+        let (_, _, again) = alpha_beta_root(&state, 5, -INFINITY, INFINITY, &mut info);
+        assert_eq!(again, first);
+    }
+
+    #[test]
+    fn test_mate_in_from_search_score() {
+        let white = GameState::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let result = search(&white, 2);
+        assert_eq!(mate_in(result.score, &white), Some(1));
+
+        let black = GameState::from_fen("r5k1/8/8/8/8/8/5PPP/6K1 b - - 0 1").unwrap();
+        let result = search(&black, 2);
+        assert_eq!(mate_in(result.score, &black), Some(1));
+
+        let losing = GameState::from_fen("7k/R7/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        let result = search(&losing, 3);
+        assert_eq!(mate_in(result.score, &losing), Some(-1));
+
+        assert_eq!(mate_in(35, &white), None);
+    }
+
+    #[test]
+    fn test_delta_pruning_keeps_tactics_and_saves_nodes() {
+        let positions = [
+            // Free queen on d5
+            "rnb1kbnr/ppp1pppp/8/3q4/8/2N5/PPPP1PPP/R1BQKBNR w KQkq - 0 3",
+            // Kiwipete
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ];
+
+        for fen in positions {
+            let state = GameState::from_fen(fen).unwrap();
+            let without = search_with_options(
+                &state,
+                SearchLimits::depth(3),
+                SearchOptions {
+                    delta_margin: None,
+                    ..SearchOptions::default()
+                },
+            );
+            let with =
+                search_with_options(&state, SearchLimits::depth(3), SearchOptions::default());
+
+            assert_eq!(with.best_move, without.best_move, "{fen}");
+            assert_eq!(with.score, without.score, "{fen}");
+            assert!(
+                with.nodes < without.nodes,
+                "{fen}: {} vs {}",
+                with.nodes,
+                without.nodes
+            );
+        }
+    }
+
+    #[test]
+    fn test_parallel_search_finds_move() {
+        let state = GameState::new();
+        let result = search_parallel(&state, SearchLimits::depth(3), 1, 2);
+
+        assert!(result.best_move.is_some());
+        assert_eq!(result.depth, 3);
+    }
+
+    #[test]
+    fn test_quiescence_checks_see_back_rank_mate() {
+        // Qxa2 wins a pawn but allows Re8#, a quiet check one ply past the horizon
+        let state = GameState::from_fen("6k1/5ppp/8/8/8/1q6/P4PPP/4R1K1 b - - 0 1").unwrap();
+        let grab = Move::new(
+            chess_core::Square::from_index(17).unwrap(),
+            chess_core::Square::from_index(8).unwrap(),
+        );
+
+        let without = search_with_options(&state, SearchLimits::depth(1), SearchOptions::default());
+        assert_eq!(without.best_move, Some(grab));
+
+        let with = search_with_options(
+            &state,
+            SearchLimits::depth(1),
+            SearchOptions {
+                quiescence_checks: 1,
+                ..SearchOptions::default()
+            },
+        );
+        assert_ne!(with.best_move, Some(grab));
+        assert!(with.score.abs() < CHECKMATE_SCORE / 2, "{}", with.score);
+    }
+
+    #[test]
+    fn test_shared_best_move_can_be_polled() {
+        let state = GameState::from_fen(chess_core::positions::KIWIPETE).unwrap();
+        let legal = generate_legal_moves(&state);
+        let shared: SharedBestMove = Arc::new(Mutex::new(None));
+
+        let search = {
+            let state = state.clone();
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                search_with_shared_best(&state, SearchLimits::nodes(50_000), shared)
+            })
+        };
+
+        // Poll until the first iteration publishes its move
+        let polled = loop {
+            if let Some(mv) = *shared.lock().unwrap() {
+                break mv;
+            }
+            assert!(!search.is_finished() || shared.lock().unwrap().is_some());
+            thread::yield_now();
+        };
+        assert!(legal.iter().any(|&mv| mv == polled));
+
+        let result = search.join().unwrap();
+        assert!(result.best_move.is_some());
+        assert_eq!(*shared.lock().unwrap(), result.best_move);
+
+        // Fixed-depth searches publish their result too
+        let result = search_with_shared_best(&state, SearchLimits::depth(2), Arc::clone(&shared));
+        assert_eq!(*shared.lock().unwrap(), result.best_move);
+    }
+
+    #[test]
+    fn test_currmove_callback_reports_each_root_move() {
+        let state = GameState::new();
+        let reported = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reported);
+
+        search_with_callbacks_and_stop(
+            &state,
+            SearchLimits::depth(3),
+            Box::new(|_| {}),
+            Box::new(move |progress| {
+                sink.lock().unwrap().push((
+                    progress.depth,
+                    progress.currmove,
+                    progress.currmovenumber,
+                ));
+            }),
+            Arc::new(AtomicBool::new(false)),
+        );
+
+        let reported = reported.lock().unwrap();
+        let root_moves = generate_legal_moves(&state).len();
+        let numbers: Vec<usize> = reported.iter().map(|&(_, _, n)| n).collect();
+        assert_eq!(numbers, (1..=root_moves).collect::<Vec<_>>());
+        assert!(reported.iter().all(|&(depth, _, _)| depth == 3));
+
+        // Each root move is reported once
+        let mut moves: Vec<Move> = reported.iter().map(|&(_, mv, _)| mv).collect();
+        moves.sort_by_key(|mv| (mv.from.index(), mv.to.index()));
+        moves.dedup();
+        assert_eq!(moves.len(), root_moves);
+    }
+
+    #[test]
+    fn test_info_interval_throttles_but_reports_final_depth() {
+        let state = GameState::new();
+        let search_depths = |info_interval| {
+            let depths = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let sink = Arc::clone(&depths);
+            let limits = SearchLimits {
+                info_interval,
+                ..SearchLimits::nodes(20_000)
+            };
+            let result = search_with_callback(
+                &state,
+                limits,
+                Box::new(move |progress| sink.lock().unwrap().push(progress.depth)),
+            );
+            let depths = depths.lock().unwrap().clone();
+            (result, depths)
+        };
+
+        // Unthrottled, every completed depth reports
+        let (result, depths) = search_depths(None);
+        assert!(result.depth >= 3);
+        assert_eq!(depths, (1..=result.depth).collect::<Vec<_>>());
+
+        // The first depth reports at once; the rest fall inside the interval
+        // and only the final one is sent
+        let (result, depths) = search_depths(Some(Duration::from_secs(3600)));
+        assert_eq!(depths, vec![1, result.depth]);
+    }
+
+    #[test]
+    fn test_stop_flag_interrupts_infinite_search() {
+        let state = GameState::new();
+        let limits = SearchLimits::infinite();
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let stopper = {
+            let stop_flag = Arc::clone(&stop_flag);
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                stop_flag.store(true, Ordering::Relaxed);
+            })
+        };
+
+        let start = Instant::now();
+        let result =
+            search_with_callback_and_stop(&state, limits, Box::new(|_| {}), Arc::clone(&stop_flag));
+        stopper.join().unwrap();
+
+        assert!(result.stopped);
+        assert!(result.best_move.is_some());
+        assert!(result.depth >= 1);
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "{:?}",
+            start.elapsed()
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use chess_core::Move;
 use std::sync::atomic::{AtomicU64, Ordering};
 
+/// Added to scores before packing so negative scores fit in unsigned bits.
+const SCORE_OFFSET: i32 = 1 << 23;
+
 /// Type of node in the search tree.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NodeType {
@@ -129,6 +132,16 @@ impl TranspositionTable {
         self.generation = 0;
     }
 
+    /// Returns how full the table is in permille, sampled over the first
+    /// 1000 slots as UCI `hashfull` expects.
+    pub fn hashfull(&self) -> u32 {
+        let slots = (self.size_mask + 1).min(1000);
+        let used = (0..slots)
+            .filter(|&slot| self.entries[slot * 2].load(Ordering::Relaxed) != 0)
+            .count();
+        (used * 1000 / slots) as u32
+    }
+
     /// Advances to the next search generation.
     pub fn new_search(&mut self) {
         self.generation = self.generation.wrapping_add(1);
@@ -139,31 +152,16 @@ impl TranspositionTable {
         // First u64: full hash
         let packed1 = entry.hash;
 
-        // Second u64: move (16 bits) + score (16 bits) + depth (8 bits) +
-        //              node_type (2 bits) + age (8 bits) + reserved (14 bits)
+        // Second u64: move (16 bits) + depth (8 bits) + node_type (2 bits) +
+        //              age (8 bits) + reserved (6 bits) + score (24 bits)
         let mut packed2 = 0u64;
 
-        // Pack move (16 bits)
-        if let Some(mv) = entry.best_move {
-            let from = mv.from.index() as u64;
-            let to = mv.to.index() as u64;
-            let promo = match mv.promotion {
-                None => 0,
-                Some(chess_core::PieceType::Queen) => 1,
-                Some(chess_core::PieceType::Rook) => 2,
-                Some(chess_core::PieceType::Bishop) => 3,
-                Some(chess_core::PieceType::Knight) => 4,
-                _ => 0,
-            };
-            packed2 |= (from << 10) | (to << 4) | promo;
-        }
-
-        // Pack score (16 bits, offset by 32768 to handle negative values)
-        let score_bits = ((entry.score + 32768) as u64) & 0xFFFF;
-        packed2 |= score_bits << 16;
+        // Pack move (16 bits); no best move is stored as the null move
+        let mv = entry.best_move.unwrap_or(Move::NULL);
+        packed2 |= u64::from(mv.to_u16());
 
         // Pack depth (8 bits)
-        packed2 |= (entry.depth as u64) << 32;
+        packed2 |= (entry.depth as u64) << 16;
 
         // Pack node type (2 bits)
         let node_type_bits = match entry.node_type {
@@ -171,10 +169,15 @@ impl TranspositionTable {
             NodeType::LowerBound => 1,
             NodeType::UpperBound => 2,
         };
-        packed2 |= node_type_bits << 40;
+        packed2 |= node_type_bits << 24;
 
         // Pack age (8 bits)
-        packed2 |= (entry.age as u64) << 42;
+        packed2 |= (entry.age as u64) << 26;
+
+        // Pack score (24 bits, offset to handle negative values). Mate
+        // scores need more than 16 bits
+        let score_bits = ((entry.score + SCORE_OFFSET) as u64) & 0xFF_FFFF;
+        packed2 |= score_bits << 40;
 
         (packed1, packed2)
     }
@@ -184,35 +187,18 @@ impl TranspositionTable {
         let hash = packed1;
 
         // Unpack move
-        let move_bits = packed2 & 0xFFFF;
-        let best_move = if move_bits != 0 {
-            let from = chess_core::Square::from_index(((move_bits >> 10) & 0x3F) as u8).unwrap();
-            let to = chess_core::Square::from_index(((move_bits >> 4) & 0x3F) as u8).unwrap();
-            let promo = match move_bits & 0xF {
-                1 => Some(chess_core::PieceType::Queen),
-                2 => Some(chess_core::PieceType::Rook),
-                3 => Some(chess_core::PieceType::Bishop),
-                4 => Some(chess_core::PieceType::Knight),
-                _ => None,
-            };
-            if let Some(p) = promo {
-                Some(Move::new_promotion(from, to, p))
-            } else {
-                Some(Move::new(from, to))
-            }
-        } else {
-            None
-        };
+        let mv = Move::from_u16((packed2 & 0xFFFF) as u16);
+        let best_move = (!mv.is_null()).then_some(mv);
 
         // Unpack score
-        let score_bits = (packed2 >> 16) & 0xFFFF;
-        let score = (score_bits as i32) - 32768;
+        let score_bits = (packed2 >> 40) & 0xFF_FFFF;
+        let score = (score_bits as i32) - SCORE_OFFSET;
 
         // Unpack depth
-        let depth = ((packed2 >> 32) & 0xFF) as u8;
+        let depth = ((packed2 >> 16) & 0xFF) as u8;
 
         // Unpack node type
-        let node_type = match (packed2 >> 40) & 0x3 {
+        let node_type = match (packed2 >> 24) & 0x3 {
             0 => NodeType::Exact,
             1 => NodeType::LowerBound,
             2 => NodeType::UpperBound,
@@ -220,7 +206,7 @@ impl TranspositionTable {
         };
 
         // Unpack age
-        let age = ((packed2 >> 42) & 0xFF) as u8;
+        let age = ((packed2 >> 26) & 0xFF) as u8;
 
         TranspositionEntry {
             hash,
@@ -232,3 +218,61 @@ impl TranspositionTable {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::CHECKMATE_SCORE;
+    use chess_core::{PieceType, Square};
+
+    #[test]
+    fn test_entries_round_trip() {
+        let sq = |name: &str| name.parse::<Square>().unwrap();
+        let mut tt = TranspositionTable::new(1);
+        for _ in 0..u8::MAX {
+            tt.new_search();
+        }
+
+        let moves = [
+            None,
+            Some(Move::new(sq("g1"), sq("f3"))),
+            Some(Move::new_promotion(sq("a7"), sq("b8"), PieceType::Knight)),
+            Some(Move::new_castle(sq("e8"), sq("c8"))),
+            Some(Move::new_en_passant(sq("e5"), sq("d6"))),
+        ];
+        let scores = [
+            0,
+            -37,
+            CHECKMATE_SCORE,
+            -CHECKMATE_SCORE,
+            CHECKMATE_SCORE - 41,
+            -CHECKMATE_SCORE + 41,
+        ];
+        let node_types = [NodeType::Exact, NodeType::LowerBound, NodeType::UpperBound];
+
+        let mut hash = 0x9E37_79B9_7F4A_7C15_u64;
+        for best_move in moves {
+            for score in scores {
+                for node_type in node_types {
+                    for depth in [0, 7, u8::MAX] {
+                        hash = hash.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                        tt.store(hash, best_move, score, depth, node_type);
+
+                        let entry = tt.probe(hash).unwrap();
+                        assert_eq!(entry.hash, hash);
+                        assert_eq!(entry.best_move, best_move);
+                        assert_eq!(
+                            entry.best_move.map(|mv| mv.flags),
+                            best_move.map(|mv| mv.flags)
+                        );
+                        assert_eq!(entry.score, score);
+                        assert_eq!(entry.depth, depth);
+                        assert_eq!(entry.node_type, node_type);
+                        assert_eq!(entry.age, u8::MAX);
+                    }
+                }
+            }
+        }
+        assert!(tt.probe(hash ^ 1).is_none());
+    }
+}
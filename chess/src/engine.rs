@@ -0,0 +1,656 @@
+use crate::{display_board, epd, interactive, nodes_per_second, selfplay, uci, JSON_FLAG};
+use chess_agents::{
+    mate_in, search_parallel, search_with_callback, search_with_limits, search_with_tt_size,
+    Evaluatable, SearchLimits, SearchProgress, SearchResult, DEFAULT_TT_SIZE_MB,
+};
+use chess_core::{format_san_line, generate_legal_moves, replay, Color, GameState, Pgn};
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// Runs `args[1]` if it names a command that needs the engine, returning
+/// false to leave any other command to `main`.
+pub fn run_command(args: &[String]) -> bool {
+    if args.len() > 1 && args[1] == "uci" {
+        let mut engine = uci::UciEngine::new();
+        engine.run();
+    } else if args.len() > 1 && args[1] == "eval" {
+        // Evaluate position
+        let state = if args.len() > 2 {
+            match GameState::from_fen(&args[2]) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("Error parsing FEN: {}", e);
+                    return true;
+                }
+            }
+        } else {
+            GameState::new()
+        };
+
+        display_board(&state);
+        let breakdown = state.evaluate_explained();
+        println!("{:<14}{:>7}{:>7}{:>7}", "Term", "White", "Black", "Net");
+        for (name, term) in breakdown.terms() {
+            println!(
+                "{:<14}{:>7}{:>7}{:>+7}",
+                name,
+                term.white,
+                term.black,
+                term.net()
+            );
+        }
+        println!();
+        println!("Evaluation: {} cp", state.evaluate());
+        println!(
+            "(from {}'s perspective)",
+            if state.turn == chess_core::Color::White {
+                "White"
+            } else {
+                "Black"
+            }
+        );
+        println!(
+            "Absolute eval: {} cp (+ = White, - = Black)",
+            state.evaluate_absolute()
+        );
+    } else if args.len() > 1 && args[1] == "search" {
+        // Search for best move
+        let (positional, options) = split_search_args(&args[2..]);
+        let (state, depth) = if !positional.is_empty() {
+            // Check if second arg is a number (depth) or FEN
+            if let Ok(d) = positional[0].parse::<u8>() {
+                (GameState::new(), d)
+            } else {
+                // Try to parse as FEN
+                match GameState::from_fen(positional[0]) {
+                    Ok(s) => {
+                        let d = if positional.len() > 1 {
+                            positional[1].parse().unwrap_or(6)
+                        } else {
+                            6
+                        };
+                        (s, d)
+                    }
+                    Err(e) => {
+                        eprintln!("Error parsing FEN: {}", e);
+                        return true;
+                    }
+                }
+            }
+        } else {
+            (GameState::new(), 6)
+        };
+        let depth = options.depth.unwrap_or(depth);
+        if !check_legal_position(&state) {
+            return true;
+        }
+
+        if !options.json {
+            println!("Position: {}", state.to_fen());
+            println!(
+                "Searching to depth {} (hash {} MB, {} threads)...",
+                depth,
+                options.hash_mb(),
+                options.threads()
+            );
+        }
+
+        let start = std::time::Instant::now();
+        let result = run_search(&state, SearchLimits::depth(depth), &options);
+        let elapsed = start.elapsed();
+
+        if options.json {
+            println!("{}", search_json(&state, &result, elapsed));
+        } else if let Some(best_move) = result.best_move {
+            println!("\nBest move: {}", best_move);
+            println!("PV: {}", format_san_line(&state, &result.pv));
+            println!("Score: {} cp", result.score);
+            println!("Depth: {}", result.depth);
+            println!("Nodes: {}", result.nodes);
+            println!("Time: {:.2}s", elapsed.as_secs_f64());
+            println!("NPS: {:.0}", result.nodes as f64 / elapsed.as_secs_f64());
+        } else {
+            println!("No legal moves available");
+        }
+    } else if args.len() > 1 && args[1] == "movetime" {
+        // Search with time limit
+        let (positional, options) = split_search_args(&args[2..]);
+        let (state, millis) = if !positional.is_empty() {
+            // Check if second arg is a number (time) or FEN
+            if let Ok(ms) = positional[0].parse::<u64>() {
+                (GameState::new(), ms)
+            } else {
+                // Try to parse as FEN
+                match GameState::from_fen(positional[0]) {
+                    Ok(s) => {
+                        let ms = if positional.len() > 1 {
+                            positional[1].parse().unwrap_or(1000)
+                        } else {
+                            1000
+                        };
+                        (s, ms)
+                    }
+                    Err(e) => {
+                        eprintln!("Error parsing FEN: {}", e);
+                        return true;
+                    }
+                }
+            }
+        } else {
+            (GameState::new(), 1000)
+        };
+        if !check_legal_position(&state) {
+            return true;
+        }
+
+        if !options.json {
+            println!("Position: {}", state.to_fen());
+            println!(
+                "Searching for {} ms (hash {} MB, {} threads)...",
+                millis,
+                options.hash_mb(),
+                options.threads()
+            );
+        }
+
+        let start = std::time::Instant::now();
+        let result = run_search(&state, SearchLimits::move_time(millis), &options);
+        let elapsed = start.elapsed();
+
+        if options.json {
+            println!("{}", search_json(&state, &result, elapsed));
+        } else if let Some(best_move) = result.best_move {
+            println!("\nBest move: {}", best_move);
+            println!("PV: {}", format_san_line(&state, &result.pv));
+            println!("Score: {} cp", result.score);
+            println!("Depth: {}", result.depth);
+            println!("Nodes: {}", result.nodes);
+            println!("Time: {:.2}s", elapsed.as_secs_f64());
+            println!("NPS: {:.0}", result.nodes as f64 / elapsed.as_secs_f64());
+            if result.stopped {
+                println!("(search stopped by time limit)");
+            }
+        } else {
+            println!("No legal moves available");
+        }
+    } else if args.len() > 1 && args[1] == "analyze" {
+        if args.len() < 3 {
+            println!("Usage: {} analyze <fen> [ms]", args[0]);
+            return true;
+        }
+
+        match GameState::from_fen(&args[2]) {
+            Ok(state) => {
+                if !check_legal_position(&state) {
+                    return true;
+                }
+                let millis = args.get(3).and_then(|ms| ms.parse().ok()).unwrap_or(3000);
+                analyze(&state, millis);
+            }
+            Err(e) => eprintln!("Error parsing FEN: {}", e),
+        }
+    } else if args.len() > 1 && args[1] == "pgn" {
+        let (positional, options) = split_search_args(&args[2..]);
+        let Some(path) = positional.first() else {
+            println!("Usage: {} pgn <file> [depth=<n>]", args[0]);
+            return true;
+        };
+        review_pgn(path, options.depth.unwrap_or(4));
+    } else if args.len() > 1 && args[1] == "epd" {
+        let Some(path) = args.get(2) else {
+            println!("Usage: {} epd <file> [ms]", args[0]);
+            return true;
+        };
+        let millis = args
+            .get(3)
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(epd::DEFAULT_MOVE_TIME_MS);
+        epd::run_epd_file(path, millis);
+    } else if args.len() > 1 && args[1] == "selfplay" {
+        let config = selfplay::SelfPlayConfig::from_args(&args[2..]);
+        if let Err(e) = selfplay::run_selfplay(&config, &mut io::stdout().lock()) {
+            eprintln!("Error writing self-play data: {}", e);
+        }
+    } else if args.len() > 1 && args[1] == "play" {
+        if args.len() > 2 && args[2] == "text" {
+            play_text_mode();
+        } else {
+            play_interactive();
+        }
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Help lines for the commands in `run_command`.
+pub fn print_commands() {
+    println!("  play                 - Play with interactive board (vim keys)");
+    println!("  play text            - Play with text input (e2e4 style)");
+    println!("  uci                  - Run in UCI mode for GUI compatibility");
+    println!("  eval [fen]           - Evaluate position");
+    println!("  search [depth|fen] [depth] - Search for best move");
+    println!("  movetime [ms|fen] [ms] - Search with time limit (ms)");
+    println!("    search/movetime accept key=value options in any order:");
+    println!("    depth=<n> hash=<mb> threads=<n>, and --json for a JSON object");
+    println!("  analyze <fen> [ms]   - Print each iteration's PV in SAN and the best line");
+    println!("  pgn <file> [depth=<n>] - Replay PGN games with static and search evals");
+    println!("  epd <file> [ms]      - Run an EPD test suite, checking bm/am moves");
+    println!("  selfplay [games] [ms] [seed=<n>] [depth=<n>] - Write self-play positions as CSV");
+    println!("    a seed without a depth searches to depth 3, so the run repeats exactly");
+}
+
+/// Optional `key=value` settings and flags for the `search` and
+/// `movetime` commands.
+#[derive(Debug, Default, PartialEq)]
+struct SearchArgs {
+    depth: Option<u8>,
+    hash_mb: Option<usize>,
+    threads: Option<usize>,
+    /// `--json`: print only the result, as one JSON object
+    json: bool,
+}
+
+impl SearchArgs {
+    fn hash_mb(&self) -> usize {
+        self.hash_mb.unwrap_or(DEFAULT_TT_SIZE_MB).max(1)
+    }
+
+    fn threads(&self) -> usize {
+        self.threads.unwrap_or(1).max(1)
+    }
+}
+
+/// Splits command arguments into positional values, `key=value` options and
+/// the `--json` flag. Unknown keys and unparsable values are ignored.
+fn split_search_args(args: &[String]) -> (Vec<&str>, SearchArgs) {
+    let mut positional = Vec::new();
+    let mut options = SearchArgs::default();
+
+    for arg in args {
+        if arg == JSON_FLAG {
+            options.json = true;
+            continue;
+        }
+        match arg.split_once('=') {
+            Some(("depth", value)) => options.depth = value.parse().ok(),
+            Some(("hash", value)) => options.hash_mb = value.parse().ok(),
+            Some(("threads", value)) => options.threads = value.parse().ok(),
+            Some(_) => {}
+            None => positional.push(arg.as_str()),
+        }
+    }
+
+    (positional, options)
+}
+
+/// Formats a search result as a single-line JSON object for scripts. Moves
+/// are in UCI notation, `score` is in centipawns from the side to move, and
+/// `mate` is the `#N` distance or null.
+fn search_json(state: &GameState, result: &SearchResult, elapsed: Duration) -> String {
+    let best_move = result
+        .best_move
+        .map_or_else(|| "null".to_string(), |mv| format!("\"{mv}\""));
+    let mate = mate_in(result.score, state).map_or_else(|| "null".to_string(), |m| m.to_string());
+    let pv: Vec<String> = result.pv.iter().map(|mv| format!("\"{mv}\"")).collect();
+    format!(
+        "{{\"bestmove\":{},\"score\":{},\"mate\":{},\"depth\":{},\"nodes\":{},\"time_ms\":{},\"nps\":{},\"pv\":[{}],\"stopped\":{}}}",
+        best_move,
+        result.score,
+        mate,
+        result.depth,
+        result.nodes,
+        elapsed.as_millis(),
+        nodes_per_second(result.nodes, elapsed),
+        pv.join(","),
+        result.stopped
+    )
+}
+
+fn run_search(state: &GameState, limits: SearchLimits, options: &SearchArgs) -> SearchResult {
+    if options.threads() > 1 {
+        search_parallel(state, limits, options.hash_mb(), options.threads())
+    } else {
+        search_with_tt_size(state, limits, options.hash_mb())
+    }
+}
+
+/// Prints an error and returns false if `state` cannot arise in a game,
+/// so commands refuse to search it.
+fn check_legal_position(state: &GameState) -> bool {
+    let legal = state.is_legal_position();
+    if !legal {
+        eprintln!(
+            "Illegal position: {} (needs one king per side, no pawns on the back ranks, \
+             and the side not to move must not be in check)",
+            state.to_fen()
+        );
+    }
+    legal
+}
+
+/// Formats a search score for display, showing mates as `#N` (negative when getting mated).
+fn format_score(score: i32, state: &GameState) -> String {
+    match mate_in(score, state) {
+        Some(moves) => format!("#{}", moves),
+        None => format!("{} cp", score),
+    }
+}
+
+fn analyze(state: &GameState, millis: u64) {
+    println!("Position: {}", state.to_fen());
+    println!("Analyzing for {} ms...\n", millis);
+
+    let root = state.clone();
+    let callback = Box::new(move |info: &SearchProgress| {
+        println!(
+            "depth {:>2}  score {:>8}  nodes {:>9}  time {:>6} ms  pv {}",
+            info.depth,
+            format_score(info.score, &root),
+            info.nodes,
+            info.time_ms,
+            format_san_line(&root, &info.pv)
+        );
+    });
+
+    let result = search_with_callback(state, SearchLimits::move_time(millis), callback);
+
+    if result.best_move.is_none() {
+        println!("No legal moves available");
+        return;
+    }
+
+    println!("\nBest line: {}", format_san_line(state, &result.pv));
+    println!(
+        "Evaluation: {} (from {}'s perspective)",
+        format_score(result.score, state),
+        state.turn
+    );
+}
+
+/// Formats a side-to-move score from White's perspective, so evaluations
+/// along a game can be compared ply by ply.
+fn format_white_score(score: i32, state: &GameState) -> String {
+    let sign = if state.turn == Color::White { 1 } else { -1 };
+    match mate_in(score, state) {
+        Some(moves) => format!("#{}", sign * moves),
+        None => format!("{:+} cp", sign * score),
+    }
+}
+
+/// Replays every game in a PGN file, printing each move in SAN with a static
+/// eval and a shallow search eval of the position after it.
+fn review_pgn(path: &str, depth: u8) {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", path, e);
+            return;
+        }
+    };
+
+    let games = Pgn::from_pgn_all(&text);
+    if games.is_empty() {
+        println!("No games found in {}", path);
+        return;
+    }
+
+    for (index, game) in games.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        let pgn = match game {
+            Ok(pgn) => pgn,
+            Err(e) => {
+                eprintln!("Game {}: {}", index + 1, e);
+                continue;
+            }
+        };
+
+        println!(
+            "Game {}: {} vs {}",
+            index + 1,
+            pgn.tag("White").unwrap_or("?"),
+            pgn.tag("Black").unwrap_or("?")
+        );
+        if pgn.tag("FEN").is_some() {
+            println!("Start: {}", pgn.start.to_fen());
+        }
+        println!(
+            "Evaluations from White's perspective (search depth {})",
+            depth
+        );
+
+        let mut state = pgn.start.clone();
+        for &mv in &pgn.moves {
+            let number = if state.turn == Color::White {
+                format!("{}.", state.fullmove_number)
+            } else {
+                format!("{}...", state.fullmove_number)
+            };
+            let san = state.move_to_san(mv);
+            state = state.apply_move(mv);
+
+            let search_eval = if !state.has_legal_move() {
+                if state.is_in_check() {
+                    "checkmate".to_string()
+                } else {
+                    "stalemate".to_string()
+                }
+            } else {
+                let result = search_with_limits(&state, SearchLimits::depth(depth));
+                format_white_score(result.score, &state)
+            };
+
+            println!(
+                "{:>6} {:<8} static {:>+6} cp  search {:>9}",
+                number,
+                san,
+                state.evaluate_absolute(),
+                search_eval
+            );
+        }
+
+        if let Some(result) = pgn.tag("Result") {
+            println!("Result: {}", result);
+        }
+    }
+}
+
+fn play_interactive() {
+    let mut game = interactive::InteractiveGame::new();
+    if let Err(e) = game.run() {
+        eprintln!("Error: {}", e);
+    }
+}
+
+fn play_text_mode() {
+    let mut state = GameState::new();
+    let mut move_history = Vec::new();
+
+    println!("Chess Engine - Text Mode");
+    println!("Enter moves in algebraic notation (e.g., e2e4, e7e8q for promotion)");
+    println!("Commands: 'quit', 'undo', 'new', 'help'");
+    println!();
+
+    loop {
+        display_board(&state);
+
+        // Check for game over
+        let legal_moves = generate_legal_moves(&state);
+        if legal_moves.is_empty() {
+            if state.is_in_check() {
+                println!(
+                    "Checkmate! {} wins!",
+                    if state.turn == Color::White {
+                        "Black"
+                    } else {
+                        "White"
+                    }
+                );
+            } else {
+                println!("Stalemate!");
+            }
+            break;
+        }
+
+        if state.is_in_check() {
+            println!("Check!");
+        }
+
+        // Get player move
+        print!("Your move: ");
+        io::stdout().flush().unwrap();
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).unwrap();
+        let input = input.trim();
+
+        match input {
+            "quit" => break,
+            "help" => {
+                println!("Enter moves like 'e2e4' or 'e7e8q' (for promotion to queen)");
+                println!("Commands: quit, undo, new, help");
+                continue;
+            }
+            "new" => {
+                state = GameState::new();
+                move_history.clear();
+                println!("New game started!");
+                continue;
+            }
+            "undo" => {
+                if move_history.len() >= 2 {
+                    move_history.pop();
+                    move_history.pop();
+                    state = replay(&move_history);
+                    println!("Undid last move");
+                } else {
+                    println!("Nothing to undo");
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        // Parse and apply move
+        match state.parse_uci(input) {
+            Some(mv) => {
+                state = state.apply_move(mv);
+                move_history.push(mv);
+
+                // Engine's turn
+                display_board(&state);
+                println!("Engine thinking...");
+
+                let result = search_with_limits(&state, SearchLimits::move_time(2000));
+
+                if let Some(engine_move) = result.best_move {
+                    println!("Engine plays: {}", engine_move);
+                    state = state.apply_move(engine_move);
+                    move_history.push(engine_move);
+                }
+            }
+            None => {
+                println!("Invalid move. Try again (e.g., e2e4)");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_split_search_args_extracts_options() {
+        let args = to_args(&[
+            "threads=4",
+            "8/8/8/8/8/8/8/K6k w - - 0 1",
+            "hash=64",
+            "--json",
+            "depth=8",
+        ]);
+        let (positional, options) = split_search_args(&args);
+
+        assert_eq!(positional, vec!["8/8/8/8/8/8/8/K6k w - - 0 1"]);
+        assert_eq!(
+            options,
+            SearchArgs {
+                depth: Some(8),
+                hash_mb: Some(64),
+                threads: Some(4),
+                json: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_format_score_shows_mates() {
+        let state = GameState::new();
+        assert_eq!(format_score(42, &state), "42 cp");
+        assert_eq!(format_score(100_000 - 3, &state), "#3");
+        assert_eq!(format_score(-(100_000 - 3), &state), "#-2");
+    }
+
+    #[test]
+    fn test_search_json() {
+        let state = GameState::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let result = search_with_limits(&state, SearchLimits::depth(2));
+        let json = search_json(&state, &result, Duration::from_millis(500));
+
+        assert_eq!(
+            json,
+            format!(
+                "{{\"bestmove\":\"a1a8\",\"score\":{},\"mate\":1,\"depth\":2,\"nodes\":{},\"time_ms\":500,\"nps\":{},\"pv\":[\"a1a8\"],\"stopped\":false}}",
+                result.score,
+                result.nodes,
+                result.nodes * 2
+            )
+        );
+
+        // Neither a move nor a mate: both come out as null
+        let no_moves = SearchResult {
+            best_move: None,
+            score: 0,
+            pv: Vec::new(),
+            ..result
+        };
+        assert_eq!(
+            search_json(&state, &no_moves, Duration::ZERO),
+            format!(
+                "{{\"bestmove\":null,\"score\":0,\"mate\":null,\"depth\":2,\"nodes\":{},\"time_ms\":0,\"nps\":0,\"pv\":[],\"stopped\":false}}",
+                result.nodes
+            )
+        );
+
+        // A side that is already mated has no move and a mate distance of 0
+        let mated = GameState::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+        let result = search_with_limits(&mated, SearchLimits::depth(1));
+        assert_eq!(
+            search_json(&mated, &result, Duration::from_millis(1)),
+            format!(
+                "{{\"bestmove\":null,\"score\":{},\"mate\":0,\"depth\":{},\"nodes\":{},\"time_ms\":1,\"nps\":{},\"pv\":[],\"stopped\":false}}",
+                result.score,
+                result.depth,
+                result.nodes,
+                result.nodes * 1000
+            )
+        );
+    }
+
+    #[test]
+    fn test_split_search_args_defaults() {
+        let args = to_args(&["5", "hash=lots", "color=blue"]);
+        let (positional, options) = split_search_args(&args);
+
+        assert_eq!(positional, vec!["5"]);
+        assert_eq!(options, SearchArgs::default());
+        assert_eq!(options.hash_mb(), DEFAULT_TT_SIZE_MB);
+        assert_eq!(options.threads(), 1);
+    }
+}
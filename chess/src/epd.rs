@@ -0,0 +1,184 @@
+use chess_agents::{search_with_limits, SearchLimits};
+use chess_core::{EpdRecord, Move};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Default search time per position for the `epd` command
+pub const DEFAULT_MOVE_TIME_MS: u64 = 1000;
+
+/// A suite position whose search did not find a `bm` move or played an
+/// `am` move.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpdFailure {
+    pub id: String,
+    pub fen: String,
+    /// The `bm` moves in SAN, or the `am` moves prefixed with "not"
+    pub expected: String,
+    /// The engine's move in SAN, or "none" if it found no move
+    pub found: String,
+}
+
+/// Totals for one run over an EPD suite.
+#[derive(Debug, Clone, Default)]
+pub struct EpdSummary {
+    pub solved: usize,
+    /// Positions with a `bm` or `am` operation; others are skipped
+    pub total: usize,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub failures: Vec<EpdFailure>,
+}
+
+/// Searches every record with a `bm` or `am` operation. A position is
+/// solved when the engine's move is one of the `bm` moves and none of the
+/// `am` moves.
+pub fn run_suite(records: &[EpdRecord], limits: SearchLimits) -> EpdSummary {
+    let mut summary = EpdSummary::default();
+    let start = Instant::now();
+
+    for (index, record) in records.iter().enumerate() {
+        let best = record.best_moves();
+        let avoid = record.avoid_moves();
+        if best.is_empty() && avoid.is_empty() {
+            continue;
+        }
+
+        let result = search_with_limits(&record.state, limits.clone());
+        summary.total += 1;
+        summary.nodes += result.nodes;
+
+        let solved = result
+            .best_move
+            .is_some_and(|mv| (best.is_empty() || best.contains(&mv)) && !avoid.contains(&mv));
+        if solved {
+            summary.solved += 1;
+            continue;
+        }
+
+        let san_list = |moves: &[Move]| {
+            moves
+                .iter()
+                .map(|&mv| record.state.move_to_san(mv))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let expected = if best.is_empty() {
+            format!("not {}", san_list(&avoid))
+        } else {
+            san_list(&best)
+        };
+        summary.failures.push(EpdFailure {
+            id: record
+                .id()
+                .map_or_else(|| format!("#{}", index + 1), str::to_string),
+            fen: record.state.to_fen(),
+            expected,
+            found: result
+                .best_move
+                .map_or_else(|| "none".to_string(), |mv| record.state.move_to_san(mv)),
+        });
+    }
+
+    summary.elapsed = start.elapsed();
+    summary
+}
+
+/// Prints the failed positions followed by the
+/// `solved X / Y, N nodes, time` summary line.
+pub fn print_summary(summary: &EpdSummary, out: &mut impl Write) -> io::Result<()> {
+    for failure in &summary.failures {
+        writeln!(
+            out,
+            "FAILED {}: expected {}, found {} ({})",
+            failure.id, failure.expected, failure.found, failure.fen
+        )?;
+    }
+    writeln!(
+        out,
+        "solved {} / {}, {} nodes, {:.2}s",
+        summary.solved,
+        summary.total,
+        summary.nodes,
+        summary.elapsed.as_secs_f64()
+    )
+}
+
+/// Loads the EPD file at `path` and runs it with `millis` per position.
+/// Lines that fail to parse are reported and skipped.
+pub fn run_epd_file(path: &str, millis: u64) {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", path, e);
+            return;
+        }
+    };
+
+    let mut records = Vec::new();
+    for (index, record) in EpdRecord::parse_all(&text).into_iter().enumerate() {
+        match record {
+            Ok(record) => records.push(record),
+            Err(e) => eprintln!("Position {}: {}", index + 1, e),
+        }
+    }
+
+    println!(
+        "Running {} positions at {} ms per position...",
+        records.len(),
+        millis
+    );
+    let summary = run_suite(&records, SearchLimits::move_time(millis));
+    if let Err(e) = print_summary(&summary, &mut io::stdout().lock()) {
+        eprintln!("Error writing summary: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATE_IN_ONE: &str = include_str!("../suites/mate_in_one.epd");
+
+    fn parse(text: &str) -> Vec<EpdRecord> {
+        EpdRecord::parse_all(text)
+            .into_iter()
+            .map(|record| record.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_run_suite_solves_mate_in_one() {
+        let records = parse(MATE_IN_ONE);
+        let summary = run_suite(&records, SearchLimits::depth(2));
+
+        assert_eq!(summary.total, records.len());
+        assert_eq!(summary.solved, summary.total, "{:?}", summary.failures);
+        assert!(summary.nodes > 0);
+
+        let mut out = Vec::new();
+        print_summary(&summary, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with(&format!("solved {0} / {0}, ", records.len())));
+    }
+
+    #[test]
+    fn test_run_suite_reports_failures() {
+        // The mate is the only move the `am` operation forbids
+        let records = parse(
+            "6k1/5ppp/8/8/8/8/8/R5K1 w - - am Ra8#; id \"avoid mate\";\n\
+             6k1/5ppp/8/8/8/8/8/R5K1 w - - c0 \"no bm or am\";",
+        );
+        let summary = run_suite(&records, SearchLimits::depth(2));
+
+        assert_eq!((summary.solved, summary.total), (0, 1));
+        assert_eq!(
+            summary.failures,
+            vec![EpdFailure {
+                id: "avoid mate".to_string(),
+                fen: "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1".to_string(),
+                expected: "not Ra8#".to_string(),
+                found: "Ra8#".to_string(),
+            }]
+        );
+    }
+}
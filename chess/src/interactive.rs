@@ -1,5 +1,8 @@
 use chess_agents::{search_with_limits, SearchLimits};
-use chess_core::{generate_legal_moves, Color, File, GameState, Move, PieceType, Rank, Square};
+use chess_core::{
+    generate_legal_moves, moves_to_san, replay, Color, File, Game, GameState, Move, PieceType,
+    Rank, Square,
+};
 use crossterm::{
     cursor::{Hide, MoveTo, Show},
     event::{self, Event, KeyCode, KeyEvent},
@@ -16,6 +19,8 @@ pub struct InteractiveGame {
     legal_moves_for_selected: Vec<Move>,
     message: String,
     move_history: Vec<Move>,
+    /// Text typed after `:`, while a move is being entered by name
+    command: Option<String>,
 }
 
 impl InteractiveGame {
@@ -27,6 +32,7 @@ impl InteractiveGame {
             legal_moves_for_selected: Vec::new(),
             message: String::from("Use hjkl to move, Enter to select/move, q to quit"),
             move_history: Vec::new(),
+            command: None,
         }
     }
 
@@ -53,31 +59,26 @@ impl InteractiveGame {
             self.draw_board()?;
 
             // Check for game over
-            let legal_moves = generate_legal_moves(&self.state);
-            if legal_moves.is_empty() {
-                if self.state.is_in_check() {
-                    self.message = format!(
-                        "Checkmate! {} wins!",
-                        if self.state.turn == Color::White {
-                            "Black"
-                        } else {
-                            "White"
-                        }
-                    );
-                } else {
-                    self.message = String::from("Stalemate!");
-                }
+            if let Some(banner) = result_banner(&self.game()) {
+                self.message = format!("{banner} Press any key.");
                 self.draw_board()?;
                 event::read()?; // Wait for any key
                 break;
             }
 
-            if self.state.is_in_check() {
+            if self.state.is_in_check() && self.command.is_none() {
                 self.message = String::from("Check!");
             }
 
             // Handle input
             if let Event::Key(KeyEvent { code, .. }) = event::read()? {
+                if self.command.is_some() {
+                    if self.handle_command_key(code) {
+                        self.engine_move()?;
+                    }
+                    continue;
+                }
+
                 match code {
                     KeyCode::Char('q') | KeyCode::Esc => break,
                     KeyCode::Char('h') | KeyCode::Left => self.move_cursor(-1, 0),
@@ -92,6 +93,10 @@ impl InteractiveGame {
                     }
                     KeyCode::Char('u') => self.undo_move(),
                     KeyCode::Char('n') => self.new_game(),
+                    KeyCode::Char(':') => {
+                        self.command = Some(String::new());
+                        self.message = String::from(":");
+                    }
                     _ => {}
                 }
             }
@@ -100,6 +105,15 @@ impl InteractiveGame {
         Ok(())
     }
 
+    /// The game so far, with the history needed to spot repetitions.
+    fn game(&self) -> Game {
+        let mut game = Game::new();
+        for &mv in &self.move_history {
+            game.apply_move(mv);
+        }
+        game
+    }
+
     fn move_cursor(&mut self, dx: i8, dy: i8) {
         let new_file = self.cursor_pos.0 as i8 + dx;
         let new_rank = self.cursor_pos.1 as i8 + dy;
@@ -138,11 +152,7 @@ impl InteractiveGame {
                     *mv
                 };
 
-                self.state = self.state.apply_move(mv);
-                self.move_history.push(mv);
-                self.selected_square = None;
-                self.legal_moves_for_selected.clear();
-                self.message = format!("Moved: {}", mv);
+                self.play_move(mv);
                 return true;
             } else {
                 // Clicked somewhere else, deselect
@@ -182,6 +192,53 @@ impl InteractiveGame {
         false
     }
 
+    /// Handles a key while a typed move is being entered. Enter plays the
+    /// move if it is legal and returns true; Esc, or Backspace on an empty
+    /// line, leaves command mode.
+    fn handle_command_key(&mut self, code: KeyCode) -> bool {
+        let Some(command) = &mut self.command else {
+            return false;
+        };
+
+        match code {
+            KeyCode::Char(c) => command.push(c),
+            KeyCode::Backspace if !command.is_empty() => {
+                command.pop();
+            }
+            KeyCode::Backspace | KeyCode::Esc => {
+                self.command = None;
+                self.message.clear();
+                return false;
+            }
+            KeyCode::Enter => {
+                let text = self.command.take().unwrap_or_default();
+                match parse_typed_move(&self.state, &text) {
+                    Some(mv) => {
+                        self.play_move(mv);
+                        return true;
+                    }
+                    None => {
+                        self.message = format!("Not a legal move: {}", text.trim());
+                        return false;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        self.message = format!(":{command}");
+        false
+    }
+
+    /// Plays the player's move and clears any selection.
+    fn play_move(&mut self, mv: Move) {
+        self.state = self.state.apply_move(mv);
+        self.move_history.push(mv);
+        self.selected_square = None;
+        self.legal_moves_for_selected.clear();
+        self.message = format!("Moved: {}", mv);
+    }
+
     fn engine_move(&mut self) -> io::Result<()> {
         self.message = String::from("Engine thinking...");
         self.draw_board()?;
@@ -204,10 +261,7 @@ impl InteractiveGame {
             self.move_history.pop();
 
             // Rebuild position
-            self.state = GameState::new();
-            for mv in &self.move_history {
-                self.state = self.state.apply_move(*mv);
-            }
+            self.state = replay(&self.move_history);
 
             self.selected_square = None;
             self.legal_moves_for_selected.clear();
@@ -232,10 +286,11 @@ impl InteractiveGame {
 
         // Title
         println!("Chess - Interactive Mode (vim keys: hjkl)\r");
-        println!("Commands: Enter=select/move, u=undo, n=new, q=quit\r");
+        println!("Commands: Enter=select/move, :=type a move, u=undo, n=new, q=quit\r");
         println!("\r");
 
-        // Board with coordinates
+        // Board with coordinates, and the latest moves beside it
+        let log = move_log(&self.move_history, 8);
         println!("  a b c d e f g h  \r");
         println!(" ┌─────────────────┐\r");
 
@@ -265,20 +320,7 @@ impl InteractiveGame {
 
                 // Draw piece or empty square
                 if let Some(piece) = self.state.board.piece_at(square) {
-                    let symbol = match (piece.piece_type, piece.color) {
-                        (PieceType::King, Color::White) => '♔',
-                        (PieceType::Queen, Color::White) => '♕',
-                        (PieceType::Rook, Color::White) => '♖',
-                        (PieceType::Bishop, Color::White) => '♗',
-                        (PieceType::Knight, Color::White) => '♘',
-                        (PieceType::Pawn, Color::White) => '♙',
-                        (PieceType::King, Color::Black) => '♚',
-                        (PieceType::Queen, Color::Black) => '♛',
-                        (PieceType::Rook, Color::Black) => '♜',
-                        (PieceType::Bishop, Color::Black) => '♝',
-                        (PieceType::Knight, Color::Black) => '♞',
-                        (PieceType::Pawn, Color::Black) => '♟',
-                    };
+                    let symbol = piece.unicode();
 
                     if piece.color == Color::White {
                         stdout.execute(SetForegroundColor(TermColor::White))?;
@@ -294,7 +336,8 @@ impl InteractiveGame {
                 stdout.execute(ResetColor)?;
             }
 
-            println!("│{}\r", rank_idx + 1);
+            let log_line = log.get(7 - rank_idx as usize).map_or("", String::as_str);
+            println!("│{}   {:<24}\r", rank_idx + 1, log_line);
         }
 
         println!(" └─────────────────┘\r");
@@ -312,11 +355,99 @@ impl InteractiveGame {
             self.state.fullmove_number
         );
 
-        // Status message
+        // Status message, padded to wipe out a longer previous one
         println!("\r");
-        println!("{}\r", self.message);
+        println!("{:<60}\r", self.message);
 
         stdout.flush()?;
         Ok(())
     }
 }
+
+/// Reads a typed move in UCI (`e2e4`, `e7e8q`) or SAN (`Nf3`, `O-O`) form,
+/// returning it only if it is legal in `state`.
+fn parse_typed_move(state: &GameState, text: &str) -> Option<Move> {
+    let text = text.trim();
+    state.parse_uci(text).or_else(|| state.parse_san(text))
+}
+
+/// The last `rows` full moves of a game from the starting position in SAN,
+/// one numbered move pair per line.
+fn move_log(moves: &[Move], rows: usize) -> Vec<String> {
+    let sans = moves_to_san(&GameState::new(), moves);
+    let lines: Vec<String> = sans
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| format!("{}. {}", i + 1, pair.join(" ")))
+        .collect();
+    lines[lines.len().saturating_sub(rows)..].to_vec()
+}
+
+/// A final-result banner once the game is over, naming the winner of a
+/// checkmate or the rule that drew the game.
+fn result_banner(game: &Game) -> Option<String> {
+    game.result()
+        .map(|result| format!("{result} ({}).", result.pgn()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_from(fen: &str) -> Game {
+        Game::from_state(GameState::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn test_result_banner() {
+        assert_eq!(result_banner(&Game::new()), None);
+        assert_eq!(
+            result_banner(&game_from("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1")).as_deref(),
+            Some("White wins by checkmate (1-0).")
+        );
+        assert_eq!(
+            result_banner(&game_from("6k1/8/8/8/8/8/5PPP/r5K1 w - - 0 1")).as_deref(),
+            Some("Black wins by checkmate (0-1).")
+        );
+        assert_eq!(
+            result_banner(&game_from("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1")).as_deref(),
+            Some("Draw by stalemate (1/2-1/2).")
+        );
+    }
+
+    #[test]
+    fn test_parse_typed_move() {
+        let sq = |name: &str| name.parse::<Square>().unwrap();
+        let state = GameState::new();
+        assert_eq!(
+            parse_typed_move(&state, "e2e4"),
+            Some(Move::new(sq("e2"), sq("e4")))
+        );
+        assert_eq!(
+            parse_typed_move(&state, " Nf3 "),
+            Some(Move::new(sq("g1"), sq("f3")))
+        );
+        assert_eq!(parse_typed_move(&state, "e2e5"), None);
+        assert_eq!(parse_typed_move(&state, "Ke2"), None);
+        assert_eq!(parse_typed_move(&state, ""), None);
+
+        let state = GameState::from_fen("4k3/P7/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let promotion = Some(Move::new_promotion(sq("a7"), sq("a8"), PieceType::Knight));
+        assert_eq!(parse_typed_move(&state, "a7a8n"), promotion);
+        assert_eq!(parse_typed_move(&state, "a8=N"), promotion);
+        assert_eq!(
+            parse_typed_move(&state, "O-O"),
+            Some(Move::new(sq("e1"), sq("g1")))
+        );
+    }
+
+    #[test]
+    fn test_move_log_shows_latest_pairs() {
+        let moves: Vec<Move> = ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5"]
+            .iter()
+            .map(|uci| Move::new(uci[..2].parse().unwrap(), uci[2..].parse().unwrap()))
+            .collect();
+        assert_eq!(move_log(&moves, 8), ["1. e4 e5", "2. Nf3 Nc6", "3. Bb5"]);
+        assert_eq!(move_log(&moves, 1), ["3. Bb5"]);
+    }
+}
@@ -1,13 +1,22 @@
+// Everything that searches or evaluates needs `chess_agents`, which the
+// default `search` feature pulls in. Without it only the rules commands,
+// `perft` and `fen`, are built.
+#[cfg(feature = "search")]
+mod engine;
+#[cfg(feature = "search")]
+mod epd;
+#[cfg(feature = "search")]
 mod interactive;
+#[cfg(feature = "search")]
+mod selfplay;
+#[cfg(feature = "search")]
 mod uci;
 
-use chess_agents::{iterative_deepening, search, search_with_limits, Evaluatable, SearchLimits};
 use chess_core::{
-    generate_legal_moves, perft, perft_divide, positions, Color, File, GameState, Move, PieceType,
-    Rank, Square,
+    perft, perft_detailed, perft_divide, positions, Color, File, GameState, Rank, Square,
 };
 use std::env;
-use std::io::{self, Write};
+use std::time::Duration;
 
 fn display_board(state: &GameState) {
     println!("\n  a b c d e f g h");
@@ -22,20 +31,7 @@ fn display_board(state: &GameState) {
             let square = Square::new(file, rank);
 
             if let Some(piece) = state.board.piece_at(square) {
-                let symbol = match (piece.piece_type, piece.color) {
-                    (PieceType::King, Color::White) => '♔',
-                    (PieceType::Queen, Color::White) => '♕',
-                    (PieceType::Rook, Color::White) => '♖',
-                    (PieceType::Bishop, Color::White) => '♗',
-                    (PieceType::Knight, Color::White) => '♘',
-                    (PieceType::Pawn, Color::White) => '♙',
-                    (PieceType::King, Color::Black) => '♚',
-                    (PieceType::Queen, Color::Black) => '♛',
-                    (PieceType::Rook, Color::Black) => '♜',
-                    (PieceType::Bishop, Color::Black) => '♝',
-                    (PieceType::Knight, Color::Black) => '♞',
-                    (PieceType::Pawn, Color::Black) => '♟',
-                };
+                let symbol = piece.unicode();
                 print!("{} ", symbol);
             } else {
                 print!(". ");
@@ -82,165 +78,83 @@ fn display_board(state: &GameState) {
     println!("Move {}", state.fullmove_number);
 }
 
-fn parse_move(state: &GameState, move_str: &str) -> Option<Move> {
-    // Try to parse algebraic notation (e2e4, e7e8q)
-    if move_str.len() >= 4 {
-        let from_file = File::from_char(move_str.chars().nth(0)?)?;
-        let from_rank = Rank::from_char(move_str.chars().nth(1)?)?;
-        let to_file = File::from_char(move_str.chars().nth(2)?)?;
-        let to_rank = Rank::from_char(move_str.chars().nth(3)?)?;
-
-        let from = Square::new(from_file, from_rank);
-        let to = Square::new(to_file, to_rank);
-
-        // Check for promotion
-        let promotion = if move_str.len() > 4 {
-            match move_str.chars().nth(4)? {
-                'q' | 'Q' => Some(PieceType::Queen),
-                'r' | 'R' => Some(PieceType::Rook),
-                'b' | 'B' => Some(PieceType::Bishop),
-                'n' | 'N' => Some(PieceType::Knight),
-                _ => None,
-            }
-        } else {
-            None
-        };
-
-        let mv = if promotion.is_some() {
-            Move::new_promotion(from, to, promotion.unwrap())
-        } else {
-            Move::new(from, to)
-        };
+/// Flag that switches `search`, `movetime` and `perft` to JSON output
+const JSON_FLAG: &str = "--json";
 
-        // Verify it's legal
-        let legal_moves = generate_legal_moves(state);
-        if legal_moves.iter().any(|&legal_mv| legal_mv == mv) {
-            Some(mv)
-        } else {
-            None
-        }
+/// Nodes per second, or 0 when no time was measured.
+fn nodes_per_second(nodes: u64, elapsed: Duration) -> u64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 {
+        (nodes as f64 / secs) as u64
     } else {
-        None
+        0
     }
 }
 
-fn play_interactive() {
-    let mut game = interactive::InteractiveGame::new();
-    if let Err(e) = game.run() {
-        eprintln!("Error: {}", e);
-    }
+/// Formats a perft count as a single-line JSON object for scripts.
+fn perft_json(depth: u8, nodes: u64, elapsed: Duration) -> String {
+    format!(
+        "{{\"depth\":{},\"nodes\":{},\"time_ms\":{},\"nps\":{}}}",
+        depth,
+        nodes,
+        elapsed.as_millis(),
+        nodes_per_second(nodes, elapsed)
+    )
 }
 
-fn play_text_mode() {
-    let mut state = GameState::new();
-    let mut move_history = Vec::new();
-
-    println!("Chess Engine - Text Mode");
-    println!("Enter moves in algebraic notation (e.g., e2e4, e7e8q for promotion)");
-    println!("Commands: 'quit', 'undo', 'new', 'help'");
-    println!();
-
-    loop {
-        display_board(&state);
-
-        // Check for game over
-        let legal_moves = generate_legal_moves(&state);
-        if legal_moves.is_empty() {
-            if state.is_in_check() {
-                println!(
-                    "Checkmate! {} wins!",
-                    if state.turn == Color::White {
-                        "Black"
-                    } else {
-                        "White"
-                    }
-                );
-            } else {
-                println!("Stalemate!");
-            }
-            break;
-        }
-
-        if state.is_in_check() {
-            println!("Check!");
-        }
-
-        // Get player move
-        print!("Your move: ");
-        io::stdout().flush().unwrap();
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        let input = input.trim();
-
-        match input {
-            "quit" => break,
-            "help" => {
-                println!("Enter moves like 'e2e4' or 'e7e8q' (for promotion to queen)");
-                println!("Commands: quit, undo, new, help");
-                continue;
-            }
-            "new" => {
-                state = GameState::new();
-                move_history.clear();
-                println!("New game started!");
-                continue;
-            }
-            "undo" => {
-                if move_history.len() >= 2 {
-                    move_history.pop();
-                    move_history.pop();
-                    state = GameState::new();
-                    for mv in &move_history {
-                        state = state.apply_move(*mv);
-                    }
-                    println!("Undid last move");
-                } else {
-                    println!("Nothing to undo");
-                }
+/// Compares `perft_detailed` against the published breakdown of every
+/// suite position up to `max_depth`. Returns true if every count matches.
+fn run_perft_suite(max_depth: u8) -> bool {
+    let mut all_passed = true;
+
+    for (name, fen, table) in chess_core::perft::positions::DETAILED_SUITE {
+        let state = GameState::from_fen(fen).expect("suite FEN is valid");
+        for (depth, expected) in table.iter().filter(|(depth, _)| *depth <= max_depth) {
+            let actual = perft_detailed(&state, *depth);
+            if actual == *expected {
+                println!("{} perft({}): ok ({} nodes)", name, depth, actual.nodes);
                 continue;
             }
-            _ => {}
-        }
-
-        // Parse and apply move
-        match parse_move(&state, input) {
-            Some(mv) => {
-                state = state.apply_move(mv);
-                move_history.push(mv);
-
-                // Engine's turn
-                display_board(&state);
-                println!("Engine thinking...");
-
-                let result = search_with_limits(&state, SearchLimits::move_time(2000));
 
-                if let Some(engine_move) = result.best_move {
-                    println!("Engine plays: {}", engine_move);
-                    state = state.apply_move(engine_move);
-                    move_history.push(engine_move);
+            all_passed = false;
+            println!("{} perft({}): MISMATCH", name, depth);
+            for ((field, want), (_, got)) in expected.fields().iter().zip(actual.fields()) {
+                if *want != got {
+                    println!("  {}: expected {}, got {}", field, want, got);
                 }
             }
-            None => {
-                println!("Invalid move. Try again (e.g., e2e4)");
-            }
         }
     }
+
+    all_passed
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    // Check if running in UCI mode
-    if args.len() > 1 && args[1] == "uci" {
-        let mut engine = uci::UciEngine::new();
-        engine.run();
+    #[cfg(feature = "search")]
+    if engine::run_command(&args) {
         return;
     }
 
     if args.len() > 1 && args[1] == "perft" {
+        let json = args.iter().any(|arg| arg == JSON_FLAG);
+        let args: Vec<String> = args
+            .iter()
+            .filter(|arg| *arg != JSON_FLAG)
+            .cloned()
+            .collect();
         if args.len() < 3 {
-            println!("Usage: {} perft <depth> [fen]", args[0]);
+            println!("Usage: {} perft <depth> [fen] [--json]", args[0]);
+            println!("       {} perft suite [max_depth]", args[0]);
+            return;
+        }
+
+        if args[2] == "suite" {
+            let max_depth = args.get(3).and_then(|s| s.parse().ok()).unwrap_or(3);
+            if !run_perft_suite(max_depth) {
+                std::process::exit(1);
+            }
             return;
         }
 
@@ -259,6 +173,13 @@ fn main() {
             GameState::new()
         };
 
+        if json {
+            let start = std::time::Instant::now();
+            let nodes = perft(&state, depth);
+            println!("{}", perft_json(depth, nodes, start.elapsed()));
+            return;
+        }
+
         println!("Running perft({})...", depth);
         println!("Position: {}", state.to_fen());
 
@@ -297,148 +218,49 @@ fn main() {
             }
             Err(e) => eprintln!("Error parsing FEN: {}", e),
         }
-    } else if args.len() > 1 && args[1] == "eval" {
-        // Evaluate position
-        let state = if args.len() > 2 {
-            match GameState::from_fen(&args[2]) {
-                Ok(s) => s,
-                Err(e) => {
-                    eprintln!("Error parsing FEN: {}", e);
-                    return;
-                }
-            }
-        } else {
-            GameState::new()
-        };
-
-        display_board(&state);
-        println!("Evaluation: {} cp", state.evaluate());
-        println!(
-            "(from {}'s perspective)",
-            if state.turn == chess_core::Color::White {
-                "White"
-            } else {
-                "Black"
-            }
-        );
-        println!(
-            "Absolute eval: {} cp (+ = White, - = Black)",
-            state.evaluate_absolute()
-        );
-    } else if args.len() > 1 && args[1] == "search" {
-        // Search for best move
-        let (state, depth) = if args.len() > 2 {
-            // Check if second arg is a number (depth) or FEN
-            if let Ok(d) = args[2].parse::<u8>() {
-                (GameState::new(), d)
-            } else {
-                // Try to parse as FEN
-                match GameState::from_fen(&args[2]) {
-                    Ok(s) => {
-                        let d = if args.len() > 3 {
-                            args[3].parse().unwrap_or(6)
-                        } else {
-                            6
-                        };
-                        (s, d)
-                    }
-                    Err(e) => {
-                        eprintln!("Error parsing FEN: {}", e);
-                        return;
-                    }
-                }
-            }
-        } else {
-            (GameState::new(), 6)
-        };
-
-        println!("Position: {}", state.to_fen());
-        println!("Searching to depth {}...", depth);
-
-        let start = std::time::Instant::now();
-        let result = if depth > 1 {
-            iterative_deepening(&state, depth)
-        } else {
-            search(&state, depth)
-        };
-        let elapsed = start.elapsed();
-
-        if let Some(best_move) = result.best_move {
-            println!("\nBest move: {}", best_move);
-            println!("Score: {} cp", result.score);
-            println!("Depth: {}", result.depth);
-            println!("Nodes: {}", result.nodes);
-            println!("Time: {:.2}s", elapsed.as_secs_f64());
-            println!("NPS: {:.0}", result.nodes as f64 / elapsed.as_secs_f64());
-        } else {
-            println!("No legal moves available");
-        }
-    } else if args.len() > 1 && args[1] == "movetime" {
-        // Search with time limit
-        let (state, millis) = if args.len() > 2 {
-            // Check if second arg is a number (time) or FEN
-            if let Ok(ms) = args[2].parse::<u64>() {
-                (GameState::new(), ms)
-            } else {
-                // Try to parse as FEN
-                match GameState::from_fen(&args[2]) {
-                    Ok(s) => {
-                        let ms = if args.len() > 3 {
-                            args[3].parse().unwrap_or(1000)
-                        } else {
-                            1000
-                        };
-                        (s, ms)
-                    }
-                    Err(e) => {
-                        eprintln!("Error parsing FEN: {}", e);
-                        return;
-                    }
-                }
-            }
-        } else {
-            (GameState::new(), 1000)
-        };
-
-        println!("Position: {}", state.to_fen());
-        println!("Searching for {} ms...", millis);
-
-        let start = std::time::Instant::now();
-        let result = search_with_limits(&state, SearchLimits::move_time(millis));
-        let elapsed = start.elapsed();
-
-        if let Some(best_move) = result.best_move {
-            println!("\nBest move: {}", best_move);
-            println!("Score: {} cp", result.score);
-            println!("Depth: {}", result.depth);
-            println!("Nodes: {}", result.nodes);
-            println!("Time: {:.2}s", elapsed.as_secs_f64());
-            println!("NPS: {:.0}", result.nodes as f64 / elapsed.as_secs_f64());
-            if result.stopped {
-                println!("(search stopped by time limit)");
-            }
-        } else {
-            println!("No legal moves available");
-        }
-    } else if args.len() > 1 && args[1] == "play" {
-        if args.len() > 2 && args[2] == "text" {
-            play_text_mode();
-        } else {
-            play_interactive();
-        }
     } else {
         println!("Chess engine");
         println!("Commands:");
-        println!("  play                 - Play with interactive board (vim keys)");
-        println!("  play text            - Play with text input (e2e4 style)");
-        println!("  uci                  - Run in UCI mode for GUI compatibility");
-        println!("  perft <depth> [fen]  - Run perft test");
+        #[cfg(feature = "search")]
+        engine::print_commands();
+        println!("  perft <depth> [fen]  - Run perft test (--json for a JSON object)");
+        println!("  perft suite [depth]  - Check perft breakdowns against published tables");
         println!("  fen <fen_string>     - Parse and display FEN position");
-        println!("  eval [fen]           - Evaluate position");
-        println!("  search [depth|fen] [depth] - Search for best move");
-        println!("  movetime [ms|fen] [ms] - Search with time limit (ms)");
         println!("\nExample FEN positions:");
         println!("  Starting: {}", positions::STARTING);
         println!("  Kiwipete: {}", positions::KIWIPETE);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perft_json() {
+        assert_eq!(
+            perft_json(3, 8902, Duration::from_millis(2)),
+            "{\"depth\":3,\"nodes\":8902,\"time_ms\":2,\"nps\":4451000}"
+        );
+    }
+
+    #[test]
+    fn test_builds_without_search_feature() {
+        // The rules-only binary is easy to break from the default build, so
+        // check it here rather than relying on someone remembering the flag
+        let status = std::process::Command::new(env!("CARGO"))
+            .args([
+                "check",
+                "--quiet",
+                "--package",
+                "chess",
+                "--no-default-features",
+            ])
+            .arg("--target-dir")
+            .arg(std::env::temp_dir().join("chess-rules-only"))
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .status()
+            .expect("failed to run cargo");
+        assert!(status.success());
+    }
+}
@@ -0,0 +1,226 @@
+use chess_agents::{Agent, MinimaxAgent};
+use chess_core::{Color, Game, GameState};
+use std::io::{self, Write};
+
+/// Plies at the start of each game played with opening randomization
+const OPENING_PLIES: u32 = 8;
+
+/// Centipawn margin within which opening moves are picked at random
+const OPENING_MARGIN: i32 = 30;
+
+/// Games still running after this many plies are adjudicated as draws
+pub const MAX_PLIES: usize = 400;
+
+/// Search depth for runs given a `seed=` but no `depth=`. A move time would
+/// make the games depend on machine speed and defeat the seed.
+pub const SEEDED_DEPTH: u8 = 3;
+
+/// Settings for the `selfplay` command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfPlayConfig {
+    pub games: usize,
+    pub move_time_ms: u64,
+    /// Searches to a fixed depth instead of the move time, which makes the
+    /// output independent of machine speed.
+    pub depth: Option<u8>,
+    pub seed: u64,
+    pub max_plies: usize,
+}
+
+impl Default for SelfPlayConfig {
+    fn default() -> Self {
+        Self {
+            games: 10,
+            move_time_ms: 100,
+            depth: None,
+            seed: 0,
+            max_plies: MAX_PLIES,
+        }
+    }
+}
+
+impl SelfPlayConfig {
+    /// Parses `[games] [ms]` followed by optional `depth=<n>` and
+    /// `seed=<n>`. Missing or unparsable values keep their defaults, except
+    /// that a seed without a depth searches to `SEEDED_DEPTH` so the run is
+    /// reproducible.
+    pub fn from_args(args: &[String]) -> Self {
+        let mut config = Self::default();
+        let mut positional = Vec::new();
+        let mut seeded = false;
+
+        for arg in args {
+            match arg.split_once('=') {
+                Some(("depth", value)) => config.depth = value.parse().ok(),
+                Some(("seed", value)) => {
+                    config.seed = value.parse().unwrap_or(config.seed);
+                    seeded = true;
+                }
+                Some(_) => {}
+                None => positional.push(arg.as_str()),
+            }
+        }
+
+        if let Some(games) = positional.first().and_then(|s| s.parse().ok()) {
+            config.games = games;
+        }
+        if let Some(ms) = positional.get(1).and_then(|s| s.parse().ok()) {
+            config.move_time_ms = ms;
+        }
+        if seeded && config.depth.is_none() {
+            config.depth = Some(SEEDED_DEPTH);
+        }
+
+        config
+    }
+
+    fn agent(&self, seed: u64) -> MinimaxAgent {
+        let agent = match self.depth {
+            Some(depth) => MinimaxAgent::with_depth(depth),
+            None => MinimaxAgent::with_time_limit(self.move_time_ms),
+        };
+        agent.with_opening_randomness(OPENING_PLIES, OPENING_MARGIN, seed)
+    }
+}
+
+/// Positions from one finished game and its result in PGN notation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfPlayGame {
+    pub positions: Vec<GameState>,
+    pub result: &'static str,
+}
+
+/// Plays one game between two agents. The game ends at checkmate,
+/// stalemate, the fifty-move rule, insufficient material, threefold
+/// repetition, or after `max_plies` plies, which counts as a draw.
+pub fn play_game(white: &mut dyn Agent, black: &mut dyn Agent, max_plies: usize) -> SelfPlayGame {
+    let mut game = Game::new();
+    let mut positions = Vec::new();
+
+    let result = loop {
+        if let Some(result) = game.result() {
+            break result.pgn();
+        }
+        if positions.len() >= max_plies {
+            break "1/2-1/2";
+        }
+
+        let state = game.state();
+        let best_move = match state.turn {
+            Color::White => white.best_move(state),
+            Color::Black => black.best_move(state),
+        };
+        let Some(mv) = best_move else {
+            break "1/2-1/2";
+        };
+        positions.push(state.clone());
+        game.apply_move(mv);
+    };
+
+    SelfPlayGame { positions, result }
+}
+
+/// Plays `config.games` games and writes every position before each move
+/// as a `fen,result` CSV line, after a header. Each game seeds the two
+/// agents' opening randomization from `config.seed`, so a fixed seed and
+/// depth always produce the same file.
+pub fn run_selfplay(config: &SelfPlayConfig, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "fen,result")?;
+
+    for index in 0..config.games as u64 {
+        let game_seed = config.seed.wrapping_add(index.wrapping_mul(2));
+        let mut white = config.agent(game_seed);
+        let mut black = config.agent(game_seed.wrapping_add(1));
+        let game = play_game(&mut white, &mut black, config.max_plies);
+
+        for state in &game.positions {
+            writeln!(out, "{},{}", state.to_fen(), game.result)?;
+        }
+        eprintln!(
+            "Game {}/{}: {} in {} plies",
+            index + 1,
+            config.games,
+            game.result,
+            game.positions.len()
+        );
+    }
+
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn quick_config(seed: u64) -> SelfPlayConfig {
+        SelfPlayConfig {
+            games: 2,
+            depth: Some(1),
+            seed,
+            max_plies: 60,
+            ..SelfPlayConfig::default()
+        }
+    }
+
+    fn selfplay_output(config: &SelfPlayConfig) -> String {
+        let mut out = Vec::new();
+        run_selfplay(config, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_selfplay_config_from_args() {
+        let config = SelfPlayConfig::from_args(&to_args(&["5", "250", "seed=7", "depth=3"]));
+        assert_eq!(config.games, 5);
+        assert_eq!(config.move_time_ms, 250);
+        assert_eq!(config.seed, 7);
+        assert_eq!(config.depth, Some(3));
+
+        assert_eq!(SelfPlayConfig::from_args(&[]), SelfPlayConfig::default());
+    }
+
+    #[test]
+    fn test_seeded_args_repeat_the_same_games() {
+        let mut config = SelfPlayConfig::from_args(&to_args(&["1", "50", "seed=11"]));
+        assert_eq!(config.depth, Some(SEEDED_DEPTH));
+
+        config.max_plies = 8;
+        let output = selfplay_output(&config);
+        assert_eq!(output, selfplay_output(&config));
+        assert_eq!(output.lines().count(), 1 + 8);
+    }
+
+    #[test]
+    fn test_selfplay_is_reproducible() {
+        let output = selfplay_output(&quick_config(3));
+        assert_eq!(output, selfplay_output(&quick_config(3)));
+
+        let mut lines = output.lines();
+        assert_eq!(lines.next(), Some("fen,result"));
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with(chess_core::positions::STARTING));
+        for line in lines {
+            let (fen, result) = line.rsplit_once(',').unwrap();
+            assert!(GameState::from_fen(fen).is_ok(), "{fen}");
+            assert!(["1-0", "0-1", "1/2-1/2"].contains(&result), "{result}");
+        }
+    }
+
+    #[test]
+    fn test_play_game_stops_at_ply_limit() {
+        let mut white = MinimaxAgent::new(1);
+        let mut black = MinimaxAgent::new(1);
+        let game = play_game(&mut white, &mut black, 10);
+        assert!(game.positions.len() <= 10);
+        assert_eq!(game.positions[0], GameState::new());
+        if game.positions.len() == 10 {
+            assert_eq!(game.result, "1/2-1/2");
+        }
+    }
+}
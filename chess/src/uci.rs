@@ -1,4 +1,8 @@
-use chess_agents::{search_with_callback_and_stop, SearchLimits, SearchProgress};
+use chess_agents::transposition::TranspositionTable;
+use chess_agents::{
+    search_with_tt, RootMoveProgress, SearchLimits, SearchProgress, SearchResult,
+    DEFAULT_TT_SIZE_MB,
+};
 use chess_core::{GameState, Move};
 use std::io::{self, BufRead, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -8,6 +12,11 @@ use std::time::Duration;
 
 pub struct UciEngine {
     position: GameState,
+    // The game line behind `position`: where it started and the moves since
+    start: GameState,
+    moves: Vec<Move>,
+    // Kept between searches of the same game
+    tt: Arc<TranspositionTable>,
     debug: bool,
     stop_flag: Arc<AtomicBool>,
     search_thread: Option<thread::JoinHandle<()>>,
@@ -17,6 +26,9 @@ impl UciEngine {
     pub fn new() -> Self {
         Self {
             position: GameState::new(),
+            start: GameState::new(),
+            moves: Vec::new(),
+            tt: Arc::new(TranspositionTable::new(DEFAULT_TT_SIZE_MB)),
             debug: false,
             stop_flag: Arc::new(AtomicBool::new(false)),
             search_thread: None,
@@ -56,6 +68,9 @@ impl UciEngine {
                 }
                 "ucinewgame" => {
                     self.position = GameState::new();
+                    self.start = GameState::new();
+                    self.moves.clear();
+                    self.tt = Arc::new(TranspositionTable::new(DEFAULT_TT_SIZE_MB));
                 }
                 "position" => {
                     self.handle_position(&parts);
@@ -93,7 +108,7 @@ impl UciEngine {
                 idx += 1;
             }
             "fen" => {
-                // Collect FEN string (6 parts)
+                // Collect the FEN fields; the move counters may be left off
                 let mut fen_parts = Vec::new();
                 idx += 1;
 
@@ -102,10 +117,19 @@ impl UciEngine {
                     idx += 1;
                 }
 
-                if fen_parts.len() >= 6 {
+                if fen_parts.len() >= 4 {
                     let fen = fen_parts.join(" ");
-                    match GameState::from_fen(&fen) {
-                        Ok(pos) => self.position = pos,
+                    match GameState::from_fen_lenient(&fen) {
+                        Ok(mut pos) => {
+                            // A FEN may name an en passant square no pawn can
+                            // capture on. Drop it, as `apply_move` would, so
+                            // hashes and the reported FEN agree with positions
+                            // reached through `moves`
+                            if !pos.can_capture_en_passant() {
+                                pos.en_passant = None;
+                            }
+                            self.position = pos;
+                        }
                         Err(e) => {
                             if self.debug {
                                 eprintln!("Invalid FEN: {}", e);
@@ -119,131 +143,47 @@ impl UciEngine {
         }
 
         // Apply moves if present
+        let start = self.position.clone();
+        let mut moves = Vec::new();
         if idx < parts.len() && parts[idx] == "moves" {
             idx += 1;
             while idx < parts.len() {
-                if let Some(mv) = self.parse_move(parts[idx]) {
+                if let Some(mv) = self.position.parse_uci(parts[idx]) {
                     self.position = self.position.apply_move(mv);
+                    moves.push(mv);
                 } else if self.debug {
                     eprintln!("Invalid move: {}", parts[idx]);
                 }
                 idx += 1;
             }
         }
-    }
 
-    fn handle_go(&mut self, parts: &[&str]) {
-        let mut limits = SearchLimits {
-            max_depth: None,
-            move_time: None,
-            nodes: None,
-            white_time: None,
-            black_time: None,
-            white_increment: None,
-            black_increment: None,
-            moves_to_go: None,
-        };
+        self.set_line(start, moves);
+    }
 
-        let mut idx = 1;
-        while idx < parts.len() {
-            match parts[idx] {
-                "depth" => {
-                    if idx + 1 < parts.len() {
-                        if let Ok(d) = parts[idx + 1].parse::<u8>() {
-                            limits.max_depth = Some(d);
-                        }
-                        idx += 2;
-                    } else {
-                        idx += 1;
-                    }
-                }
-                "movetime" => {
-                    if idx + 1 < parts.len() {
-                        if let Ok(ms) = parts[idx + 1].parse::<u64>() {
-                            limits.move_time = Some(Duration::from_millis(ms));
-                        }
-                        idx += 2;
-                    } else {
-                        idx += 1;
-                    }
-                }
-                "nodes" => {
-                    if idx + 1 < parts.len() {
-                        if let Ok(n) = parts[idx + 1].parse::<u64>() {
-                            limits.nodes = Some(n);
-                        }
-                        idx += 2;
-                    } else {
-                        idx += 1;
-                    }
-                }
-                "infinite" => {
-                    // Search until stopped
-                    limits.max_depth = Some(100);
-                    idx += 1;
-                }
-                "wtime" => {
-                    if idx + 1 < parts.len() {
-                        if let Ok(ms) = parts[idx + 1].parse::<u64>() {
-                            limits.white_time = Some(Duration::from_millis(ms));
-                        }
-                        idx += 2;
-                    } else {
-                        idx += 1;
-                    }
-                }
-                "btime" => {
-                    if idx + 1 < parts.len() {
-                        if let Ok(ms) = parts[idx + 1].parse::<u64>() {
-                            limits.black_time = Some(Duration::from_millis(ms));
-                        }
-                        idx += 2;
-                    } else {
-                        idx += 1;
-                    }
-                }
-                "winc" => {
-                    if idx + 1 < parts.len() {
-                        if let Ok(ms) = parts[idx + 1].parse::<u64>() {
-                            limits.white_increment = Some(Duration::from_millis(ms));
-                        }
-                        idx += 2;
-                    } else {
-                        idx += 1;
-                    }
-                }
-                "binc" => {
-                    if idx + 1 < parts.len() {
-                        if let Ok(ms) = parts[idx + 1].parse::<u64>() {
-                            limits.black_increment = Some(Duration::from_millis(ms));
-                        }
-                        idx += 2;
-                    } else {
-                        idx += 1;
-                    }
-                }
-                "movestogo" => {
-                    if idx + 1 < parts.len() {
-                        if let Ok(mtg) = parts[idx + 1].parse::<u32>() {
-                            limits.moves_to_go = Some(mtg);
-                        }
-                        idx += 2;
-                    } else {
-                        idx += 1;
-                    }
-                }
-                _ => idx += 1,
-            }
+    /// Records the game line of a new `position`. The transposition table
+    /// is kept while the line continues the previous one or is a prefix of
+    /// it, as after a takeback, since its entries are still valid and speed
+    /// up the next search. Any other position starts a fresh table.
+    fn set_line(&mut self, start: GameState, moves: Vec<Move>) {
+        let same_game = start == self.start
+            && (self.moves.starts_with(&moves) || moves.starts_with(&self.moves));
+        if !same_game {
+            self.tt = Arc::new(TranspositionTable::new(DEFAULT_TT_SIZE_MB));
         }
+        self.start = start;
+        self.moves = moves;
+    }
+
+    fn handle_go(&mut self, parts: &[&str]) {
+        let (limits, infinite) = parse_go(parts);
 
-        // Default to depth 6 if no limits specified (including time control)
-        if limits.max_depth.is_none()
-            && limits.move_time.is_none()
-            && limits.nodes.is_none()
-            && limits.white_time.is_none()
-            && limits.black_time.is_none()
-        {
-            limits.max_depth = Some(6);
+        // Searching an impossible position can panic; answer with a null move instead
+        if !self.position.is_legal_position() {
+            println!("info string illegal position, refusing to search");
+            println!("bestmove {}", Move::NULL);
+            io::stdout().flush().unwrap();
+            return;
         }
 
         // Wait for any previous search to finish
@@ -255,112 +195,205 @@ impl UciEngine {
         // Reset stop flag for new search
         self.stop_flag.store(false, Ordering::Relaxed);
 
+        // The previous search has finished, so the table is ours again
+        if let Some(tt) = Arc::get_mut(&mut self.tt) {
+            tt.new_search();
+        }
+
         // Clone necessary data for the search thread
         let position = self.position.clone();
         let stop_flag = Arc::clone(&self.stop_flag);
+        let tt = Arc::clone(&self.tt);
 
         // Spawn search thread
         let search_thread = thread::spawn(move || {
-            let callback = Box::new(move |info: &SearchProgress| {
-                print!(
-                    "info depth {} score cp {} nodes {} time {} nps {} pv",
-                    info.depth,
-                    info.score,
-                    info.nodes,
-                    info.time_ms,
-                    if info.time_ms > 0 {
-                        (info.nodes * 1000) / info.time_ms
-                    } else {
-                        0
-                    }
-                );
+            let result = search_position(&position, limits, tt, Arc::clone(&stop_flag));
 
-                // Print principal variation
-                for mv in &info.pv {
-                    print!(" {}", format_move_static(*mv));
+            // An infinite search must not answer before `stop`, even after
+            // finding a mate
+            if infinite {
+                while !stop_flag.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(1));
                 }
-                println!();
-                io::stdout().flush().unwrap();
-            });
-
-            let result = search_with_callback_and_stop(&position, limits, callback, stop_flag);
+            }
 
             // Output result
-            if let Some(best_move) = result.best_move {
-                println!("bestmove {}", format_move_static(best_move));
-            } else {
-                println!("bestmove 0000"); // Null move (no legal moves)
-            }
+            // No legal moves: answer with the null move, "0000"
+            let best_move = result.best_move.unwrap_or(Move::NULL);
+            println!("bestmove {}", format_move_static(best_move));
             io::stdout().flush().unwrap();
         });
 
         self.search_thread = Some(search_thread);
     }
 
-    fn parse_move(&self, move_str: &str) -> Option<Move> {
-        if move_str.len() < 4 {
-            return None;
-        }
-
-        let bytes = move_str.as_bytes();
+    fn handle_stop(&mut self) {
+        // Set the stop flag
+        self.stop_flag.store(true, Ordering::Relaxed);
 
-        // Parse from square
-        let from_file = (bytes[0] as i8 - b'a' as i8) as u8;
-        let from_rank = (bytes[1] as i8 - b'1' as i8) as u8;
-        let from = chess_core::Square::new(
-            chess_core::File::new(from_file)?,
-            chess_core::Rank::new(from_rank)?,
-        );
+        // Wait for search thread to finish
+        if let Some(thread) = self.search_thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
 
-        // Parse to square
-        let to_file = (bytes[2] as i8 - b'a' as i8) as u8;
-        let to_rank = (bytes[3] as i8 - b'1' as i8) as u8;
-        let to = chess_core::Square::new(
-            chess_core::File::new(to_file)?,
-            chess_core::Rank::new(to_rank)?,
+/// Searches `position`, printing `info` lines as the search progresses.
+fn search_position(
+    position: &GameState,
+    limits: SearchLimits,
+    tt: Arc<TranspositionTable>,
+    stop_flag: Arc<AtomicBool>,
+) -> SearchResult {
+    let callback = Box::new(move |info: &SearchProgress| {
+        println!("{}", format_info(info));
+        io::stdout().flush().unwrap();
+    });
+
+    let currmove_callback = Box::new(|progress: &RootMoveProgress| {
+        println!(
+            "info depth {} currmove {} currmovenumber {}",
+            progress.depth,
+            format_move_static(progress.currmove),
+            progress.currmovenumber
         );
+        io::stdout().flush().unwrap();
+    });
 
-        // Parse promotion if present
-        let promotion = if move_str.len() > 4 {
-            match bytes[4] {
-                b'q' => Some(chess_core::PieceType::Queen),
-                b'r' => Some(chess_core::PieceType::Rook),
-                b'b' => Some(chess_core::PieceType::Bishop),
-                b'n' => Some(chess_core::PieceType::Knight),
-                _ => None,
-            }
-        } else {
-            None
-        };
+    search_with_tt(position, limits, tt, callback, currmove_callback, stop_flag)
+}
 
-        // Create move and check if it's legal
-        let mv = Move {
-            from,
-            to,
-            promotion,
-        };
+/// Parses the arguments of a `go` command. The flag is set for
+/// `go infinite`, which carries no limits and runs until `stop`.
+fn parse_go(parts: &[&str]) -> (SearchLimits, bool) {
+    let mut limits = SearchLimits::infinite();
 
-        // Verify move is legal
-        let moves = chess_core::generate_legal_moves(&self.position);
-        if moves.iter().any(|&legal_mv| legal_mv == mv) {
-            Some(mv)
-        } else {
-            None
+    let mut infinite = false;
+    let mut idx = 1;
+    while idx < parts.len() {
+        match parts[idx] {
+            "depth" => {
+                if idx + 1 < parts.len() {
+                    if let Ok(d) = parts[idx + 1].parse::<u8>() {
+                        limits.max_depth = Some(d);
+                    }
+                    idx += 2;
+                } else {
+                    idx += 1;
+                }
+            }
+            "movetime" => {
+                if idx + 1 < parts.len() {
+                    if let Ok(ms) = parts[idx + 1].parse::<u64>() {
+                        limits.move_time = Some(Duration::from_millis(ms));
+                    }
+                    idx += 2;
+                } else {
+                    idx += 1;
+                }
+            }
+            "nodes" => {
+                if idx + 1 < parts.len() {
+                    if let Ok(n) = parts[idx + 1].parse::<u64>() {
+                        limits.nodes = Some(n);
+                    }
+                    idx += 2;
+                } else {
+                    idx += 1;
+                }
+            }
+            "infinite" => {
+                // No limits at all: deepen until stopped
+                infinite = true;
+                idx += 1;
+            }
+            "wtime" => {
+                if idx + 1 < parts.len() {
+                    if let Ok(ms) = parts[idx + 1].parse::<u64>() {
+                        limits.white_time = Some(Duration::from_millis(ms));
+                    }
+                    idx += 2;
+                } else {
+                    idx += 1;
+                }
+            }
+            "btime" => {
+                if idx + 1 < parts.len() {
+                    if let Ok(ms) = parts[idx + 1].parse::<u64>() {
+                        limits.black_time = Some(Duration::from_millis(ms));
+                    }
+                    idx += 2;
+                } else {
+                    idx += 1;
+                }
+            }
+            "winc" => {
+                if idx + 1 < parts.len() {
+                    if let Ok(ms) = parts[idx + 1].parse::<u64>() {
+                        limits.white_increment = Some(Duration::from_millis(ms));
+                    }
+                    idx += 2;
+                } else {
+                    idx += 1;
+                }
+            }
+            "binc" => {
+                if idx + 1 < parts.len() {
+                    if let Ok(ms) = parts[idx + 1].parse::<u64>() {
+                        limits.black_increment = Some(Duration::from_millis(ms));
+                    }
+                    idx += 2;
+                } else {
+                    idx += 1;
+                }
+            }
+            "movestogo" => {
+                if idx + 1 < parts.len() {
+                    if let Ok(mtg) = parts[idx + 1].parse::<u32>() {
+                        limits.moves_to_go = Some(mtg);
+                    }
+                    idx += 2;
+                } else {
+                    idx += 1;
+                }
+            }
+            _ => idx += 1,
         }
     }
 
-    fn handle_stop(&mut self) {
-        // Set the stop flag
-        self.stop_flag.store(true, Ordering::Relaxed);
+    // Default to depth 6 if no limits specified (including time control)
+    if !infinite
+        && limits.max_depth.is_none()
+        && limits.move_time.is_none()
+        && limits.nodes.is_none()
+        && limits.white_time.is_none()
+        && limits.black_time.is_none()
+    {
+        limits.max_depth = Some(6);
+    }
 
-        // Wait for search thread to finish
-        if let Some(thread) = self.search_thread.take() {
-            let _ = thread.join();
-        }
+    (limits, infinite)
+}
+
+/// Formats a per-depth `info` line with every token GUIs expect.
+fn format_info(info: &SearchProgress) -> String {
+    // The spec expects nps even when no measurable time has passed
+    let nps = (info.nodes * 1000).checked_div(info.time_ms).unwrap_or(0);
+    let mut line = format!(
+        "info depth {} score cp {} nodes {} nps {} hashfull {} tbhits 0 time {} pv",
+        info.depth, info.score, info.nodes, nps, info.hashfull, info.time_ms
+    );
+    for mv in &info.pv {
+        line.push(' ');
+        line.push_str(&format_move_static(*mv));
     }
+    line
 }
 
 fn format_move_static(mv: Move) -> String {
+    if mv.is_null() {
+        return mv.to_string();
+    }
     let mut result = format!("{}{}", mv.from, mv.to);
     if let Some(promo) = mv.promotion {
         result.push(match promo {
@@ -373,3 +406,192 @@ fn format_move_static(mv: Move) -> String {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess_agents::search_with_callbacks_and_stop;
+    use chess_core::Square;
+
+    fn info_tokens(line: &str) -> Vec<(&str, &str)> {
+        let mut parts = line.split_whitespace();
+        assert_eq!(parts.next(), Some("info"));
+        let mut tokens = Vec::new();
+        while let Some(key) = parts.next() {
+            if key == "pv" {
+                break;
+            }
+            let key = if key == "score" {
+                parts.next().unwrap()
+            } else {
+                key
+            };
+            tokens.push((key, parts.next().unwrap()));
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_info_line_has_required_tokens() {
+        let e2e4 = Move::new(
+            Square::from_algebraic("e2").unwrap(),
+            Square::from_algebraic("e4").unwrap(),
+        );
+        let mut progress = SearchProgress {
+            depth: 5,
+            score: 31,
+            nodes: 20_000,
+            pv: vec![e2e4],
+            time_ms: 40,
+            hashfull: 12,
+        };
+
+        let line = format_info(&progress);
+        assert!(line.ends_with(" pv e2e4"), "{}", line);
+        let tokens = info_tokens(&line);
+        for (key, value) in [
+            ("depth", "5"),
+            ("cp", "31"),
+            ("nodes", "20000"),
+            ("nps", "500000"),
+            ("hashfull", "12"),
+            ("tbhits", "0"),
+            ("time", "40"),
+        ] {
+            assert!(
+                tokens.contains(&(key, value)),
+                "{} {} in {}",
+                key,
+                value,
+                line
+            );
+        }
+
+        // nps is still reported when no time has elapsed
+        progress.time_ms = 0;
+        let line = format_info(&progress);
+        assert!(info_tokens(&line).contains(&("nps", "0")), "{}", line);
+    }
+
+    #[test]
+    fn test_go_infinite_deepens_until_stopped() {
+        let (limits, infinite) = parse_go(&["go", "infinite"]);
+        assert!(infinite);
+        assert_eq!(limits.max_depth, None);
+
+        let state = GameState::new();
+        let depths = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&depths);
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let stopper = {
+            let stop_flag = Arc::clone(&stop_flag);
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(200));
+                stop_flag.store(true, Ordering::Relaxed);
+            })
+        };
+
+        let start = std::time::Instant::now();
+        let result = search_with_callbacks_and_stop(
+            &state,
+            limits,
+            Box::new(move |info| sink.lock().unwrap().push(format_info(info))),
+            Box::new(|_| {}),
+            stop_flag,
+        );
+        stopper.join().unwrap();
+
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "{:?}",
+            start.elapsed()
+        );
+        assert!(result.stopped);
+        let best_move = result.best_move.unwrap();
+        assert!(chess_core::generate_legal_moves(&state)
+            .iter()
+            .any(|&mv| mv == best_move));
+        let lines = depths.lock().unwrap();
+        assert!(!lines.is_empty());
+        assert!(lines[0].starts_with("info depth 1 "), "{}", lines[0]);
+    }
+
+    fn position_after(command: &str) -> GameState {
+        let mut engine = UciEngine::new();
+        let parts: Vec<&str> = command.split_whitespace().collect();
+        engine.handle_position(&parts);
+        engine.position
+    }
+
+    fn search_depth(engine: &UciEngine, tt: Arc<TranspositionTable>) -> SearchResult {
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        search_position(&engine.position, SearchLimits::depth(4), tt, stop_flag)
+    }
+
+    #[test]
+    fn test_takeback_keeps_transposition_table() {
+        let mut engine = UciEngine::new();
+        let line = "position startpos moves e2e4 e7e5 g1f3 b8c6";
+        engine.handle_position(&line.split_whitespace().collect::<Vec<_>>());
+        search_depth(&engine, Arc::clone(&engine.tt));
+        let tt = Arc::clone(&engine.tt);
+
+        // Taking back Nc6 stays on the same line, so the table survives
+        let takeback = "position startpos moves e2e4 e7e5 g1f3";
+        engine.handle_position(&takeback.split_whitespace().collect::<Vec<_>>());
+        assert!(Arc::ptr_eq(&tt, &engine.tt));
+        assert!(engine.tt.hashfull() > 0);
+
+        let warm = search_depth(&engine, Arc::clone(&engine.tt));
+        let cold = search_depth(
+            &engine,
+            Arc::new(TranspositionTable::new(DEFAULT_TT_SIZE_MB)),
+        );
+        assert!(warm.nodes < cold.nodes, "{} vs {}", warm.nodes, cold.nodes);
+
+        // A different game starts over
+        let other = "position startpos moves d2d4";
+        engine.handle_position(&other.split_whitespace().collect::<Vec<_>>());
+        assert!(!Arc::ptr_eq(&tt, &engine.tt));
+        assert_eq!(engine.tt.hashfull(), 0);
+    }
+
+    #[test]
+    fn test_position_reports_only_capturable_en_passant() {
+        // No black pawn can take on e3
+        let after_e4 = position_after("position startpos moves e2e4");
+        assert_eq!(after_e4.en_passant, None);
+        assert_eq!(
+            after_e4.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        );
+
+        // A FEN that names e3 anyway is the same position
+        let from_fen = position_after(
+            "position fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        );
+        assert_eq!(from_fen, after_e4);
+        assert_eq!(from_fen.zobrist_hash(), after_e4.zobrist_hash());
+
+        // The e5 pawn can take on f6, so the square stays
+        let capturable = position_after("position startpos moves e2e4 a7a6 e4e5 f7f5");
+        assert_eq!(capturable.en_passant, Square::from_algebraic("f6"));
+        assert!(capturable.to_fen().contains(" w KQkq f6 "));
+        let from_fen = position_after(&format!("position fen {}", capturable.to_fen()));
+        assert_eq!(from_fen, capturable);
+    }
+
+    #[test]
+    fn test_position_fen_without_move_counters() {
+        let state = position_after(
+            "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - moves e2e4",
+        );
+        assert_eq!(
+            state.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        );
+
+        let state = position_after("position fen 4k3/8/8/8/8/8/8/4K2R w K - 12 moves e1g1");
+        assert_eq!(state.to_fen(), "4k3/8/8/8/8/8/8/5RK1 b - - 13 1");
+    }
+}
@@ -202,12 +202,12 @@ impl BitBoardSet {
 
     /// Gets the bitboard for pieces of a specific type and color.
     pub fn pieces(&self, piece_type: PieceType, color: Color) -> BitBoard {
-        self.pieces[color as usize][piece_type as usize]
+        self.pieces[color.index()][piece_type.index()]
     }
 
     /// Gets all pieces of a specific color.
     pub fn color_occupancy(&self, color: Color) -> BitBoard {
-        self.color_occupancy[color as usize]
+        self.color_occupancy[color.index()]
     }
 
     /// Gets all occupied squares.
@@ -223,10 +223,9 @@ impl BitBoardSet {
     /// Sets a piece on the given square.
     fn set_piece(&mut self, square: Square, piece: Piece) {
         let bb = BitBoard::from_square(square);
-        self.pieces[piece.color as usize][piece.piece_type as usize] =
-            self.pieces[piece.color as usize][piece.piece_type as usize].union(bb);
-        self.color_occupancy[piece.color as usize] =
-            self.color_occupancy[piece.color as usize].union(bb);
+        let (color, piece_type) = (piece.color.index(), piece.piece_type.index());
+        self.pieces[color][piece_type] = self.pieces[color][piece_type].union(bb);
+        self.color_occupancy[color] = self.color_occupancy[color].union(bb);
         self.all_occupancy = self.all_occupancy.union(bb);
     }
 
@@ -297,9 +296,13 @@ impl BoardState {
     }
 
     /// Sets a piece at the given square.
+    /// Only the affected square is updated in the bitboards.
     pub fn set_square(&mut self, square: Square, piece: Option<Piece>) {
         self.array_board.set_piece(square, piece);
-        self.bitboards = BitBoardSet::from_board(&self.array_board);
+        self.bitboards.clear_square(square);
+        if let Some(piece) = piece {
+            self.bitboards.set_piece(square, piece);
+        }
     }
 
     /// Returns true if the representations are consistent.
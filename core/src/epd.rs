@@ -0,0 +1,247 @@
+use crate::fen::FenError;
+use crate::game_state::GameState;
+use crate::types::Move;
+use std::fmt;
+
+/// A position from an Extended Position Description (EPD) line, e.g.
+/// `6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id "back rank";`.
+#[derive(Debug, Clone)]
+pub struct EpdRecord {
+    /// The position. Its clocks come from the `hmvc` and `fmvn`
+    /// operations when present, otherwise they are 0 and 1.
+    pub state: GameState,
+    /// Operations in line order as (opcode, operands), with quotes removed
+    /// from string operands.
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EpdError {
+    /// The line has fewer than the four position fields
+    MissingFields(usize),
+    InvalidFen(FenError),
+    UnterminatedString,
+    /// A `bm` or `am` operand that is not a legal move in the position
+    IllegalMove {
+        opcode: String,
+        san: String,
+    },
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EpdError::MissingFields(count) => {
+                write!(f, "Invalid EPD: expected 4 position fields, got {count}")
+            }
+            EpdError::InvalidFen(e) => write!(f, "Invalid EPD position: {e}"),
+            EpdError::UnterminatedString => write!(f, "Unterminated string operand"),
+            EpdError::IllegalMove { opcode, san } => {
+                write!(f, "Illegal move '{san}' in '{opcode}' operation")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EpdError {}
+
+impl From<FenError> for EpdError {
+    fn from(e: FenError) -> Self {
+        EpdError::InvalidFen(e)
+    }
+}
+
+impl EpdRecord {
+    /// Parses one EPD line. The `bm` and `am` operands must be legal SAN
+    /// moves in the position.
+    pub fn parse(line: &str) -> Result<Self, EpdError> {
+        let line = line.trim();
+        let mut fields = Vec::new();
+        let mut rest = line;
+        while fields.len() < 4 {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                return Err(EpdError::MissingFields(fields.len()));
+            }
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            fields.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+
+        let operations = parse_operations(rest)?;
+        let clock = |opcode: &str, default: &str| {
+            operations
+                .iter()
+                .find(|(op, _)| op == opcode)
+                .and_then(|(_, operands)| operands.first().cloned())
+                .unwrap_or_else(|| default.to_string())
+        };
+        let fen = format!(
+            "{} {} {}",
+            fields.join(" "),
+            clock("hmvc", "0"),
+            clock("fmvn", "1")
+        );
+        let state = GameState::from_fen(&fen)?;
+
+        for (opcode, operands) in &operations {
+            if opcode != "bm" && opcode != "am" {
+                continue;
+            }
+            if let Some(san) = operands.iter().find(|san| state.parse_san(san).is_none()) {
+                return Err(EpdError::IllegalMove {
+                    opcode: opcode.clone(),
+                    san: san.clone(),
+                });
+            }
+        }
+
+        Ok(Self { state, operations })
+    }
+
+    /// Parses every non-blank line of `text` that is not a `#` comment, one
+    /// result per line, so a bad line does not hide the ones after it.
+    pub fn parse_all(text: &str) -> Vec<Result<Self, EpdError>> {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(Self::parse)
+            .collect()
+    }
+
+    /// Operands of the first operation named `opcode`.
+    pub fn operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|(op, _)| op == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    /// The `id` operation, which names the position in a test suite.
+    pub fn id(&self) -> Option<&str> {
+        self.operation("id")?.first().map(String::as_str)
+    }
+
+    /// Moves listed by the `bm` (best move) operation.
+    pub fn best_moves(&self) -> Vec<Move> {
+        self.operation_moves("bm")
+    }
+
+    /// Moves listed by the `am` (avoid move) operation.
+    pub fn avoid_moves(&self) -> Vec<Move> {
+        self.operation_moves("am")
+    }
+
+    fn operation_moves(&self, opcode: &str) -> Vec<Move> {
+        self.operation(opcode)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|san| self.state.parse_san(san))
+            .collect()
+    }
+}
+
+/// Splits the text after the position fields into `opcode operand...;`
+/// operations. Double-quoted operands may contain spaces and semicolons.
+fn parse_operations(text: &str) -> Result<Vec<(String, Vec<String>)>, EpdError> {
+    let mut operations = Vec::new();
+    let mut tokens: Vec<String> = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    let mut finish = |tokens: &mut Vec<String>| {
+        if !tokens.is_empty() {
+            let opcode = tokens.remove(0);
+            operations.push((opcode, std::mem::take(tokens)));
+        }
+    };
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == ';' {
+            chars.next();
+            finish(&mut tokens);
+        } else if c == '"' {
+            chars.next();
+            let mut operand = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => operand.push(c),
+                    None => return Err(EpdError::UnterminatedString),
+                }
+            }
+            tokens.push(operand);
+        } else {
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == ';' || c == '"' {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+    // The final semicolon is often left off
+    finish(&mut tokens);
+
+    Ok(operations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Square;
+
+    #[test]
+    fn test_parse_epd_record() {
+        let record = EpdRecord::parse(
+            r#"6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id "back rank; easy"; hmvc 4;"#,
+        )
+        .unwrap();
+
+        assert_eq!(record.id(), Some("back rank; easy"));
+        assert_eq!(record.state.halfmove_clock, 4);
+        assert_eq!(record.state.fullmove_number, 1);
+        let sq = |name: &str| name.parse::<Square>().unwrap();
+        assert_eq!(record.best_moves(), vec![Move::new(sq("a1"), sq("a8"))]);
+        assert!(record.avoid_moves().is_empty());
+    }
+
+    #[test]
+    fn test_parse_epd_multiple_best_moves() {
+        let record =
+            EpdRecord::parse("4k3/8/8/8/8/8/8/R3K2R w KQ - bm O-O Rh8+; id \"two\"").unwrap();
+        assert_eq!(record.best_moves().len(), 2);
+        assert_eq!(record.id(), Some("two"));
+    }
+
+    #[test]
+    fn test_parse_epd_errors() {
+        assert_eq!(
+            EpdRecord::parse("8/8/8/8 w").unwrap_err(),
+            EpdError::MissingFields(2)
+        );
+        assert_eq!(
+            EpdRecord::parse("4k3/8/8/8/8/8/8/4K3 w - - id \"open").unwrap_err(),
+            EpdError::UnterminatedString
+        );
+        assert_eq!(
+            EpdRecord::parse("4k3/8/8/8/8/8/8/4K3 w - - bm Qd1;").unwrap_err(),
+            EpdError::IllegalMove {
+                opcode: "bm".to_string(),
+                san: "Qd1".to_string()
+            }
+        );
+        assert!(matches!(
+            EpdRecord::parse("4k3/8/8/8/8/8/8/4K3 x - - bm Kd1;"),
+            Err(EpdError::InvalidFen(_))
+        ));
+
+        let records = EpdRecord::parse_all("# comment\n\n8/8 w\n4k3/8/8/8/8/8/8/4K3 w - -\n");
+        assert_eq!(records.len(), 2);
+        assert!(records[0].is_err() && records[1].is_ok());
+    }
+}
@@ -8,31 +8,88 @@ use std::fmt;
 /// FEN (Forsyth-Edwards Notation) parsing and serialization.
 /// Standard notation for describing chess positions.
 /// FEN parsing error types.
+/// Each variant carries the text of the field that failed so the message
+/// can point at it, e.g. `Invalid castling rights (field 3) "KQxq": unexpected character 'x'`.
 #[derive(Debug, Clone, PartialEq)]
 pub enum FenError {
-    InvalidFormat(String),
-    InvalidPiece(char),
-    InvalidSquare(String),
-    InvalidColor(String),
-    InvalidCastling(String),
-    InvalidEnPassant(String),
-    InvalidNumber(String),
+    /// The string does not have six whitespace-separated fields
+    WrongFieldCount(usize),
+    InvalidPlacement {
+        field: String,
+        reason: String,
+    },
+    InvalidColor {
+        field: String,
+    },
+    InvalidCastling {
+        field: String,
+        reason: String,
+    },
+    InvalidEnPassant {
+        field: String,
+        reason: String,
+    },
+    InvalidHalfmoveClock {
+        field: String,
+    },
+    InvalidFullmoveNumber {
+        field: String,
+    },
 }
 
-impl fmt::Display for FenError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl FenError {
+    /// Name of the FEN field that failed to parse.
+    pub fn field_name(&self) -> &'static str {
+        match self {
+            FenError::WrongFieldCount(_) => "FEN",
+            FenError::InvalidPlacement { .. } => "piece placement",
+            FenError::InvalidColor { .. } => "side to move",
+            FenError::InvalidCastling { .. } => "castling rights",
+            FenError::InvalidEnPassant { .. } => "en passant square",
+            FenError::InvalidHalfmoveClock { .. } => "halfmove clock",
+            FenError::InvalidFullmoveNumber { .. } => "fullmove number",
+        }
+    }
+
+    /// 1-based position of the failing field within the FEN string.
+    pub fn field_index(&self) -> Option<usize> {
         match self {
-            FenError::InvalidFormat(s) => write!(f, "Invalid FEN format: {s}"),
-            FenError::InvalidPiece(c) => write!(f, "Invalid piece character: '{c}'"),
-            FenError::InvalidSquare(s) => write!(f, "Invalid square: {s}"),
-            FenError::InvalidColor(s) => write!(f, "Invalid color: {s}"),
-            FenError::InvalidCastling(s) => write!(f, "Invalid castling rights: {s}"),
-            FenError::InvalidEnPassant(s) => write!(f, "Invalid en passant square: {s}"),
-            FenError::InvalidNumber(s) => write!(f, "Invalid number: {s}"),
+            FenError::WrongFieldCount(_) => None,
+            FenError::InvalidPlacement { .. } => Some(1),
+            FenError::InvalidColor { .. } => Some(2),
+            FenError::InvalidCastling { .. } => Some(3),
+            FenError::InvalidEnPassant { .. } => Some(4),
+            FenError::InvalidHalfmoveClock { .. } => Some(5),
+            FenError::InvalidFullmoveNumber { .. } => Some(6),
         }
     }
 }
 
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (field, reason) = match self {
+            FenError::WrongFieldCount(count) => {
+                return write!(f, "Invalid FEN: expected 6 fields, got {count}");
+            }
+            FenError::InvalidPlacement { field, reason }
+            | FenError::InvalidCastling { field, reason }
+            | FenError::InvalidEnPassant { field, reason } => (field, reason.as_str()),
+            FenError::InvalidColor { field } => (field, "expected 'w' or 'b'"),
+            FenError::InvalidHalfmoveClock { field }
+            | FenError::InvalidFullmoveNumber { field } => {
+                (field, "expected a non-negative number")
+            }
+        };
+
+        write!(
+            f,
+            "Invalid {} (field {}) \"{field}\": {reason}",
+            self.field_name(),
+            self.field_index().unwrap_or(0)
+        )
+    }
+}
+
 impl std::error::Error for FenError {}
 
 impl GameState {
@@ -42,10 +99,7 @@ impl GameState {
         let parts: Vec<&str> = fen.split_whitespace().collect();
 
         if parts.len() != 6 {
-            return Err(FenError::InvalidFormat(format!(
-                "Expected 6 fields, got {}",
-                parts.len()
-            )));
+            return Err(FenError::WrongFieldCount(parts.len()));
         }
 
         // Parse board position
@@ -55,7 +109,11 @@ impl GameState {
         let turn = match parts[1] {
             "w" => Color::White,
             "b" => Color::Black,
-            _ => return Err(FenError::InvalidColor(parts[1].to_string())),
+            _ => {
+                return Err(FenError::InvalidColor {
+                    field: parts[1].to_string(),
+                })
+            }
         };
 
         // Parse castling rights
@@ -65,14 +123,20 @@ impl GameState {
         let en_passant = parse_en_passant(parts[3])?;
 
         // Parse halfmove clock
-        let halfmove_clock = parts[4]
-            .parse::<u16>()
-            .map_err(|_| FenError::InvalidNumber(parts[4].to_string()))?;
+        let halfmove_clock =
+            parts[4]
+                .parse::<u16>()
+                .map_err(|_| FenError::InvalidHalfmoveClock {
+                    field: parts[4].to_string(),
+                })?;
 
         // Parse fullmove number
-        let fullmove_number = parts[5]
-            .parse::<u16>()
-            .map_err(|_| FenError::InvalidNumber(parts[5].to_string()))?;
+        let fullmove_number =
+            parts[5]
+                .parse::<u16>()
+                .map_err(|_| FenError::InvalidFullmoveNumber {
+                    field: parts[5].to_string(),
+                })?;
 
         Ok(GameState {
             board,
@@ -84,14 +148,28 @@ impl GameState {
         })
     }
 
+    /// Like `from_fen`, but also accepts FENs without the halfmove clock or
+    /// both move counters, which default to 0 and 1.
+    pub fn from_fen_lenient(fen: &str) -> Result<Self, FenError> {
+        let parts: Vec<&str> = fen.split_whitespace().collect();
+        match parts.len() {
+            4 => Self::from_fen(&format!("{fen} 0 1")),
+            5 => Self::from_fen(&format!("{fen} 1")),
+            _ => Self::from_fen(fen),
+        }
+    }
+
     /// Converts the game state to a FEN string.
+    /// The en passant square is only written when a pawn can actually capture there.
     pub fn to_fen(&self) -> String {
+        let en_passant = self.en_passant.filter(|_| self.can_capture_en_passant());
+
         format!(
             "{} {} {} {} {} {}",
             board_to_fen(&self.board),
             if self.turn == Color::White { "w" } else { "b" },
             castling_to_fen(self.castling),
-            en_passant_to_fen(self.en_passant),
+            en_passant_to_fen(en_passant),
             self.halfmove_clock,
             self.fullmove_number
         )
@@ -102,12 +180,13 @@ impl GameState {
 fn parse_board(board_str: &str) -> Result<BoardState, FenError> {
     let mut board = BoardState::empty();
     let ranks: Vec<&str> = board_str.split('/').collect();
+    let error = |reason: String| FenError::InvalidPlacement {
+        field: board_str.to_string(),
+        reason,
+    };
 
     if ranks.len() != 8 {
-        return Err(FenError::InvalidFormat(format!(
-            "Expected 8 ranks, got {}",
-            ranks.len()
-        )));
+        return Err(error(format!("expected 8 ranks, got {}", ranks.len())));
     }
 
     for (rank_idx, rank_str) in ranks.iter().enumerate() {
@@ -117,10 +196,7 @@ fn parse_board(board_str: &str) -> Result<BoardState, FenError> {
 
         for ch in rank_str.chars() {
             if file_idx >= 8 {
-                return Err(FenError::InvalidFormat(format!(
-                    "Too many squares in rank {}",
-                    8 - rank_idx
-                )));
+                return Err(error(format!("too many squares in rank {}", 8 - rank_idx)));
             }
 
             if ch.is_numeric() {
@@ -131,15 +207,20 @@ fn parse_board(board_str: &str) -> Result<BoardState, FenError> {
                 // Piece
                 let file = File::new(file_idx).unwrap();
                 let square = Square::new(file, rank);
-                let piece = piece_from_char(ch)?;
+                let piece = piece_from_char(ch).ok_or_else(|| {
+                    error(format!(
+                        "unexpected character '{ch}' in rank {}",
+                        8 - rank_idx
+                    ))
+                })?;
                 board.set_square(square, Some(piece));
                 file_idx += 1;
             }
         }
 
         if file_idx != 8 {
-            return Err(FenError::InvalidFormat(format!(
-                "Rank {} has {} squares, expected 8",
+            return Err(error(format!(
+                "rank {} has {} squares, expected 8",
                 8 - rank_idx,
                 file_idx
             )));
@@ -168,7 +249,7 @@ fn board_to_fen(board: &BoardState) -> String {
                         fen.push_str(&empty_count.to_string());
                         empty_count = 0;
                     }
-                    fen.push(piece_to_char(piece));
+                    fen.push(piece.fen_char());
                 }
                 None => {
                     empty_count += 1;
@@ -188,26 +269,8 @@ fn board_to_fen(board: &BoardState) -> String {
     fen
 }
 
-/// Converts a piece to its FEN character.
-fn piece_to_char(piece: Piece) -> char {
-    let ch = match piece.piece_type {
-        PieceType::Pawn => 'p',
-        PieceType::Knight => 'n',
-        PieceType::Bishop => 'b',
-        PieceType::Rook => 'r',
-        PieceType::Queen => 'q',
-        PieceType::King => 'k',
-    };
-
-    if piece.color == Color::White {
-        ch.to_ascii_uppercase()
-    } else {
-        ch
-    }
-}
-
 /// Parses a FEN character into a piece.
-fn piece_from_char(ch: char) -> Result<Piece, FenError> {
+fn piece_from_char(ch: char) -> Option<Piece> {
     let color = if ch.is_uppercase() {
         Color::White
     } else {
@@ -221,10 +284,10 @@ fn piece_from_char(ch: char) -> Result<Piece, FenError> {
         'r' => PieceType::Rook,
         'q' => PieceType::Queen,
         'k' => PieceType::King,
-        _ => return Err(FenError::InvalidPiece(ch)),
+        _ => return None,
     };
 
-    Ok(Piece { piece_type, color })
+    Some(Piece { piece_type, color })
 }
 
 /// Parses castling rights from FEN notation.
@@ -248,7 +311,12 @@ fn parse_castling(castling_str: &str) -> Result<CastlingRights, FenError> {
             'Q' => white.queenside = true,
             'k' => black.kingside = true,
             'q' => black.queenside = true,
-            _ => return Err(FenError::InvalidCastling(castling_str.to_string())),
+            _ => {
+                return Err(FenError::InvalidCastling {
+                    field: castling_str.to_string(),
+                    reason: format!("unexpected character '{ch}'"),
+                })
+            }
         }
     }
 
@@ -285,15 +353,18 @@ fn parse_en_passant(ep_str: &str) -> Result<Option<Square>, FenError> {
         return Ok(None);
     }
 
-    if ep_str.len() != 2 {
-        return Err(FenError::InvalidEnPassant(ep_str.to_string()));
-    }
+    let error = |reason: &str| FenError::InvalidEnPassant {
+        field: ep_str.to_string(),
+        reason: reason.to_string(),
+    };
 
     let chars: Vec<char> = ep_str.chars().collect();
-    let file =
-        File::from_char(chars[0]).ok_or_else(|| FenError::InvalidEnPassant(ep_str.to_string()))?;
-    let rank =
-        Rank::from_char(chars[1]).ok_or_else(|| FenError::InvalidEnPassant(ep_str.to_string()))?;
+    if chars.len() != 2 {
+        return Err(error("expected '-' or a square like e3"));
+    }
+
+    let file = File::from_char(chars[0]).ok_or_else(|| error("invalid file"))?;
+    let rank = Rank::from_char(chars[1]).ok_or_else(|| error("invalid rank"))?;
 
     Ok(Some(Square::new(file, rank)))
 }
@@ -332,6 +403,24 @@ mod tests {
         assert!(state.en_passant.is_none());
     }
 
+    #[test]
+    fn test_parse_lenient_move_counters() {
+        let four = "4k3/8/8/8/8/8/8/4K2R w K -";
+        let state = GameState::from_fen_lenient(four).unwrap();
+        assert_eq!(state.to_fen(), format!("{four} 0 1"));
+
+        let five = GameState::from_fen_lenient(&format!("{four} 7")).unwrap();
+        assert_eq!((five.halfmove_clock, five.fullmove_number), (7, 1));
+        let six = GameState::from_fen_lenient(&format!("{four} 7 30")).unwrap();
+        assert_eq!((six.halfmove_clock, six.fullmove_number), (7, 30));
+
+        assert_eq!(
+            GameState::from_fen_lenient("4k3/8/8/8/8/8/8/4K2R w").unwrap_err(),
+            FenError::WrongFieldCount(2)
+        );
+        assert!(GameState::from_fen(four).is_err());
+    }
+
     #[test]
     fn test_round_trip() {
         let original_fen = positions::STARTING;
@@ -340,6 +429,43 @@ mod tests {
         assert_eq!(original_fen, new_fen);
     }
 
+    #[test]
+    fn test_round_trip_random_positions() {
+        let mut positions = 0;
+        let mut en_passant_positions = 0;
+        let mut check = |state: &GameState| {
+            let fen = state.to_fen();
+            let parsed = GameState::from_fen(&fen).unwrap();
+            assert_eq!(parsed.board, state.board, "{fen}");
+            assert_eq!(parsed.turn, state.turn, "{fen}");
+            assert_eq!(parsed.castling, state.castling, "{fen}");
+            assert_eq!(parsed.halfmove_clock, state.halfmove_clock, "{fen}");
+            assert_eq!(parsed.fullmove_number, state.fullmove_number, "{fen}");
+
+            // The en passant square is only written when a capture is possible
+            let capturable = state.en_passant.filter(|_| state.can_capture_en_passant());
+            assert_eq!(parsed.en_passant, capturable, "{fen}");
+            assert_eq!(parsed.to_fen(), fen);
+
+            positions += 1;
+            en_passant_positions += capturable.is_some() as usize;
+        };
+
+        // Every position of a few seeded random games
+        for seed in 0..25 {
+            let (moves, _) = crate::random_game(seed, 150);
+            let mut state = GameState::new();
+            check(&state);
+            for mv in moves {
+                state = state.apply_move(mv);
+                check(&state);
+            }
+        }
+
+        assert!(positions >= 200, "{positions}");
+        assert!(en_passant_positions > 0);
+    }
+
     #[test]
     fn test_parse_kiwipete() {
         let state = GameState::from_fen(positions::KIWIPETE).unwrap();
@@ -363,11 +489,47 @@ mod tests {
 
     #[test]
     fn test_invalid_fen() {
-        assert!(GameState::from_fen("invalid").is_err());
-        assert!(GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").is_err());
-        assert!(
+        assert_eq!(
+            GameState::from_fen("invalid").unwrap_err(),
+            FenError::WrongFieldCount(1)
+        );
+        assert_eq!(
+            GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").unwrap_err(),
+            FenError::WrongFieldCount(1)
+        );
+        assert_eq!(
             GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1")
-                .is_err()
+                .unwrap_err(),
+            FenError::InvalidColor {
+                field: "x".to_string()
+            }
+        );
+
+        let err = GameState::from_fen("rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+            .unwrap_err();
+        assert_eq!(err.field_index(), Some(1));
+        assert!(err.to_string().contains("'x' in rank 7"), "{err}");
+
+        let err = GameState::from_fen("8/8/8/8/8/8/8/4K2k w - e9 0 1").unwrap_err();
+        assert_eq!(err.field_name(), "en passant square");
+
+        let err = GameState::from_fen("8/8/8/8/8/8/8/4K2k w - - 0 x").unwrap_err();
+        assert_eq!(err.field_index(), Some(6));
+    }
+
+    #[test]
+    fn test_castling_error_names_field() {
+        let err = GameState::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQxq - 0 1").unwrap_err();
+        assert_eq!(
+            err,
+            FenError::InvalidCastling {
+                field: "KQxq".to_string(),
+                reason: "unexpected character 'x'".to_string()
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Invalid castling rights (field 3) \"KQxq\": unexpected character 'x'"
         );
     }
 }
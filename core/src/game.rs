@@ -0,0 +1,226 @@
+use crate::game_state::GameState;
+use crate::move_gen::{is_checkmate, is_stalemate};
+use crate::types::{Color, Move};
+use std::fmt;
+
+/// How a finished game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    Checkmate { winner: Color },
+    Stalemate,
+    FiftyMoveRule,
+    InsufficientMaterial,
+    Repetition,
+}
+
+impl GameResult {
+    /// The side that won, or `None` for a draw.
+    pub fn winner(self) -> Option<Color> {
+        match self {
+            GameResult::Checkmate { winner } => Some(winner),
+            _ => None,
+        }
+    }
+
+    /// The result in PGN notation: "1-0", "0-1" or "1/2-1/2".
+    pub fn pgn(self) -> &'static str {
+        match self.winner() {
+            Some(Color::White) => "1-0",
+            Some(Color::Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameResult::Checkmate { winner } => write!(f, "{winner} wins by checkmate"),
+            GameResult::Stalemate => write!(f, "Draw by stalemate"),
+            GameResult::FiftyMoveRule => write!(f, "Draw by the fifty-move rule"),
+            GameResult::InsufficientMaterial => write!(f, "Draw by insufficient material"),
+            GameResult::Repetition => write!(f, "Draw by threefold repetition"),
+        }
+    }
+}
+
+/// A game in progress: the current position plus the Zobrist hashes of the
+/// positions that led to it, so repetitions can be detected without the
+/// front-end keeping its own history.
+///
+/// The history lives here rather than in `GameState` so that the state
+/// cloned at every search node stays small.
+#[derive(Clone, Debug)]
+pub struct Game {
+    state: GameState,
+    /// Hashes of earlier positions since the last irreversible move
+    history: Vec<u64>,
+}
+
+impl Game {
+    /// Creates a game from the starting position.
+    pub fn new() -> Self {
+        Self::from_state(GameState::new())
+    }
+
+    /// Creates a game starting from an arbitrary position with no history.
+    pub fn from_state(state: GameState) -> Self {
+        Self {
+            state,
+            history: Vec::new(),
+        }
+    }
+
+    /// The current position.
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// Hashes of earlier positions that could still repeat, oldest first.
+    pub fn history(&self) -> &[u64] {
+        &self.history
+    }
+
+    /// Plays a move, recording the position it was played from.
+    /// Captures and pawn moves reset the halfmove clock; no earlier position
+    /// can recur after them, so the history is cleared.
+    pub fn apply_move(&mut self, mv: Move) {
+        let previous_hash = self.state.repetition_key();
+        self.state = self.state.apply_move(mv);
+
+        if self.state.halfmove_clock == 0 {
+            self.history.clear();
+        } else {
+            self.history.push(previous_hash);
+        }
+    }
+
+    /// Number of times the current position has occurred, including now.
+    pub fn repetition_count(&self) -> usize {
+        let hash = self.state.repetition_key();
+        1 + self.history.iter().filter(|&&h| h == hash).count()
+    }
+
+    /// Returns true if the current position has occurred three times.
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetition_count() >= 3
+    }
+
+    /// How the game ended, or `None` while it is still going. Checkmate
+    /// and stalemate take precedence over the draw rules.
+    pub fn result(&self) -> Option<GameResult> {
+        let state = &self.state;
+        if is_checkmate(state) {
+            Some(GameResult::Checkmate {
+                winner: state.turn.opponent(),
+            })
+        } else if is_stalemate(state) {
+            Some(GameResult::Stalemate)
+        } else if state.is_fifty_move_draw() {
+            Some(GameResult::FiftyMoveRule)
+        } else if state.is_insufficient_material() {
+            Some(GameResult::InsufficientMaterial)
+        } else if self.is_threefold_repetition() {
+            Some(GameResult::Repetition)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{File, Rank, Square};
+
+    fn sq(file: u8, rank: u8) -> Square {
+        Square::new(File::new(file).unwrap(), Rank::new(rank).unwrap())
+    }
+
+    #[test]
+    fn test_detects_threefold_repetition() {
+        let knight_dance = [
+            Move::new(sq(6, 0), sq(5, 2)), // Nf3
+            Move::new(sq(6, 7), sq(5, 5)), // Nf6
+            Move::new(sq(5, 2), sq(6, 0)), // Ng1
+            Move::new(sq(5, 5), sq(6, 7)), // Ng8
+        ];
+
+        let mut game = Game::new();
+        for mv in knight_dance {
+            game.apply_move(mv);
+        }
+        assert_eq!(game.repetition_count(), 2);
+        assert!(!game.is_threefold_repetition());
+
+        for mv in knight_dance {
+            game.apply_move(mv);
+        }
+        assert_eq!(game.repetition_count(), 3);
+        assert!(game.is_threefold_repetition());
+    }
+
+    #[test]
+    fn test_pawn_move_clears_history() {
+        let mut game = Game::new();
+        game.apply_move(Move::new(sq(6, 0), sq(5, 2))); // Nf3
+        assert_eq!(game.history().len(), 1);
+
+        game.apply_move(Move::new(sq(4, 6), sq(4, 4))); // e5
+        assert!(game.history().is_empty());
+        assert_eq!(game.repetition_count(), 1);
+    }
+
+    fn result_of(fen: &str) -> Option<GameResult> {
+        Game::from_state(GameState::from_fen(fen).unwrap()).result()
+    }
+
+    #[test]
+    fn test_game_result() {
+        assert_eq!(Game::new().result(), None);
+
+        // Back-rank mate delivered by Black
+        let mated = result_of("6k1/8/8/8/8/8/5PPP/r5K1 w - - 0 1");
+        assert_eq!(
+            mated,
+            Some(GameResult::Checkmate {
+                winner: Color::Black
+            })
+        );
+        assert_eq!(mated.unwrap().pgn(), "0-1");
+        assert_eq!(mated.unwrap().to_string(), "Black wins by checkmate");
+
+        assert_eq!(
+            result_of("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            Some(GameResult::Stalemate)
+        );
+        assert_eq!(
+            result_of("4k3/8/8/8/8/8/4R3/4K3 w - - 100 80"),
+            Some(GameResult::FiftyMoveRule)
+        );
+        assert_eq!(
+            result_of("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"),
+            Some(GameResult::InsufficientMaterial)
+        );
+
+        let mut game = Game::new();
+        for _ in 0..2 {
+            for mv in [
+                Move::new(sq(6, 0), sq(5, 2)),
+                Move::new(sq(6, 7), sq(5, 5)),
+                Move::new(sq(5, 2), sq(6, 0)),
+                Move::new(sq(5, 5), sq(6, 7)),
+            ] {
+                game.apply_move(mv);
+            }
+        }
+        assert_eq!(game.result(), Some(GameResult::Repetition));
+        assert_eq!(game.result().unwrap().pgn(), "1/2-1/2");
+    }
+}
@@ -1,8 +1,54 @@
 use crate::board::*;
+use crate::move_gen::{
+    generate_captures, generate_legal_moves, has_legal_move, is_checkmate, is_stalemate,
+    pawn_attacks,
+};
 /// Complete game state including board, turn, castling rights, etc.
 /// This module provides the main interface for chess game management.
 use crate::types::*;
 use crate::zobrist::ZOBRIST;
+use std::fmt;
+
+/// Reasons a move can be rejected by `GameState::try_apply_move` or
+/// `GameState::from_startpos_with_moves`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoveError {
+    NoPieceAtSource(Square),
+    WrongColor(Square),
+    SameSquare(Square),
+    OwnPieceAtDestination(Square),
+    InvalidPromotion(Move),
+    /// Text that is not a move in UCI notation, such as "e2e4" or "e7e8q".
+    InvalidUci(String),
+    /// A well-formed move that is not legal in the position, with the ply
+    /// at which it was played.
+    Illegal {
+        ply: usize,
+        mv: Move,
+    },
+    /// A two-square king move that is not castling from the home square
+    /// towards a friendly rook.
+    InvalidCastle(Move),
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::NoPieceAtSource(sq) => write!(f, "No piece at source square {sq}"),
+            MoveError::WrongColor(sq) => write!(f, "Piece at {sq} belongs to the side not to move"),
+            MoveError::SameSquare(sq) => write!(f, "Move starts and ends on {sq}"),
+            MoveError::OwnPieceAtDestination(sq) => {
+                write!(f, "Destination square {sq} is occupied by a friendly piece")
+            }
+            MoveError::InvalidPromotion(mv) => write!(f, "Invalid promotion: {mv}"),
+            MoveError::InvalidUci(text) => write!(f, "Not a UCI move: {text:?}"),
+            MoveError::Illegal { ply, mv } => write!(f, "Illegal move {mv} at ply {ply}"),
+            MoveError::InvalidCastle(mv) => write!(f, "Invalid castling move: {mv}"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
 
 /// Complete state of a chess game, matching FEN components.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -102,50 +148,82 @@ impl GameState {
         count
     }
 
+    /// Returns a copy with the pieces on `squares` removed, e.g. to set up
+    /// material odds. Castling rights that relied on a removed king or rook
+    /// are dropped.
+    pub fn with_squares_cleared(&self, squares: &[Square]) -> Self {
+        let mut state = self.clone();
+        for &square in squares {
+            state.board.set_square(square, None);
+            state.castling = state.castling.update_after_move(square, square);
+        }
+        state
+    }
+
     /// Applies a move to the game state, returning a new state.
     /// This does NOT check if the move is legal.
     pub fn apply_move(&self, mv: Move) -> Self {
         let mut new_state = self.clone();
+        self.play_onto(mv, &mut new_state);
+        new_state
+    }
+
+    /// Like `apply_move`, but writes the new state into `out`, so a loop
+    /// can reuse one buffer instead of building a state per move.
+    /// This does NOT check if the move is legal.
+    pub fn apply_move_into(&self, mv: Move, out: &mut GameState) {
+        out.clone_from(self);
+        self.play_onto(mv, out);
+    }
 
+    /// Plays `mv` on `new_state`, which must be a copy of `self`.
+    fn play_onto(&self, mv: Move, new_state: &mut GameState) {
         // Get the moving piece
         let piece = self
             .board
             .piece_at(mv.from)
             .expect("No piece at source square");
 
+        // Hand-built moves carry no flags, so special moves are recognised
+        // from the board; flags that are present must agree with it
+        let is_castle = mv.is_castle(piece);
+        let is_en_passant = piece.piece_type == PieceType::Pawn && Some(mv.to) == self.en_passant;
+        debug_assert!(!mv.is_castling() || is_castle, "stale castle flag on {mv}");
+        debug_assert!(
+            !mv.is_en_passant() || is_en_passant,
+            "stale en passant flag on {mv}"
+        );
+
         // Handle castling
-        if piece.piece_type == PieceType::King && mv.from.distance(mv.to) == 2 {
+        if is_castle {
             new_state.apply_castle(mv);
         } else {
             // Normal move or capture
             let captured = new_state.board.move_piece(mv.from, mv.to);
 
             // Handle en passant capture
-            if piece.piece_type == PieceType::Pawn && Some(mv.to) == self.en_passant {
+            if is_en_passant {
                 let capture_square = Square::new(mv.to.file(), mv.from.rank());
-                new_state.board.array_board.set_piece(capture_square, None);
-                // Update bitboards
-                new_state.board.bitboards = BitBoardSet::from_board(&new_state.board.array_board);
+                new_state.board.set_square(capture_square, None);
             }
 
             // Handle promotion
             if let Some(promotion) = mv.promotion {
                 new_state
                     .board
-                    .array_board
-                    .set_piece(mv.to, Some(Piece::new(promotion, piece.color)));
-                // Update bitboards
-                new_state.board.bitboards = BitBoardSet::from_board(&new_state.board.array_board);
+                    .set_square(mv.to, Some(Piece::new(promotion, piece.color)));
             }
 
-            // Update en passant square
+            // Update en passant square (only when an enemy pawn can actually capture)
             new_state.en_passant = None;
             if piece.piece_type == PieceType::Pawn && mv.from.distance(mv.to) == 2 {
                 let ep_square = Square::new(
                     mv.from.file(),
                     Rank::new((mv.from.rank().index() + mv.to.rank().index()) / 2).unwrap(),
                 );
-                new_state.en_passant = Some(ep_square);
+                if new_state.pawn_can_capture_en_passant(ep_square, piece.color.opponent()) {
+                    new_state.en_passant = Some(ep_square);
+                }
             }
 
             // Update halfmove clock
@@ -165,36 +243,205 @@ impl GameState {
         }
         new_state.turn = self.turn.opponent();
 
-        new_state
+        #[cfg(debug_assertions)]
+        debug_assert!(new_state.board.is_consistent());
+    }
+
+    /// Applies a move after checking that it is well-formed.
+    /// Unlike `apply_move` this never panics, but it still does NOT check full
+    /// legality (e.g. king safety); front-ends handling untrusted input should use it.
+    pub fn try_apply_move(&self, mv: Move) -> Result<Self, MoveError> {
+        let piece = self
+            .board
+            .piece_at(mv.from)
+            .ok_or(MoveError::NoPieceAtSource(mv.from))?;
+
+        if piece.color != self.turn {
+            return Err(MoveError::WrongColor(mv.from));
+        }
+        if mv.from == mv.to {
+            return Err(MoveError::SameSquare(mv.from));
+        }
+        if self.board.array_board.is_color(mv.to, self.turn) {
+            return Err(MoveError::OwnPieceAtDestination(mv.to));
+        }
+        if let Some(promotion) = mv.promotion {
+            let valid = piece.piece_type == PieceType::Pawn
+                && mv.to.rank() == self.turn.promotion_rank()
+                && !matches!(promotion, PieceType::Pawn | PieceType::King);
+            if !valid {
+                return Err(MoveError::InvalidPromotion(mv));
+            }
+        }
+        if mv.is_castle(piece) {
+            let home_rank = self.turn.opponent().promotion_rank();
+            let king_home = Square::new(File::new(4).unwrap(), home_rank);
+            let rook = Piece::new(PieceType::Rook, self.turn);
+            let rook_from = castle_rook_squares(mv).0;
+            // Every square between king and rook must be empty, or the rook
+            // would land on a piece
+            let (low, high) = match rook_from.file().index() {
+                0 => (1, 4),
+                _ => (5, 7),
+            };
+            let path_clear = (low..high).all(|file| {
+                let square = Square::new(File::new(file).unwrap(), home_rank);
+                self.board.piece_at(square).is_none()
+            });
+            let valid = mv.from == king_home
+                && mv.to.rank() == home_rank
+                && self.board.piece_at(rook_from) == Some(rook)
+                && path_clear;
+            if !valid {
+                return Err(MoveError::InvalidCastle(mv));
+            }
+        }
+
+        Ok(self.apply_move(mv))
+    }
+
+    /// Applies `moves` in order, returning the final state.
+    /// Like `apply_move`, this does NOT check legality.
+    pub fn apply_moves(&self, moves: &[Move]) -> Self {
+        let mut state = self.clone();
+        let mut next = self.clone();
+        for &mv in moves {
+            state.apply_move_into(mv, &mut next);
+            std::mem::swap(&mut state, &mut next);
+        }
+        state
+    }
+
+    /// Plays a list of UCI moves ("e2e4", "e7e8q") from the starting
+    /// position, as `position startpos moves ...` does. Every move must be
+    /// legal; the first one that is not, or does not parse, is returned as
+    /// the error.
+    pub fn from_startpos_with_moves(ucis: &[&str]) -> Result<Self, MoveError> {
+        let mut state = Self::new();
+        for (ply, text) in ucis.iter().enumerate() {
+            let mv = parse_uci_move(text)?;
+            let legal = state.legal_move(mv).ok_or(MoveError::Illegal { ply, mv })?;
+            state = state.apply_move(legal);
+        }
+        Ok(state)
+    }
+
+    /// Parses a move in UCI notation ("e2e4", "e7e8q") for this position,
+    /// returning `None` if it does not parse or is not legal.
+    pub fn parse_uci(&self, text: &str) -> Option<Move> {
+        parse_uci_move(text).ok().and_then(|mv| self.legal_move(mv))
+    }
+
+    /// The legal move equal to `mv`, as generated with its special-move
+    /// flags, or `None` if `mv` is not legal here.
+    pub fn legal_move(&self, mv: Move) -> Option<Move> {
+        generate_legal_moves(self)
+            .iter()
+            .find(|&&legal| legal == mv)
+            .copied()
+    }
+
+    /// Applies `moves` in order with `try_apply_move`, stopping at the first
+    /// move that fails and returning its index along with the error.
+    pub fn try_apply_moves(&self, moves: &[Move]) -> Result<Self, (usize, MoveError)> {
+        let mut state = self.clone();
+        for (index, &mv) in moves.iter().enumerate() {
+            state = state.try_apply_move(mv).map_err(|e| (index, e))?;
+        }
+        Ok(state)
+    }
+
+    /// Applies `mv` and updates `hash`, the Zobrist hash of this position,
+    /// to that of the new one without rehashing the whole board.
+    ///
+    /// Besides the pieces that moved, the old castling rights and en passant
+    /// file are xored out and the new ones xored in, and the side to move is
+    /// toggled. Forgetting either delta leaves stale keys in the hash, which
+    /// silently corrupts transposition table lookups.
+    pub fn apply_move_with_hash(&self, mv: Move, hash: u64) -> (Self, u64) {
+        let new_state = self.apply_move(mv);
+        let piece = self
+            .board
+            .piece_at(mv.from)
+            .expect("No piece at source square");
+
+        let mut hash = hash ^ ZOBRIST.piece_square_key(piece, mv.from);
+        let placed = mv
+            .promotion
+            .map_or(piece, |promotion| Piece::new(promotion, piece.color));
+        hash ^= ZOBRIST.piece_square_key(placed, mv.to);
+
+        if let Some((square, captured)) = self.captured_piece(mv) {
+            hash ^= ZOBRIST.piece_square_key(captured, square);
+        }
+        if piece.piece_type == PieceType::King && mv.from.distance(mv.to) == 2 {
+            let (rook_from, rook_to) = castle_rook_squares(mv);
+            let rook = Piece::new(PieceType::Rook, piece.color);
+            hash ^= ZOBRIST.piece_square_key(rook, rook_from);
+            hash ^= ZOBRIST.piece_square_key(rook, rook_to);
+        }
+
+        hash ^= ZOBRIST.castling_key(self.castling) ^ ZOBRIST.castling_key(new_state.castling);
+        hash ^=
+            ZOBRIST.en_passant_key(self.en_passant) ^ ZOBRIST.en_passant_key(new_state.en_passant);
+        hash ^= ZOBRIST.side_to_move_key(self.turn) ^ ZOBRIST.side_to_move_key(new_state.turn);
+
+        (new_state, hash)
     }
 
     /// Applies a castling move.
     fn apply_castle(&mut self, mv: Move) {
-        let (rook_from, rook_to) = if mv.to.file().index() > mv.from.file().index() {
-            // Kingside castling
-            let rank = mv.from.rank();
-            (
-                Square::new(File::new(7).unwrap(), rank), // h-file
-                Square::new(File::new(5).unwrap(), rank), // f-file
-            )
-        } else {
-            // Queenside castling
-            let rank = mv.from.rank();
-            (
-                Square::new(File::new(0).unwrap(), rank), // a-file
-                Square::new(File::new(3).unwrap(), rank), // d-file
-            )
-        };
+        let (rook_from, rook_to) = castle_rook_squares(mv);
 
         // Move king
         self.board.move_piece(mv.from, mv.to);
         // Move rook
         self.board.move_piece(rook_from, rook_to);
 
+        // Castling is never a double pawn push
+        self.en_passant = None;
+
         // Castling doesn't reset halfmove clock
         self.halfmove_clock += 1;
     }
 
+    /// The piece `mv` captures and the square it stands on, which for en
+    /// passant is beside the destination rather than on it.
+    pub fn captured_piece(&self, mv: Move) -> Option<(Square, Piece)> {
+        if let Some(piece) = self.board.piece_at(mv.to) {
+            return Some((mv.to, piece));
+        }
+
+        let mover = self.board.piece_at(mv.from)?;
+        if mover.piece_type == PieceType::Pawn && Some(mv.to) == self.en_passant {
+            let square = Square::new(mv.to.file(), mv.from.rank());
+            return self.board.piece_at(square).map(|piece| (square, piece));
+        }
+        None
+    }
+
+    /// Returns true if the side to move has a pawn next to the pawn that just
+    /// double-pushed, i.e. the en passant square is actually capturable.
+    pub fn can_capture_en_passant(&self) -> bool {
+        self.en_passant
+            .is_some_and(|ep_square| self.pawn_can_capture_en_passant(ep_square, self.turn))
+    }
+
+    /// Returns true if a `capturer` pawn stands beside the pawn that skipped over `ep_square`.
+    fn pawn_can_capture_en_passant(&self, ep_square: Square, capturer: Color) -> bool {
+        let Some(pawn_rank) = ep_square.rank().offset(-capturer.pawn_direction()) else {
+            return false;
+        };
+        let capturers = self.board.bitboards.pieces(PieceType::Pawn, capturer);
+
+        [-1, 1].iter().any(|&df| {
+            ep_square
+                .file()
+                .offset(df)
+                .is_some_and(|file| capturers.contains(Square::new(file, pawn_rank)))
+        })
+    }
+
     /// Returns the side to move.
     pub fn side_to_move(&self) -> Color {
         self.turn
@@ -225,43 +472,123 @@ impl GameState {
         false
     }
 
-    /// Returns true if the given square is attacked by enemy pawns.
-    fn is_pawn_attacked(&self, square: Square, attacker: Color) -> bool {
-        let pawn_attacks = match attacker {
-            Color::White => {
-                // White pawns attack diagonally upward
-                let mut attacks = BitBoard::EMPTY;
-                if let Some(left) = square.file().offset(-1) {
-                    if let Some(down) = square.rank().offset(-1) {
-                        attacks = attacks.set(Square::new(left, down));
-                    }
-                }
-                if let Some(right) = square.file().offset(1) {
-                    if let Some(down) = square.rank().offset(-1) {
-                        attacks = attacks.set(Square::new(right, down));
-                    }
-                }
-                attacks
-            }
-            Color::Black => {
-                // Black pawns attack diagonally downward
-                let mut attacks = BitBoard::EMPTY;
-                if let Some(left) = square.file().offset(-1) {
-                    if let Some(up) = square.rank().offset(1) {
-                        attacks = attacks.set(Square::new(left, up));
-                    }
+    /// Returns the squares of every `attacker` piece that attacks `square`.
+    pub fn attackers_to(&self, square: Square, attacker: Color) -> BitBoard {
+        self.attackers_to_past(square, attacker, None)
+    }
+
+    /// Returns true if the king move `mv` of the side to move does not
+    /// leave the king in check. Tests the destination directly instead of
+    /// playing the move, with the king's own square treated as empty so
+    /// it cannot shield itself from a slider behind it.
+    pub fn king_move_is_safe(&self, mv: Move) -> bool {
+        self.attackers_to_past(mv.to, self.turn.opponent(), Some(mv.from))
+            .is_empty()
+    }
+
+    /// `attackers_to`, with sliders seeing through `transparent`.
+    fn attackers_to_past(
+        &self,
+        square: Square,
+        attacker: Color,
+        transparent: Option<Square>,
+    ) -> BitBoard {
+        const KNIGHT_MOVES: [(i8, i8); 8] = [
+            (-2, -1),
+            (-2, 1),
+            (-1, -2),
+            (-1, 2),
+            (1, -2),
+            (1, 2),
+            (2, -1),
+            (2, 1),
+        ];
+        const RAYS: [(i8, i8); 8] = [
+            (-1, -1),
+            (-1, 1),
+            (1, -1),
+            (1, 1),
+            (-1, 0),
+            (1, 0),
+            (0, -1),
+            (0, 1),
+        ];
+
+        let bitboards = &self.board.bitboards;
+        let step = |square: Square, (df, dr): (i8, i8)| {
+            Some(Square::new(
+                square.file().offset(df)?,
+                square.rank().offset(dr)?,
+            ))
+        };
+        let mask = |deltas: &[(i8, i8)]| {
+            deltas
+                .iter()
+                .filter_map(|&delta| step(square, delta))
+                .fold(BitBoard::EMPTY, |mask, sq| mask.set(sq))
+        };
+
+        let mut attackers = pawn_attacks(BitBoard::from_square(square), attacker.opponent())
+            & bitboards.pieces(PieceType::Pawn, attacker);
+        attackers |= mask(&KNIGHT_MOVES) & bitboards.pieces(PieceType::Knight, attacker);
+        attackers |= mask(&RAYS) & bitboards.pieces(PieceType::King, attacker);
+
+        for (index, &delta) in RAYS.iter().enumerate() {
+            let slider = if index < 4 {
+                PieceType::Bishop
+            } else {
+                PieceType::Rook
+            };
+            let mut current = square;
+            while let Some(next) = step(current, delta) {
+                current = next;
+                if Some(current) == transparent {
+                    continue;
                 }
-                if let Some(right) = square.file().offset(1) {
-                    if let Some(up) = square.rank().offset(1) {
-                        attacks = attacks.set(Square::new(right, up));
+                if let Some(piece) = self.board.piece_at(current) {
+                    if piece.color == attacker
+                        && (piece.piece_type == slider || piece.piece_type == PieceType::Queen)
+                    {
+                        attackers = attackers.set(current);
                     }
+                    break;
                 }
-                attacks
             }
-        };
+        }
+
+        attackers
+    }
+
+    /// Lists every `by` piece attacking `square` with its type, in square
+    /// order. Useful for display and static exchange evaluation.
+    pub fn attackers(&self, square: Square, by: Color) -> Vec<(Square, PieceType)> {
+        self.attackers_to(square, by)
+            .iter()
+            .filter_map(|from| Some((from, self.board.piece_at(from)?.piece_type)))
+            .collect()
+    }
+
+    /// Returns the squares of the pieces giving check to the side to move.
+    pub fn checkers(&self) -> BitBoard {
+        let king_square = self.board.array_board.king_square(self.turn);
+        self.attackers_to(king_square, self.turn.opponent())
+    }
+
+    /// Returns every square attacked by `by`, whether empty or occupied.
+    /// Intended for display (e.g. a threat overlay), not for the search.
+    pub fn attacked_squares(&self, by: Color) -> BitBoard {
+        (0..64)
+            .filter_map(Square::from_index)
+            .filter(|&square| self.is_attacked_by(square, by))
+            .fold(BitBoard::EMPTY, |attacked, square| attacked.set(square))
+    }
 
+    /// Returns true if the given square is attacked by enemy pawns.
+    fn is_pawn_attacked(&self, square: Square, attacker: Color) -> bool {
+        // An attacking pawn stands where a defending pawn on `square` would capture
+        let attacker_squares = pawn_attacks(BitBoard::from_square(square), attacker.opponent());
         let enemy_pawns = self.board.bitboards.pieces(PieceType::Pawn, attacker);
-        !pawn_attacks.intersection(enemy_pawns).is_empty()
+        !(attacker_squares & enemy_pawns).is_empty()
     }
 
     /// Returns true if the given square is attacked by enemy knights.
@@ -381,18 +708,104 @@ impl GameState {
         !king_attacks.intersection(enemy_king).is_empty()
     }
 
+    /// Returns true if playing `mv` puts the opponent in check.
+    pub fn gives_check(&self, mv: Move) -> bool {
+        self.apply_move(mv).is_in_check()
+    }
+
+    /// Returns true if the side to move has at least one legal move.
+    pub fn has_legal_move(&self) -> bool {
+        has_legal_move(self)
+    }
+
+    /// Returns the legal moves of the piece on `square`, empty if the square
+    /// is empty or holds a piece of the side not to move.
+    pub fn legal_moves_from(&self, square: Square) -> Vec<Move> {
+        generate_legal_moves(self)
+            .iter()
+            .filter(|mv| mv.from == square)
+            .copied()
+            .collect()
+    }
+
+    /// Returns true if playing `mv` checkmates the opponent.
+    pub fn gives_checkmate(&self, mv: Move) -> bool {
+        is_checkmate(&self.apply_move(mv))
+    }
+
+    /// Returns true if playing `mv` leaves the opponent stalemated.
+    pub fn gives_stalemate(&self, mv: Move) -> bool {
+        is_stalemate(&self.apply_move(mv))
+    }
+
     /// Returns true if the current side to move is in check.
     pub fn is_in_check(&self) -> bool {
         let king_square = self.board.array_board.king_square(self.turn);
         self.is_attacked_by(king_square, self.turn.opponent())
     }
 
+    /// Returns true if the side to move is not in check and has no capture
+    /// or promotion, so its static evaluation can be trusted as it stands.
+    pub fn is_quiet(&self) -> bool {
+        !self.is_in_check() && generate_captures(self).is_empty()
+    }
+
     /// Returns true if the given side is in check.
     pub fn is_side_in_check(&self, color: Color) -> bool {
         let king_square = self.board.array_board.king_square(color);
         self.is_attacked_by(king_square, color.opponent())
     }
 
+    /// Returns true if the position could arise in a game: each side has
+    /// exactly one king, no pawns stand on the first or eighth rank, and the
+    /// side that just moved is not left in check.
+    /// Searching a position that fails this check can panic or give nonsense.
+    pub fn is_legal_position(&self) -> bool {
+        let bitboards = &self.board.bitboards;
+        for color in [Color::White, Color::Black] {
+            if bitboards.pieces(PieceType::King, color).count() != 1 {
+                return false;
+            }
+        }
+
+        let back_ranks = BitBoard::rank_mask(Rank::FIRST) | BitBoard::rank_mask(Rank::EIGHTH);
+        let pawns = bitboards.pieces(PieceType::Pawn, Color::White)
+            | bitboards.pieces(PieceType::Pawn, Color::Black);
+        if !(pawns & back_ranks).is_empty() {
+            return false;
+        }
+
+        !self.is_side_in_check(self.turn.opponent())
+    }
+
+    /// Checks that no side has more pieces than a game allows: at most one
+    /// king, at most eight pawns, and no more extra minor or major pieces
+    /// than pawns that could have promoted to them.
+    /// Unlike `is_legal_position`, a missing king is accepted so that
+    /// partial test positions pass.
+    pub fn validate_piece_counts(&self) -> Result<(), String> {
+        for color in [Color::White, Color::Black] {
+            let count = |piece_type| self.board.bitboards.pieces(piece_type, color).count() as i32;
+
+            let kings = count(PieceType::King);
+            if kings > 1 {
+                return Err(format!("{color} has {kings} kings"));
+            }
+
+            let pawns = count(PieceType::Pawn);
+            let promoted = (count(PieceType::Knight) - 2).max(0)
+                + (count(PieceType::Bishop) - 2).max(0)
+                + (count(PieceType::Rook) - 2).max(0)
+                + (count(PieceType::Queen) - 1).max(0);
+            if pawns + promoted > 8 {
+                return Err(format!(
+                    "{color} has {pawns} pawns and {promoted} promoted pieces, more than 8 pawns allow"
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Computes the Zobrist hash for this position.
     /// This hash uniquely identifies the position for transposition table lookups.
     pub fn zobrist_hash(&self) -> u64 {
@@ -417,6 +830,32 @@ impl GameState {
 
         hash
     }
+
+    /// Hash for repetition detection. Unlike `zobrist_hash`, it only
+    /// includes the en passant file when a pawn can actually capture there.
+    pub fn repetition_key(&self) -> u64 {
+        let hash = self.zobrist_hash();
+        if self.can_capture_en_passant() {
+            hash
+        } else {
+            hash ^ ZOBRIST.en_passant_key(self.en_passant)
+        }
+    }
+
+    /// Returns true if both states are the same position for play: same
+    /// pieces, side to move, castling rights and capturable en passant
+    /// square. Unlike `==`, the move counters are ignored, so transpositions
+    /// and repetitions compare equal. `repetition_key` is the hashed form of
+    /// this comparison.
+    pub fn same_position(&self, other: &GameState) -> bool {
+        let usable_en_passant =
+            |state: &GameState| state.en_passant.filter(|_| state.can_capture_en_passant());
+
+        self.board == other.board
+            && self.turn == other.turn
+            && self.castling == other.castling
+            && usable_en_passant(self) == usable_en_passant(other)
+    }
 }
 
 /// Helper struct for counting material.
@@ -457,9 +896,66 @@ impl Default for GameState {
     }
 }
 
+/// Plays `moves` from the standard starting position.
+pub fn replay(moves: &[Move]) -> GameState {
+    replay_from(&GameState::new(), moves)
+}
+
+/// Plays `moves` from `start`, e.g. to rebuild the position after an undo.
+/// Moves are not checked for legality.
+pub fn replay_from(start: &GameState, moves: &[Move]) -> GameState {
+    start.apply_moves(moves)
+}
+
+/// Parses a move in UCI notation without checking it against a position.
+/// The flags of castling and en passant moves are left unset; use
+/// `GameState::parse_uci` to get the legal move with its flags.
+pub fn parse_uci_move(text: &str) -> Result<Move, MoveError> {
+    let invalid = || MoveError::InvalidUci(text.to_string());
+    let square = |range| {
+        text.get(range)
+            .and_then(|name: &str| name.parse::<Square>().ok())
+    };
+    let from = square(0..2).ok_or_else(invalid)?;
+    let to = square(2..4).ok_or_else(invalid)?;
+    let promotion = match text.get(4..) {
+        Some("") => None,
+        Some("q" | "Q") => Some(PieceType::Queen),
+        Some("r" | "R") => Some(PieceType::Rook),
+        Some("b" | "B") => Some(PieceType::Bishop),
+        Some("n" | "N") => Some(PieceType::Knight),
+        _ => return Err(invalid()),
+    };
+    Ok(Move {
+        from,
+        to,
+        promotion,
+        flags: MoveFlags::NONE,
+    })
+}
+
+/// The rook's source and destination squares for a castling king move.
+pub(crate) fn castle_rook_squares(mv: Move) -> (Square, Square) {
+    let rank = mv.from.rank();
+    if mv.to.file().index() > mv.from.file().index() {
+        // Kingside castling
+        (
+            Square::new(File::new(7).unwrap(), rank), // h-file
+            Square::new(File::new(5).unwrap(), rank), // f-file
+        )
+    } else {
+        // Queenside castling
+        (
+            Square::new(File::new(0).unwrap(), rank), // a-file
+            Square::new(File::new(3).unwrap(), rank), // d-file
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::position_builder::PositionBuilder;
 
     #[test]
     fn test_starting_position() {
@@ -481,21 +977,575 @@ mod tests {
 
         let new_state = state.apply_move(mv);
         assert_eq!(new_state.turn, Color::Black);
-        assert_eq!(new_state.en_passant, Some(Square::from_index(20).unwrap())); // e3
+        // No black pawn can capture on e3, so no en passant square is recorded
+        assert_eq!(new_state.en_passant, None);
         assert_eq!(new_state.halfmove_clock, 0);
         assert_eq!(new_state.fullmove_number, 1);
+        assert_eq!(
+            new_state.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        );
     }
 
     #[test]
-    fn test_is_attacked() {
-        let mut state = GameState::empty();
+    fn test_en_passant_recorded_when_capturable() {
+        let state =
+            GameState::from_fen("rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 3")
+                .unwrap();
+        let mv = Move::new(
+            Square::from_index(12).unwrap(), // e2
+            Square::from_index(28).unwrap(), // e4
+        );
+
+        let new_state = state.apply_move(mv);
+        assert_eq!(new_state.en_passant, Some(Square::from_index(20).unwrap())); // e3
+        assert!(new_state.can_capture_en_passant());
+        assert!(new_state.to_fen().contains(" b KQkq e3 "));
+    }
+
+    #[test]
+    fn test_castling_clears_en_passant() {
+        let state = GameState::from_fen("4k3/8/8/3pP3/8/8/8/4K2R w K d6 0 1").unwrap();
+        let castle = Move::new(
+            Square::from_index(4).unwrap(), // e1
+            Square::from_index(6).unwrap(), // g1
+        );
+
+        assert_eq!(state.apply_move(castle).en_passant, None);
+    }
+
+    #[test]
+    fn test_apply_move_into_matches_apply_move() {
+        let fens = [
+            crate::positions::STARTING,
+            crate::perft::positions::KIWIPETE,
+            // Promotions with and without capture, and en passant
+            "1n2k3/P7/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            "4k3/8/8/8/8/8/p7/1N2K2R b K - 3 40",
+        ];
+        let mut out = GameState::new();
+        let mut kinds = (false, false, false);
+        for fen in fens {
+            let state = GameState::from_fen(fen).unwrap();
+            for &mv in generate_legal_moves(&state).iter() {
+                state.apply_move_into(mv, &mut out);
+                assert_eq!(out, state.apply_move(mv), "{fen} {mv}");
+
+                let piece = state.board.piece_at(mv.from).unwrap();
+                kinds.0 |= piece.piece_type == PieceType::King && mv.from.distance(mv.to) == 2;
+                kinds.1 |= mv.promotion.is_some();
+                kinds.2 |= piece.piece_type == PieceType::Pawn && Some(mv.to) == state.en_passant;
+            }
+        }
+        // Castling, promotion and en passant were all covered
+        assert_eq!(kinds, (true, true, true));
+    }
+
+    #[test]
+    fn test_castling_rights_after_corner_moves() {
+        let sq = |name: &str| name.parse::<Square>().unwrap();
+        let rights_after = |fen: &str, from: &str, to: &str| {
+            let state = GameState::from_fen(fen).unwrap();
+            let fen = state.apply_move(Move::new(sq(from), sq(to))).to_fen();
+            fen.split(' ').nth(2).unwrap().to_string()
+        };
+        let both_sides = "r3k2r/8/8/8/8/8/6b1/R3K2R";
+
+        // A bishop taking the h1 rook removes only that rook's right
+        assert_eq!(
+            rights_after(&format!("{both_sides} b KQkq - 0 1"), "g2", "h1"),
+            "Qkq"
+        );
+        // A rook taking a rook on a corner ends both sides' rights there
+        assert_eq!(
+            rights_after(&format!("{both_sides} w KQkq - 0 1"), "a1", "a8"),
+            "Kk"
+        );
+        assert_eq!(
+            rights_after(&format!("{both_sides} w KQkq - 0 1"), "h1", "h2"),
+            "Qkq"
+        );
+        // A king move ends its own side's rights and nothing else
+        assert_eq!(
+            rights_after(&format!("{both_sides} w KQkq - 0 1"), "e1", "d1"),
+            "kq"
+        );
+        assert_eq!(
+            rights_after("r3k2r/8/8/8/8/8/8/1K6 w kq - 0 1", "b1", "a1"),
+            "kq"
+        );
+        // Other moves leave every right alone
+        assert_eq!(
+            rights_after(&format!("{both_sides} b KQkq - 0 1"), "g2", "f1"),
+            "KQkq"
+        );
+    }
+
+    #[test]
+    fn test_gives_checkmate_and_stalemate() {
+        let sq = |name: &str| name.parse::<Square>().unwrap();
+
+        // Back-rank mate: the king's own pawns block every escape
+        let state = GameState::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert!(state.gives_checkmate(Move::new(sq("a1"), sq("a8"))));
+        assert!(!state.gives_checkmate(Move::new(sq("a1"), sq("a7"))));
+
+        // With h7 open the same check is not mate
+        let state = GameState::from_fen("6k1/5pp1/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let check = Move::new(sq("a1"), sq("a8"));
+        assert!(state.gives_check(check));
+        assert!(!state.gives_checkmate(check));
+        assert!(!state.gives_stalemate(check));
+
+        let state = GameState::from_fen("7k/8/6K1/8/8/8/8/5Q2 w - - 0 1").unwrap();
+        assert!(state.gives_stalemate(Move::new(sq("f1"), sq("f7"))));
+        assert!(!state.gives_stalemate(Move::new(sq("f1"), sq("f8"))));
+    }
+
+    #[test]
+    fn test_checkers() {
+        let sq = |name: &str| name.parse::<Square>().unwrap();
+        assert!(GameState::new().checkers().is_empty());
+
+        // Double check from the knight on f6 and the rook on e1
+        let state = GameState::from_fen("4k3/8/5N2/8/8/8/8/4RK2 b - - 0 1").unwrap();
+        let checkers = state.checkers();
+        assert_eq!(checkers.count(), 2);
+        assert!(checkers.contains(sq("f6")) && checkers.contains(sq("e1")));
+
+        // A blocked slider and an adjacent pawn
+        let state = GameState::from_fen("4k3/3P4/8/8/4n3/8/8/4RK2 b - - 0 1").unwrap();
+        assert_eq!(state.checkers(), BitBoard::from_square(sq("d7")));
+    }
+
+    #[test]
+    fn test_attackers() {
+        let sq = |name: &str| name.parse::<Square>().unwrap();
+        // d5 is hit by the bishop on g2 and the knight on c3; the rook on
+        // d1 is blocked by the pawn on d3
+        let state = GameState::from_fen("4k3/8/8/8/8/2NP4/6B1/3RK3 w - - 0 1").unwrap();
+
+        assert_eq!(
+            state.attackers(sq("d5"), Color::White),
+            vec![(sq("g2"), PieceType::Bishop), (sq("c3"), PieceType::Knight)]
+        );
+        assert!(state.attackers(sq("d5"), Color::Black).is_empty());
+    }
+
+    #[test]
+    fn test_captured_piece() {
+        let sq = |name: &str| name.parse::<Square>().unwrap();
+        let state = GameState::from_fen("4k3/8/2n5/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let black_pawn = Piece::new(PieceType::Pawn, Color::Black);
+
+        // En passant takes the pawn on d5, not anything on d6
+        assert_eq!(
+            state.captured_piece(Move::new(sq("e5"), sq("d6"))),
+            Some((sq("d5"), black_pawn))
+        );
+        assert_eq!(state.captured_piece(Move::new(sq("e5"), sq("e6"))), None);
+        assert_eq!(state.captured_piece(Move::new(sq("e1"), sq("d1"))), None);
+
+        let state = GameState::from_fen("4k3/8/2n5/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            state.captured_piece(Move::new(sq("d5"), sq("c6"))),
+            Some((sq("c6"), Piece::new(PieceType::Knight, Color::Black)))
+        );
+    }
+
+    #[test]
+    fn test_apply_en_passant_capture() {
+        let state = GameState::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let mv = Move::new(
+            Square::from_index(36).unwrap(), // e5
+            Square::from_index(43).unwrap(), // d6
+        );
+
+        let new_state = state.apply_move(mv);
+        assert!(new_state
+            .board
+            .piece_at(Square::from_index(35).unwrap())
+            .is_none()); // d5
+        assert_eq!(
+            new_state.board.piece_at(Square::from_index(43).unwrap()),
+            Some(Piece::new(PieceType::Pawn, Color::White))
+        );
+        assert!(new_state
+            .board
+            .bitboards
+            .pieces(PieceType::Pawn, Color::Black)
+            .is_empty());
+
+        #[cfg(debug_assertions)]
+        assert!(new_state.board.is_consistent());
+    }
+
+    #[test]
+    fn test_apply_promotions() {
+        let state = GameState::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let a7 = Square::from_index(48).unwrap();
+        let a8 = Square::from_index(56).unwrap();
+        let b8 = Square::from_index(57).unwrap();
+
+        for promotion in [
+            PieceType::Queen,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+        ] {
+            for to in [a8, b8] {
+                let new_state = state.apply_move(Move::new_promotion(a7, to, promotion));
+                assert_eq!(
+                    new_state.board.piece_at(to),
+                    Some(Piece::new(promotion, Color::White))
+                );
+                assert!(new_state
+                    .board
+                    .bitboards
+                    .pieces(PieceType::Pawn, Color::White)
+                    .is_empty());
+                assert_eq!(
+                    new_state.board.bitboards.pieces(promotion, Color::White),
+                    BitBoard::from_square(to)
+                );
+
+                #[cfg(debug_assertions)]
+                assert!(new_state.board.is_consistent());
+            }
+        }
+    }
+
+    #[test]
+    fn test_try_apply_move() {
+        let state = GameState::new();
+        let e2 = Square::from_index(12).unwrap();
+        let e3 = Square::from_index(20).unwrap();
+        let e4 = Square::from_index(28).unwrap();
+        let e7 = Square::from_index(52).unwrap();
+
+        assert_eq!(
+            state.try_apply_move(Move::new(e2, e4)),
+            Ok(state.apply_move(Move::new(e2, e4)))
+        );
+        assert_eq!(
+            state.try_apply_move(Move::new(e3, e4)),
+            Err(MoveError::NoPieceAtSource(e3))
+        );
+        assert_eq!(
+            state.try_apply_move(Move::new(e7, e4)),
+            Err(MoveError::WrongColor(e7))
+        );
+        assert!(state
+            .try_apply_move(Move::new_promotion(e2, e3, PieceType::Queen))
+            .is_err());
+    }
+
+    #[test]
+    fn test_try_apply_move_rejects_malformed_castling() {
+        let e1 = Square::from_index(4).unwrap();
+        let g1 = Square::from_index(6).unwrap();
+        let e3 = Square::from_index(20).unwrap();
+
+        // Two squares up the file is not castling and must not reach apply_castle
+        let bare_kings = GameState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            bare_kings.try_apply_move(Move::new(e1, e3)),
+            Err(MoveError::InvalidCastle(Move::new(e1, e3)))
+        );
+        assert_eq!(
+            bare_kings.try_apply_move(Move::new(e1, g1)),
+            Err(MoveError::InvalidCastle(Move::new(e1, g1)))
+        );
 
-        // Place a white rook on e4
-        state.board.array_board.set_piece(
-            Square::from_index(28).unwrap(),
-            Some(Piece::new(PieceType::Rook, Color::White)),
+        let with_rook = GameState::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        assert_eq!(
+            with_rook.try_apply_move(Move::new(e1, g1)),
+            Ok(with_rook.apply_move(Move::new(e1, g1)))
         );
-        state.board.bitboards = BitBoardSet::from_board(&state.board.array_board);
+
+        // Castling through a piece on f1 or b1
+        let c1 = Square::from_index(2).unwrap();
+        for (fen, to) in [
+            ("4k3/8/8/8/8/8/8/4KB1R w K - 0 1", g1),
+            ("4k3/8/8/8/8/8/8/RN2K3 w Q - 0 1", c1),
+        ] {
+            let blocked = GameState::from_fen(fen).unwrap();
+            assert_eq!(
+                blocked.try_apply_move(Move::new(e1, to)),
+                Err(MoveError::InvalidCastle(Move::new(e1, to))),
+                "{fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_legal_position() {
+        assert!(GameState::new().is_legal_position());
+
+        // White to move while Black's king is attacked by the rook: Black's last move was illegal
+        let non_mover_in_check = GameState::from_fen("4k3/8/8/8/8/8/8/4RK2 w - - 0 1").unwrap();
+        assert!(!non_mover_in_check.is_legal_position());
+        // The same position with Black to move is an ordinary check
+        let mover_in_check = GameState::from_fen("4k3/8/8/8/8/8/8/4RK2 b - - 0 1").unwrap();
+        assert!(mover_in_check.is_legal_position());
+
+        let missing_king = GameState::from_fen("8/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(!missing_king.is_legal_position());
+
+        let two_kings = GameState::from_fen("4k3/8/8/8/8/8/8/K3K3 w - - 0 1").unwrap();
+        assert!(!two_kings.is_legal_position());
+
+        let back_rank_pawn = GameState::from_fen("4k2P/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(!back_rank_pawn.is_legal_position());
+    }
+
+    #[test]
+    fn test_attacked_squares() {
+        let attacked = GameState::new().attacked_squares(Color::White);
+
+        // Every second- and third-rank square, and the back rank except the corners
+        for file in 0..8 {
+            let file = File::new(file).unwrap();
+            assert!(attacked.contains(Square::new(file, Rank::new(1).unwrap())));
+            assert!(attacked.contains(Square::new(file, Rank::new(2).unwrap())));
+            assert!(!attacked.contains(Square::new(file, Rank::new(3).unwrap())));
+        }
+        assert!(!attacked.contains(Square::from_index(0).unwrap())); // a1
+        assert!(attacked.contains(Square::from_index(1).unwrap())); // b1
+        assert!(attacked.contains(Square::from_index(6).unwrap())); // g1
+        assert!(!attacked.contains(Square::from_index(7).unwrap())); // h1
+        assert_eq!(attacked.count(), 22);
+
+        // Black's attacks mirror White's
+        assert_eq!(GameState::new().attacked_squares(Color::Black).count(), 22);
+    }
+
+    #[test]
+    fn test_apply_moves() {
+        let sq = |file, rank| Square::new(File::new(file).unwrap(), Rank::new(rank).unwrap());
+
+        // 1. e4 c5 2. Nf3 d6
+        let sicilian = [
+            Move::new(sq(4, 1), sq(4, 3)),
+            Move::new(sq(2, 6), sq(2, 4)),
+            Move::new(sq(6, 0), sq(5, 2)),
+            Move::new(sq(3, 6), sq(3, 5)),
+        ];
+        let expected = "rnbqkbnr/pp2pppp/3p4/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 3";
+        assert_eq!(GameState::new().apply_moves(&sicilian).to_fen(), expected);
+        assert_eq!(
+            GameState::new()
+                .try_apply_moves(&sicilian)
+                .unwrap()
+                .to_fen(),
+            expected
+        );
+
+        // The third move moves a black piece on White's turn
+        let mut bad = sicilian;
+        bad[2] = Move::new(sq(3, 6), sq(3, 5));
+        assert_eq!(
+            GameState::new().try_apply_moves(&bad).unwrap_err(),
+            (2, MoveError::WrongColor(sq(3, 6)))
+        );
+    }
+
+    #[test]
+    fn test_king_move_is_safe_sees_through_the_king() {
+        let sq = |name| Square::from_algebraic(name).unwrap();
+        // The rook on a1 checks along the first rank; stepping to e1 stays
+        // on the ray once d1 is empty
+        let state = GameState::from_fen("4k3/8/8/8/8/8/8/r2K4 w - - 0 1").unwrap();
+        assert!(!state.king_move_is_safe(Move::new(sq("d1"), sq("e1"))));
+        assert!(!state.king_move_is_safe(Move::new(sq("d1"), sq("c1"))));
+        assert!(state.king_move_is_safe(Move::new(sq("d1"), sq("e2"))));
+
+        let mut targets: Vec<String> = state
+            .legal_moves_from(sq("d1"))
+            .iter()
+            .map(|mv| mv.to.to_string())
+            .collect();
+        targets.sort();
+        assert_eq!(targets, ["c2", "d2", "e2"]);
+
+        // Agrees with playing the move out across many positions
+        for seed in 0..20 {
+            let (_, state) = crate::random_game(seed, 200);
+            let king = state.board.array_board.king_square(state.turn);
+            for square in (0..64).filter_map(Square::from_index) {
+                let mv = Move::new(king, square);
+                if king.distance(square) != 1
+                    || state.board.array_board.is_color(square, state.turn)
+                {
+                    continue;
+                }
+                assert_eq!(
+                    state.king_move_is_safe(mv),
+                    !state.apply_move(mv).is_side_in_check(state.turn),
+                    "{mv} in {}",
+                    state.to_fen()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_quiet() {
+        assert!(GameState::new().is_quiet());
+        assert!(GameState::from_startpos_with_moves(&["e2e4", "e7e5"])
+            .unwrap()
+            .is_quiet());
+
+        for fen in [
+            // The rook can take a hanging queen
+            "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1",
+            // In check, with no capture available
+            "4k3/8/8/8/8/8/8/4K2r w - - 0 1",
+            // A pawn can promote
+            "4k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+            // The only capture is en passant
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+        ] {
+            let state = GameState::from_fen(fen).unwrap();
+            assert!(!state.is_quiet(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_from_startpos_with_moves() {
+        let state = GameState::from_startpos_with_moves(&["e2e4", "e7e5", "g1f3"]).unwrap();
+        assert_eq!(
+            state.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+        assert_eq!(
+            GameState::from_startpos_with_moves(&[]),
+            Ok(GameState::new())
+        );
+
+        // The first bad move is reported, even with more following it
+        let e2 = Square::from_algebraic("e2").unwrap();
+        let e5 = Square::from_algebraic("e5").unwrap();
+        assert_eq!(
+            GameState::from_startpos_with_moves(&["e2e4", "e7e5", "e2e5", "xx"]),
+            Err(MoveError::Illegal {
+                ply: 2,
+                mv: Move::new(e2, e5)
+            })
+        );
+        for text in ["e2", "e2e4x", "e2e9", "e7e8k"] {
+            assert_eq!(
+                GameState::from_startpos_with_moves(&[text]),
+                Err(MoveError::InvalidUci(text.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_uci() {
+        let state = GameState::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let castle = state.parse_uci("e1g1").unwrap();
+        assert!(castle.is_castling());
+        assert_eq!(state.parse_uci("e1e3"), None);
+        assert_eq!(state.parse_uci("castle"), None);
+
+        let promotion = GameState::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let a7 = Square::from_algebraic("a7").unwrap();
+        let a8 = Square::from_algebraic("a8").unwrap();
+        for text in ["a7a8n", "a7a8N"] {
+            assert_eq!(
+                promotion.parse_uci(text),
+                Some(Move::new_promotion(a7, a8, PieceType::Knight))
+            );
+        }
+    }
+
+    #[test]
+    fn test_repetition_key_ignores_unusable_en_passant() {
+        // No black pawn stands next to e4, so the e3 target is unusable
+        let with_ep = GameState::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").unwrap();
+        let without_ep = GameState::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1").unwrap();
+        assert_ne!(with_ep.zobrist_hash(), without_ep.zobrist_hash());
+        assert_eq!(with_ep.repetition_key(), without_ep.repetition_key());
+
+        // A capturable target still distinguishes the positions
+        let usable = GameState::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+        let unusable = GameState::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+        assert_ne!(usable.repetition_key(), unusable.repetition_key());
+    }
+
+    #[test]
+    fn test_same_position_ignores_move_counters() {
+        let sq = |name: &str| name.parse::<Square>().unwrap();
+
+        // 1. Nf3 Nf6 2. Ng1 Ng8 returns to the start two moves later
+        let start = GameState::new();
+        let knight_dance = start.apply_moves(&[
+            Move::new(sq("g1"), sq("f3")),
+            Move::new(sq("g8"), sq("f6")),
+            Move::new(sq("f3"), sq("g1")),
+            Move::new(sq("f6"), sq("g8")),
+        ]);
+        assert!(knight_dance.same_position(&start));
+        assert_ne!(knight_dance, start);
+        assert_eq!(knight_dance.fullmove_number, 3);
+
+        // 1. e4 e5 2. Nf3 vs 1. Nf3 e5 2. e4: only the second leaves an
+        // e3 target, and no black pawn can use it
+        let via_e4 = start.apply_moves(&[
+            Move::new(sq("e2"), sq("e4")),
+            Move::new(sq("e7"), sq("e5")),
+            Move::new(sq("g1"), sq("f3")),
+        ]);
+        let via_nf3 = start.apply_moves(&[
+            Move::new(sq("g1"), sq("f3")),
+            Move::new(sq("e7"), sq("e5")),
+            Move::new(sq("e2"), sq("e4")),
+        ]);
+        assert!(via_e4.same_position(&via_nf3));
+        assert_ne!(via_e4, via_nf3);
+
+        // The side to move still matters
+        let mut other_turn = start.clone();
+        other_turn.turn = Color::Black;
+        assert!(!other_turn.same_position(&start));
+    }
+
+    #[test]
+    fn test_replay() {
+        let sq = |file, rank| Square::new(File::new(file).unwrap(), Rank::new(rank).unwrap());
+
+        // 1. e4 d5 2. exd5 Qxd5
+        let moves = [
+            Move::new(sq(4, 1), sq(4, 3)),
+            Move::new(sq(3, 6), sq(3, 4)),
+            Move::new(sq(4, 3), sq(3, 4)),
+            Move::new(sq(3, 7), sq(3, 4)),
+        ];
+        assert_eq!(
+            replay(&moves).to_fen(),
+            "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3"
+        );
+        assert_eq!(replay(&[]).to_fen(), GameState::new().to_fen());
+
+        // From a Black-to-move endgame: 1... Kf7 2. Kg2 g5
+        let start = GameState::from_fen("6k1/6p1/8/8/8/8/8/6K1 b - - 4 30").unwrap();
+        let moves = [
+            Move::new(sq(6, 7), sq(5, 6)),
+            Move::new(sq(6, 0), sq(6, 1)),
+            Move::new(sq(6, 6), sq(6, 4)),
+        ];
+        assert_eq!(
+            replay_from(&start, &moves).to_fen(),
+            "8/5k2/8/6p1/8/8/6K1/8 w - - 0 32"
+        );
+    }
+
+    #[test]
+    fn test_is_attacked() {
+        let state = PositionBuilder::new()
+            .place(PieceType::Rook, Color::White, "e4")
+            .build();
 
         // Check that e1, e8, a4, h4 are attacked
         assert!(state.is_attacked_by(Square::from_index(4).unwrap(), Color::White)); // e1
@@ -0,0 +1,109 @@
+use crate::game_state::GameState;
+use crate::types::{Color, File, Rank, Square};
+
+/// Odds one side gives before the game starts: a piece, or half its clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Handicap {
+    #[default]
+    None,
+    /// The f-pawn
+    Pawn,
+    /// The queen's knight
+    Knight,
+    /// The queen's rook
+    Rook,
+    Queen,
+    /// Half the base time on a timed game's clock
+    Time,
+}
+
+impl Handicap {
+    pub fn name(self) -> &'static str {
+        match self {
+            Handicap::None => "None",
+            Handicap::Pawn => "Pawn",
+            Handicap::Knight => "Knight",
+            Handicap::Rook => "Rook",
+            Handicap::Queen => "Queen",
+            Handicap::Time => "Time",
+        }
+    }
+
+    /// The handicap after this one, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            Handicap::None => Handicap::Pawn,
+            Handicap::Pawn => Handicap::Knight,
+            Handicap::Knight => Handicap::Rook,
+            Handicap::Rook => Handicap::Queen,
+            Handicap::Queen => Handicap::Time,
+            Handicap::Time => Handicap::None,
+        }
+    }
+
+    /// Starting square of the piece `giver` removes, if any.
+    pub fn square(self, giver: Color) -> Option<Square> {
+        let (file, rank) = match self {
+            Handicap::None | Handicap::Time => return None,
+            Handicap::Pawn => (5, 1),
+            Handicap::Knight => (1, 0),
+            Handicap::Rook => (0, 0),
+            Handicap::Queen => (3, 0),
+        };
+        let rank = match giver {
+            Color::White => rank,
+            Color::Black => 7 - rank,
+        };
+        Some(Square::new(File::new(file)?, Rank::new(rank)?))
+    }
+
+    /// Divisor for the giver's base clock time; 1 for material odds.
+    pub fn clock_divisor(self) -> u32 {
+        match self {
+            Handicap::Time => 2,
+            _ => 1,
+        }
+    }
+
+    /// The standard starting position with `giver`'s piece removed.
+    pub fn starting_position(self, giver: Color) -> GameState {
+        let removed: Vec<Square> = self.square(giver).into_iter().collect();
+        GameState::new().with_squares_cleared(&removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handicap_starting_positions() {
+        assert_eq!(
+            Handicap::Knight.starting_position(Color::Black).to_fen(),
+            "r1bqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
+        assert_eq!(
+            Handicap::Pawn.starting_position(Color::White).to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPP1PP/RNBQKBNR w KQkq - 0 1"
+        );
+        // Giving up a rook also gives up castling on that side
+        assert_eq!(
+            Handicap::Rook.starting_position(Color::White).to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/1NBQKBNR w Kkq - 0 1"
+        );
+        assert_eq!(
+            Handicap::None.starting_position(Color::White).to_fen(),
+            GameState::new().to_fen()
+        );
+    }
+
+    #[test]
+    fn test_time_odds_keep_all_material() {
+        assert_eq!(
+            Handicap::Time.starting_position(Color::Black),
+            GameState::new()
+        );
+        assert_eq!(Handicap::Time.clock_divisor(), 2);
+        assert_eq!(Handicap::Queen.clock_divisor(), 1);
+    }
+}
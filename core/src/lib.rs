@@ -1,14 +1,30 @@
 pub mod board;
+pub mod epd;
 pub mod fen;
+pub mod game;
 pub mod game_state;
+pub mod handicap;
 pub mod move_gen;
 pub mod perft;
+pub mod pgn;
+pub mod position_builder;
+pub mod random_game;
+pub mod san;
 pub mod types;
 pub mod zobrist;
 
 pub use board::*;
+pub use epd::{EpdError, EpdRecord};
 pub use fen::{positions, FenError};
+pub use game::{Game, GameResult};
 pub use game_state::*;
+pub use handicap::Handicap;
 pub use move_gen::*;
-pub use perft::{perft, perft_detailed, perft_divide, PerftResults};
+pub use perft::{
+    perft, perft_detailed, perft_divide, perft_divide_san, perft_parallel, PerftResults,
+};
+pub use pgn::{Pgn, PgnError};
+pub use position_builder::PositionBuilder;
+pub use random_game::random_game;
+pub use san::{format_san_line, moves_to_san};
 pub use types::*;
@@ -1,5 +1,5 @@
 use crate::game_state::GameState;
-use crate::types::{Color, File, Move, PieceType, Rank, Square};
+use crate::types::{BitBoard, Color, File, Move, PieceType, Rank, Square};
 
 /// A list of moves with a fixed capacity to avoid allocations.
 pub struct MoveList {
@@ -11,17 +11,18 @@ impl MoveList {
     /// Creates an empty move list.
     pub const fn new() -> Self {
         Self {
-            moves: [Move::new(
-                Square::from_index(0).unwrap(),
-                Square::from_index(0).unwrap(),
-            ); 256],
+            moves: [Move::NULL; 256],
             count: 0,
         }
     }
 
     /// Adds a move to the list.
+    ///
+    /// # Panics
+    /// Panics if the list already holds 256 moves. No legal position has
+    /// more than 218.
     pub fn push(&mut self, mv: Move) {
-        debug_assert!(self.count < 256, "Move list overflow");
+        assert!(self.count < 256, "Move list overflow");
         self.moves[self.count] = mv;
         self.count += 1;
     }
@@ -47,13 +48,68 @@ impl MoveList {
     }
 }
 
+impl From<&MoveList> for Vec<Move> {
+    fn from(list: &MoveList) -> Self {
+        list.moves[..list.count].to_vec()
+    }
+}
+
+/// # Panics
+/// Panics if the iterator yields more than 256 moves.
+impl FromIterator<Move> for MoveList {
+    fn from_iter<I: IntoIterator<Item = Move>>(iter: I) -> Self {
+        let mut list = MoveList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+/// # Panics
+/// Panics if the list would grow past 256 moves.
+impl Extend<Move> for MoveList {
+    fn extend<I: IntoIterator<Item = Move>>(&mut self, iter: I) {
+        for mv in iter {
+            self.push(mv);
+        }
+    }
+}
+
 /// Generates all legal moves for the current position.
 pub fn generate_legal_moves(state: &GameState) -> MoveList {
+    // A corrupt board (e.g. two kings) would otherwise yield nonsense moves
+    #[cfg(debug_assertions)]
+    if let Err(e) = state.validate_piece_counts() {
+        panic!("invalid position {}: {e}", state.to_fen());
+    }
+
     let mut moves = generate_pseudo_legal_moves(state);
     filter_legal_moves(state, &mut moves);
     moves
 }
 
+/// Returns true if the side to move has a legal move. Stops at the first one
+/// found instead of filtering every pseudo-legal move, so terminal checks are
+/// cheaper than `generate_legal_moves(state).is_empty()`.
+pub fn has_legal_move(state: &GameState) -> bool {
+    generate_pseudo_legal_moves(state)
+        .iter()
+        .any(|&mv| leaves_king_safe(state, mv))
+}
+
+/// Generates the legal captures, en passant included, and promotions: the
+/// moves that change material.
+pub fn generate_captures(state: &GameState) -> MoveList {
+    generate_pseudo_legal_moves(state)
+        .iter()
+        .copied()
+        .filter(|&mv| {
+            let changes_material =
+                state.board.piece_at(mv.to).is_some() || mv.is_en_passant() || mv.is_promotion();
+            changes_material && leaves_king_safe(state, mv)
+        })
+        .collect()
+}
+
 /// Generates all pseudo-legal moves (not checking for king safety).
 fn generate_pseudo_legal_moves(state: &GameState) -> MoveList {
     let mut moves = MoveList::new();
@@ -73,146 +129,109 @@ fn generate_pseudo_legal_moves(state: &GameState) -> MoveList {
 
 /// Filters out moves that would leave the king in check.
 fn filter_legal_moves(state: &GameState, moves: &mut MoveList) {
-    let mut legal_moves = MoveList::new();
+    *moves = moves
+        .iter()
+        .copied()
+        .filter(|&mv| leaves_king_safe(state, mv))
+        .collect();
+}
 
-    for &mv in moves.iter() {
-        let new_state = state.apply_move(mv);
-        if !new_state.is_side_in_check(state.turn) {
-            legal_moves.push(mv);
-        }
+/// Returns true if the pseudo-legal `mv` does not leave the mover in check.
+/// King moves only need their destination tested; anything else is played
+/// out, since it may uncover an attack on the king.
+fn leaves_king_safe(state: &GameState, mv: Move) -> bool {
+    let is_king = state
+        .board
+        .piece_at(mv.from)
+        .is_some_and(|piece| piece.piece_type == PieceType::King);
+    if is_king {
+        return state.king_move_is_safe(mv);
     }
+    !state.apply_move(mv).is_side_in_check(state.turn)
+}
+
+/// Squares reached by pushing each pawn one square forward onto an empty square.
+pub fn pawn_single_pushes(pawns: BitBoard, empty: BitBoard, color: Color) -> BitBoard {
+    pawns.shift(8 * color.pawn_direction()) & empty
+}
 
-    *moves = legal_moves;
+/// Squares reached by a double push from the starting rank; both squares in
+/// front of the pawn must be empty.
+pub fn pawn_double_pushes(pawns: BitBoard, empty: BitBoard, color: Color) -> BitBoard {
+    let unmoved = pawns & BitBoard::rank_mask(color.pawn_rank());
+    let single = pawn_single_pushes(unmoved, empty, color);
+    pawn_single_pushes(single, empty, color)
+}
+
+/// Squares attacked diagonally by the given pawns.
+pub fn pawn_attacks(pawns: BitBoard, color: Color) -> BitBoard {
+    let forward = 8 * color.pawn_direction();
+    pawns.shift(forward - 1) | pawns.shift(forward + 1)
 }
 
 /// Generates pawn moves for the given color.
 fn generate_pawn_moves(state: &GameState, color: Color, moves: &mut MoveList) {
     let pawns = state.board.bitboards.pieces(PieceType::Pawn, color);
-    let direction = color.pawn_direction();
-    let start_rank = color.pawn_rank();
+    let empty = state.board.bitboards.empty_squares();
+    let enemies = state.board.bitboards.color_occupancy(color.opponent());
+    let forward = 8 * color.pawn_direction();
     let promotion_rank = color.promotion_rank();
 
-    for from_square in pawns.iter() {
-        let from_rank = from_square.rank();
-        let from_file = from_square.file();
-
-        // Single push
-        if let Some(to_rank) = from_rank.offset(direction) {
-            let to_square = Square::new(from_file, to_rank);
-            if state.board.array_board.is_empty(to_square) {
-                if to_rank == promotion_rank {
-                    // Generate promotion moves
-                    moves.push(Move::new_promotion(
-                        from_square,
-                        to_square,
-                        PieceType::Queen,
-                    ));
-                    moves.push(Move::new_promotion(from_square, to_square, PieceType::Rook));
-                    moves.push(Move::new_promotion(
-                        from_square,
-                        to_square,
-                        PieceType::Bishop,
-                    ));
-                    moves.push(Move::new_promotion(
-                        from_square,
-                        to_square,
-                        PieceType::Knight,
-                    ));
-                } else {
-                    moves.push(Move::new(from_square, to_square));
-                }
+    // Pushes
+    for to_square in pawn_single_pushes(pawns, empty, color).iter() {
+        push_pawn_move(
+            moves,
+            offset_square(to_square, -forward),
+            to_square,
+            promotion_rank,
+        );
+    }
+    for to_square in pawn_double_pushes(pawns, empty, color).iter() {
+        moves.push(Move::new(offset_square(to_square, -2 * forward), to_square));
+    }
 
-                // Double push from starting position
-                if from_rank == start_rank {
-                    if let Some(double_rank) = to_rank.offset(direction) {
-                        let double_square = Square::new(from_file, double_rank);
-                        if state.board.array_board.is_empty(double_square) {
-                            moves.push(Move::new(from_square, double_square));
-                        }
-                    }
-                }
-            }
+    // Captures towards each side
+    for delta in [forward - 1, forward + 1] {
+        for to_square in (pawns.shift(delta) & enemies).iter() {
+            push_pawn_move(
+                moves,
+                offset_square(to_square, -delta),
+                to_square,
+                promotion_rank,
+            );
         }
+    }
 
-        // Captures
-        if let Some(to_rank) = from_rank.offset(direction) {
-            // Left capture
-            if let Some(left_file) = from_file.offset(-1) {
-                let capture_square = Square::new(left_file, to_rank);
-                if state.board.array_board.is_enemy(capture_square, color) {
-                    if to_rank == promotion_rank {
-                        moves.push(Move::new_promotion(
-                            from_square,
-                            capture_square,
-                            PieceType::Queen,
-                        ));
-                        moves.push(Move::new_promotion(
-                            from_square,
-                            capture_square,
-                            PieceType::Rook,
-                        ));
-                        moves.push(Move::new_promotion(
-                            from_square,
-                            capture_square,
-                            PieceType::Bishop,
-                        ));
-                        moves.push(Move::new_promotion(
-                            from_square,
-                            capture_square,
-                            PieceType::Knight,
-                        ));
-                    } else {
-                        moves.push(Move::new(from_square, capture_square));
-                    }
-                }
-            }
-
-            // Right capture
-            if let Some(right_file) = from_file.offset(1) {
-                let capture_square = Square::new(right_file, to_rank);
-                if state.board.array_board.is_enemy(capture_square, color) {
-                    if to_rank == promotion_rank {
-                        moves.push(Move::new_promotion(
-                            from_square,
-                            capture_square,
-                            PieceType::Queen,
-                        ));
-                        moves.push(Move::new_promotion(
-                            from_square,
-                            capture_square,
-                            PieceType::Rook,
-                        ));
-                        moves.push(Move::new_promotion(
-                            from_square,
-                            capture_square,
-                            PieceType::Bishop,
-                        ));
-                        moves.push(Move::new_promotion(
-                            from_square,
-                            capture_square,
-                            PieceType::Knight,
-                        ));
-                    } else {
-                        moves.push(Move::new(from_square, capture_square));
-                    }
-                }
-            }
+    // En passant: our pawns sit where an enemy pawn on the target square would attack
+    if let Some(ep_square) = state.en_passant {
+        let attackers = pawn_attacks(BitBoard::from_square(ep_square), color.opponent()) & pawns;
+        for from_square in attackers.iter() {
+            moves.push(Move::new_en_passant(from_square, ep_square));
         }
+    }
+}
 
-        // En passant
-        if let Some(ep_square) = state.en_passant {
-            if let Some(ep_rank) = from_rank.offset(direction) {
-                if ep_square.rank() == ep_rank {
-                    let file_diff = (ep_square.file().index() as i8) - (from_file.index() as i8);
-                    if file_diff.abs() == 1 {
-                        moves.push(Move::new(from_square, ep_square));
-                    }
-                }
-            }
+/// Adds a pawn move, expanding it into the four promotions on the last rank.
+fn push_pawn_move(moves: &mut MoveList, from: Square, to: Square, promotion_rank: Rank) {
+    if to.rank() == promotion_rank {
+        for piece_type in [
+            PieceType::Queen,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+        ] {
+            moves.push(Move::new_promotion(from, to, piece_type));
         }
+    } else {
+        moves.push(Move::new(from, to));
     }
 }
 
+/// The square `delta` indices away; only used for deltas known to stay on the board.
+fn offset_square(square: Square, delta: i8) -> Square {
+    Square::from_index((square.index() as i8 + delta) as u8).expect("offset stays on board")
+}
+
 /// Generates knight moves for the given color.
 fn generate_knight_moves(state: &GameState, color: Color, moves: &mut MoveList) {
     const KNIGHT_DELTAS: [(i8, i8); 8] = [
@@ -375,7 +394,7 @@ fn generate_castling_moves(state: &GameState, color: Color, moves: &mut MoveList
             if !state.is_attacked_by(f1, color.opponent())
                 && !state.is_attacked_by(g1, color.opponent())
             {
-                moves.push(Move::new(king_square, g1));
+                moves.push(Move::new_castle(king_square, g1));
             }
         }
     }
@@ -394,7 +413,7 @@ fn generate_castling_moves(state: &GameState, color: Color, moves: &mut MoveList
             if !state.is_attacked_by(d1, color.opponent())
                 && !state.is_attacked_by(c1, color.opponent())
             {
-                moves.push(Move::new(king_square, c1));
+                moves.push(Move::new_castle(king_square, c1));
             }
         }
     }
@@ -402,19 +421,127 @@ fn generate_castling_moves(state: &GameState, color: Color, moves: &mut MoveList
 
 /// Checks if the current position is checkmate.
 pub fn is_checkmate(state: &GameState) -> bool {
-    state.is_in_check() && generate_legal_moves(state).is_empty()
+    state.is_in_check() && !has_legal_move(state)
 }
 
 /// Checks if the current position is stalemate.
 pub fn is_stalemate(state: &GameState) -> bool {
-    !state.is_in_check() && generate_legal_moves(state).is_empty()
+    !state.is_in_check() && !has_legal_move(state)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::board::BitBoardSet;
-    use crate::types::Piece;
+    use crate::position_builder::PositionBuilder;
+    use crate::types::MoveFlags;
+
+    #[test]
+    fn test_move_list_conversions() {
+        let state = GameState::from_fen(crate::perft::positions::KIWIPETE).unwrap();
+        let moves = generate_legal_moves(&state);
+        let expected: Vec<Move> = moves.iter().copied().collect();
+
+        let vec = Vec::from(&moves);
+        assert_eq!(vec, expected);
+        assert_eq!(vec.len(), 48);
+
+        let collected: MoveList = vec.iter().copied().collect();
+        assert_eq!(Vec::from(&collected), expected);
+
+        let (first, rest) = vec.split_at(10);
+        let mut extended: MoveList = first.iter().copied().collect();
+        extended.extend(rest.iter().copied());
+        assert_eq!(extended.len(), moves.len());
+        assert_eq!(Vec::from(&extended), expected);
+
+        assert!(Vec::from(&MoveList::new()).is_empty());
+    }
+
+    #[test]
+    fn test_special_moves_are_flagged_and_survive_packing() {
+        for fen in [
+            crate::perft::positions::KIWIPETE,
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/R3KBNR w KQkq f6 0 3",
+        ] {
+            let state = GameState::from_fen(fen).unwrap();
+            let moves = generate_legal_moves(&state);
+            for &mv in moves.iter() {
+                let piece = state.board.piece_at(mv.from).unwrap();
+                let is_en_passant =
+                    piece.piece_type == PieceType::Pawn && Some(mv.to) == state.en_passant;
+                assert_eq!(mv.is_castling(), mv.is_castle(piece), "{mv} in {fen}");
+                assert_eq!(mv.is_en_passant(), is_en_passant, "{mv} in {fen}");
+
+                let unpacked = Move::from_u16(mv.to_u16());
+                assert_eq!(unpacked, mv);
+                assert_eq!(unpacked.flags, mv.flags, "{mv} in {fen}");
+            }
+            assert!(moves.iter().any(|mv| mv.is_castling()), "{fen}");
+        }
+
+        let state =
+            GameState::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/R3KBNR w KQkq f6 0 3")
+                .unwrap();
+        let flagged: Vec<Move> = generate_legal_moves(&state)
+            .iter()
+            .copied()
+            .filter(|mv| mv.flags != MoveFlags::NONE)
+            .collect();
+        let sq = |name| Square::from_algebraic(name).unwrap();
+        assert_eq!(
+            flagged,
+            [Move::new(sq("e5"), sq("f6")), Move::new(sq("e1"), sq("c1"))]
+        );
+
+        // Flags do not take part in equality, but do in the packed form
+        let castle = Move::new_castle(sq("e1"), sq("c1"));
+        assert_eq!(castle, Move::new(sq("e1"), sq("c1")));
+        assert_ne!(castle.to_u16(), Move::new(sq("e1"), sq("c1")).to_u16());
+        assert!(Move::from_u16(Move::NULL.to_u16()).is_null());
+    }
+
+    #[test]
+    fn test_generate_captures() {
+        // Kiwipete has 8 captures and no promotions at depth 1
+        let state = GameState::from_fen(crate::perft::positions::KIWIPETE).unwrap();
+        let captures = generate_captures(&state);
+        assert_eq!(captures.len(), 8);
+        assert!(captures
+            .iter()
+            .all(|mv| state.board.piece_at(mv.to).is_some()));
+
+        // Pinned pieces may not capture off the pin line
+        let pinned = GameState::from_fen("4r2k/8/8/3p4/4B3/8/8/4K3 w - - 0 1").unwrap();
+        assert!(generate_captures(&pinned).is_empty());
+    }
+
+    #[test]
+    fn test_has_legal_move_matches_generation() {
+        let mut states: Vec<GameState> = [
+            crate::positions::STARTING,
+            crate::perft::positions::KIWIPETE,
+            // Checkmate, stalemate, and a check with a single escape
+            "6kR/5ppp/8/8/8/8/8/6K1 b - - 0 1",
+            "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+            "7k/6pp/8/8/8/8/8/K6R b - - 0 1",
+        ]
+        .iter()
+        .map(|fen| GameState::from_fen(fen).unwrap())
+        .collect();
+        for seed in 0..10 {
+            states.push(crate::random_game(seed, 300).1);
+        }
+
+        for state in &states {
+            assert_eq!(
+                has_legal_move(state),
+                !generate_legal_moves(state).is_empty(),
+                "{}",
+                state.to_fen()
+            );
+        }
+        assert!(states.iter().any(|state| !has_legal_move(state)));
+    }
 
     #[test]
     fn test_starting_position_moves() {
@@ -426,21 +553,101 @@ mod tests {
     }
 
     #[test]
-    fn test_pawn_promotion() {
-        let mut state = GameState::empty();
-        state.board.array_board.set_piece(
-            Square::new(File::new(0).unwrap(), Rank::new(6).unwrap()), // a7
-            Some(Piece::new(PieceType::Pawn, Color::White)),
+    fn test_validate_piece_counts() {
+        assert!(GameState::new().validate_piece_counts().is_ok());
+        // Two promoted queens with six pawns left is fine
+        let state = GameState::from_fen("4k3/8/8/8/8/8/PPPPPP2/QQQ1K3 w - - 0 1").unwrap();
+        assert!(state.validate_piece_counts().is_ok());
+        // ...but not with seven
+        let state = GameState::from_fen("4k3/8/8/8/8/8/PPPPPPP1/QQQ1K3 w - - 0 1").unwrap();
+        assert!(state.validate_piece_counts().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Move list overflow")]
+    fn test_move_list_overflow_panics() {
+        let _: MoveList = std::iter::repeat_n(Move::NULL, 257).collect();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "White has 2 kings")]
+    fn test_two_white_kings_panics_in_debug() {
+        let state = GameState::from_fen("4k3/8/8/8/8/8/8/K3K3 w - - 0 1").unwrap();
+        generate_legal_moves(&state);
+    }
+
+    #[test]
+    fn test_pawn_push_bitboards() {
+        let white_pawns = BitBoard::rank_mask(Rank::SECOND);
+        let empty = BitBoard::rank_mask(Rank::THIRD) | BitBoard::rank_mask(Rank::FOURTH);
+
+        assert_eq!(
+            pawn_single_pushes(white_pawns, empty, Color::White),
+            BitBoard::rank_mask(Rank::THIRD)
+        );
+        assert_eq!(
+            pawn_double_pushes(white_pawns, empty, Color::White),
+            BitBoard::rank_mask(Rank::FOURTH)
+        );
+
+        // A blocker on e3 stops both pushes on the e-file
+        let e3 = Square::new(File::new(4).unwrap(), Rank::THIRD);
+        let blocked = empty.clear(e3);
+        assert!(!pawn_single_pushes(white_pawns, blocked, Color::White).contains(e3));
+        assert_eq!(
+            pawn_double_pushes(white_pawns, blocked, Color::White).count(),
+            7
         );
-        state.board.array_board.set_piece(
-            Square::new(File::new(0).unwrap(), Rank::new(0).unwrap()), // a1
-            Some(Piece::new(PieceType::King, Color::White)),
+
+        // Pawns off their start rank have no double push
+        let black_pawns = BitBoard::rank_mask(Rank::SIXTH);
+        assert!(pawn_double_pushes(black_pawns, BitBoard::FULL, Color::Black).is_empty());
+        assert_eq!(
+            pawn_single_pushes(black_pawns, BitBoard::FULL, Color::Black),
+            BitBoard::rank_mask(Rank::FIFTH)
         );
-        state.board.array_board.set_piece(
-            Square::new(File::new(7).unwrap(), Rank::new(7).unwrap()), // h8
-            Some(Piece::new(PieceType::King, Color::Black)),
+    }
+
+    #[test]
+    fn test_pawn_attacks_do_not_wrap() {
+        let a_file = BitBoard::file_mask(File::new(0).unwrap());
+        let b_file = BitBoard::file_mask(File::new(1).unwrap());
+        let g_file = BitBoard::file_mask(File::new(6).unwrap());
+        let h_file = BitBoard::file_mask(File::new(7).unwrap());
+        let a2 = Square::new(File::new(0).unwrap(), Rank::SECOND);
+        let h7 = Square::new(File::new(7).unwrap(), Rank::SEVENTH);
+
+        let white = pawn_attacks(BitBoard::from_square(a2), Color::White);
+        assert_eq!(
+            white,
+            BitBoard::from_square(Square::new(File::new(1).unwrap(), Rank::THIRD))
         );
-        state.board.bitboards = BitBoardSet::from_board(&state.board.array_board);
+
+        let black = pawn_attacks(BitBoard::from_square(h7), Color::Black);
+        assert_eq!(
+            black,
+            BitBoard::from_square(Square::new(File::new(6).unwrap(), Rank::SIXTH))
+        );
+
+        // Whole edge files only ever attack the neighbouring file
+        assert_eq!(
+            pawn_attacks(a_file, Color::White) & !b_file,
+            BitBoard::EMPTY
+        );
+        assert_eq!(
+            pawn_attacks(h_file, Color::Black) & !g_file,
+            BitBoard::EMPTY
+        );
+    }
+
+    #[test]
+    fn test_pawn_promotion() {
+        let state = PositionBuilder::new()
+            .place(PieceType::Pawn, Color::White, "a7")
+            .place(PieceType::King, Color::White, "a1")
+            .place(PieceType::King, Color::Black, "h8")
+            .build();
 
         let moves = generate_legal_moves(&state);
 
@@ -1,16 +1,23 @@
 use crate::game_state::GameState;
 use crate::move_gen::generate_legal_moves;
-use crate::types::{Move, PieceType};
+use crate::types::Move;
+use std::thread;
 
-/// Perft (performance test) results at each depth.
-#[derive(Debug, Default)]
+/// Perft (performance test) results at each depth, with the leaf moves
+/// classified the way the Chess Programming Wiki tables count them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct PerftResults {
     pub nodes: u64,
+    /// Captures, including en passant.
     pub captures: u64,
     pub en_passants: u64,
     pub castles: u64,
     pub promotions: u64,
     pub checks: u64,
+    /// Single checks given by a piece other than the one that moved.
+    pub discovered_checks: u64,
+    /// Checks given by two pieces at once.
+    pub double_checks: u64,
     pub checkmates: u64,
 }
 
@@ -23,8 +30,42 @@ impl PerftResults {
         self.castles += other.castles;
         self.promotions += other.promotions;
         self.checks += other.checks;
+        self.discovered_checks += other.discovered_checks;
+        self.double_checks += other.double_checks;
         self.checkmates += other.checkmates;
     }
+
+    /// Builds results from counts in table column order (see `fields`).
+    pub const fn from_counts(counts: [u64; 9]) -> Self {
+        let [nodes, captures, en_passants, castles, promotions, checks, discovered_checks, double_checks, checkmates] =
+            counts;
+        Self {
+            nodes,
+            captures,
+            en_passants,
+            castles,
+            promotions,
+            checks,
+            discovered_checks,
+            double_checks,
+            checkmates,
+        }
+    }
+
+    /// Names and values of every field, in table column order.
+    pub fn fields(&self) -> [(&'static str, u64); 9] {
+        [
+            ("nodes", self.nodes),
+            ("captures", self.captures),
+            ("e.p.", self.en_passants),
+            ("castles", self.castles),
+            ("promotions", self.promotions),
+            ("checks", self.checks),
+            ("discovery checks", self.discovered_checks),
+            ("double checks", self.double_checks),
+            ("checkmates", self.checkmates),
+        ]
+    }
 }
 
 /// Performs perft test to given depth and returns node count.
@@ -40,14 +81,45 @@ pub fn perft(state: &GameState, depth: u8) -> u64 {
     }
 
     let mut nodes = 0;
+    let mut new_state = state.clone();
     for mv in moves.iter() {
-        let new_state = state.apply_move(*mv);
+        state.apply_move_into(*mv, &mut new_state);
         nodes += perft(&new_state, depth - 1);
     }
 
     nodes
 }
 
+/// Performs perft test with the root moves split across `threads` threads.
+/// Returns exactly the same node count as `perft`.
+pub fn perft_parallel(state: &GameState, depth: u8, threads: usize) -> u64 {
+    if depth <= 1 || threads <= 1 {
+        return perft(state, depth);
+    }
+
+    let moves = Vec::from(&generate_legal_moves(state));
+    let chunk_size = moves.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = moves
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|mv| perft(&state.apply_move(*mv), depth - 1))
+                        .sum::<u64>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("perft thread panicked"))
+            .sum()
+    })
+}
+
 /// Performs detailed perft test with move breakdown.
 pub fn perft_divide(state: &GameState, depth: u8) -> Vec<(Move, u64)> {
     let moves = generate_legal_moves(state);
@@ -66,6 +138,16 @@ pub fn perft_divide(state: &GameState, depth: u8) -> Vec<(Move, u64)> {
     results
 }
 
+/// Like `perft_divide`, but labels each root move in SAN and sorts by label.
+pub fn perft_divide_san(state: &GameState, depth: u8) -> Vec<(String, u64)> {
+    let mut results: Vec<(String, u64)> = perft_divide(state, depth)
+        .into_iter()
+        .map(|(mv, nodes)| (state.move_to_san(mv), nodes))
+        .collect();
+    results.sort();
+    results
+}
+
 /// Performs perft test with detailed statistics.
 pub fn perft_detailed(state: &GameState, depth: u8) -> PerftResults {
     let mut results = PerftResults::default();
@@ -83,37 +165,38 @@ pub fn perft_detailed(state: &GameState, depth: u8) -> PerftResults {
         if depth == 1 {
             results.nodes += 1;
 
-            // Classify move types
-            let from_piece = state.board.piece_at(mv.from);
-            let to_piece = state.board.piece_at(mv.to);
-
-            // Capture detection
-            if to_piece.is_some() {
+            // Classify move types; the generator flags en passant and castling
+            if state.board.piece_at(mv.to).is_some() {
                 results.captures += 1;
             }
-
-            // En passant detection
-            if let Some(piece) = from_piece {
-                if piece.piece_type == PieceType::Pawn {
-                    // Check if it's a diagonal pawn move to empty square
-                    if mv.from.file() != mv.to.file() && to_piece.is_none() {
-                        results.en_passants += 1;
-                        results.captures += 1; // En passant is also a capture
-                    }
-                }
-
-                // Castle detection
-                if piece.piece_type == PieceType::King && mv.from.distance(mv.to) == 2 {
-                    results.castles += 1;
-                }
+            if mv.is_en_passant() {
+                results.en_passants += 1;
+                results.captures += 1; // En passant is also a capture
+            }
+            if mv.is_castling() {
+                results.castles += 1;
             }
 
             // Promotion detection
             if mv.is_promotion() {
                 results.promotions += 1;
             }
-            if new_state.is_in_check() {
+
+            let checkers = new_state.checkers();
+            if !checkers.is_empty() {
                 results.checks += 1;
+
+                // The moved piece is the castling rook when castling
+                let moved_to = if mv.is_castling() {
+                    castle_rook_squares(*mv).1
+                } else {
+                    mv.to
+                };
+                if checkers.count() > 1 {
+                    results.double_checks += 1;
+                } else if !checkers.contains(moved_to) {
+                    results.discovered_checks += 1;
+                }
                 if is_checkmate(&new_state) {
                     results.checkmates += 1;
                 }
@@ -129,6 +212,16 @@ pub fn perft_detailed(state: &GameState, depth: u8) -> PerftResults {
 
 /// Standard perft positions with expected results.
 pub mod positions {
+    use super::PerftResults;
+
+    /// Expected breakdowns by depth for one position.
+    pub type DetailedTable = &'static [(u8, PerftResults)];
+
+    /// A detailed perft table row: nodes, captures, e.p., castles,
+    /// promotions, checks, discovery checks, double checks, checkmates.
+    const fn row(depth: u8, counts: [u64; 9]) -> (u8, PerftResults) {
+        (depth, PerftResults::from_counts(counts))
+    }
 
     /// Starting position perft values.
     pub const STARTING_POSITION: &[(u8, u64)] = &[
@@ -140,6 +233,15 @@ pub mod positions {
         (6, 119_060_324),
     ];
 
+    /// Starting position breakdown from the Chess Programming Wiki.
+    pub const STARTING_POSITION_DETAILED: DetailedTable = &[
+        row(1, [20, 0, 0, 0, 0, 0, 0, 0, 0]),
+        row(2, [400, 0, 0, 0, 0, 0, 0, 0, 0]),
+        row(3, [8902, 34, 0, 0, 0, 12, 0, 0, 0]),
+        row(4, [197_281, 1576, 0, 0, 0, 469, 0, 0, 8]),
+        row(5, [4_865_609, 82_719, 258, 0, 0, 27_351, 6, 0, 347]),
+    ];
+
     /// Position after 1.e4 (Kiwipete).
     pub const KIWIPETE: &str =
         "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
@@ -150,6 +252,15 @@ pub mod positions {
         (4, 4_085_603),
         (5, 193_690_690),
     ];
+    pub const KIWIPETE_DETAILED: DetailedTable = &[
+        row(1, [48, 8, 0, 2, 0, 0, 0, 0, 0]),
+        row(2, [2039, 351, 1, 91, 0, 3, 0, 0, 0]),
+        row(3, [97_862, 17_102, 45, 3162, 0, 993, 0, 0, 1]),
+        row(
+            4,
+            [4_085_603, 757_163, 1929, 128_013, 15_172, 25_523, 42, 6, 43],
+        ),
+    ];
 
     /// Position 3 from CPW.
     pub const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
@@ -161,12 +272,31 @@ pub mod positions {
         (5, 674_624),
         (6, 11_030_083),
     ];
+    pub const POSITION_3_DETAILED: DetailedTable = &[
+        row(1, [14, 1, 0, 0, 0, 2, 0, 0, 0]),
+        row(2, [191, 14, 0, 0, 0, 10, 0, 0, 0]),
+        row(3, [2812, 209, 2, 0, 0, 267, 3, 0, 0]),
+        row(4, [43_238, 3348, 123, 0, 0, 1680, 106, 0, 17]),
+    ];
 
     /// Position 4 from CPW.
     pub const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
     pub const POSITION_4_PERFT: &[(u8, u64)] =
         &[(1, 6), (2, 264), (3, 9467), (4, 422_333), (5, 15_833_292)];
 
+    /// Every position with a published breakdown, for `perft suite`.
+    /// Position 4 is left out because its published table does not count
+    /// discovered checks.
+    pub const DETAILED_SUITE: &[(&str, &str, DetailedTable)] = &[
+        (
+            "Starting position",
+            crate::positions::STARTING,
+            STARTING_POSITION_DETAILED,
+        ),
+        ("Kiwipete", KIWIPETE, KIWIPETE_DETAILED),
+        ("Position 3", POSITION_3, POSITION_3_DETAILED),
+    ];
+
     /// Position 5 from CPW.
     pub const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
     pub const POSITION_5_PERFT: &[(u8, u64)] = &[
@@ -179,6 +309,7 @@ pub mod positions {
 }
 
 // Import functions from move_gen that are needed
+use crate::game_state::castle_rook_squares;
 use crate::move_gen::is_checkmate;
 
 #[cfg(test)]
@@ -202,6 +333,79 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_perft_parallel_matches_serial() {
+        let state = GameState::new();
+        assert_eq!(perft_parallel(&state, 3, 4), perft(&state, 3));
+
+        let kiwipete = GameState::from_fen(positions::KIWIPETE).unwrap();
+        assert_eq!(perft_parallel(&kiwipete, 2, 4), perft(&kiwipete, 2));
+    }
+
+    #[test]
+    fn test_perft_parallel_kiwipete_depth_4() {
+        let kiwipete = GameState::from_fen(positions::KIWIPETE).unwrap();
+        assert_eq!(perft_parallel(&kiwipete, 4, 4), 4_085_603);
+    }
+
+    #[test]
+    #[ignore] // Deep perft is slow in debug builds; run with --release --ignored
+    fn test_perft_parallel_deep() {
+        let state = GameState::new();
+        assert_eq!(perft_parallel(&state, 5, 4), perft(&state, 5));
+    }
+
+    fn assert_detailed(fen: &str, table: &[(u8, PerftResults)], max_depth: u8) {
+        let state = GameState::from_fen(fen).unwrap();
+        for (depth, expected) in table.iter().filter(|(depth, _)| *depth <= max_depth) {
+            assert_eq!(perft_detailed(&state, *depth), *expected, "depth {}", depth);
+        }
+    }
+
+    #[test]
+    fn test_perft_detailed_kiwipete() {
+        let kiwipete = GameState::from_fen(positions::KIWIPETE).unwrap();
+        assert_eq!(
+            perft_detailed(&kiwipete, 1),
+            PerftResults {
+                nodes: 48,
+                captures: 8,
+                castles: 2,
+                ..PerftResults::default()
+            }
+        );
+        assert_eq!(
+            perft_detailed(&kiwipete, 2),
+            PerftResults {
+                nodes: 2039,
+                captures: 351,
+                en_passants: 1,
+                castles: 91,
+                checks: 3,
+                ..PerftResults::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_perft_detailed_suite() {
+        assert_detailed(
+            crate::positions::STARTING,
+            positions::STARTING_POSITION_DETAILED,
+            3,
+        );
+        // Depth 3 has en passant captures and discovered checks
+        assert_detailed(positions::POSITION_3, positions::POSITION_3_DETAILED, 3);
+    }
+
+    #[test]
+    #[ignore] // Deep perft is slow in debug builds; run with --release --ignored
+    fn test_perft_detailed_suite_deep() {
+        for (_, fen, table) in positions::DETAILED_SUITE {
+            assert_detailed(fen, table, u8::MAX);
+        }
+    }
+
     #[test]
     fn test_perft_divide() {
         let state = GameState::new();
@@ -210,4 +414,21 @@ mod tests {
         assert_eq!(results.len(), 20);
         assert_eq!(results.iter().map(|(_, n)| n).sum::<u64>(), 20);
     }
+
+    #[test]
+    fn test_perft_divide_san() {
+        let kiwipete = GameState::from_fen(positions::KIWIPETE).unwrap();
+        let results = perft_divide_san(&kiwipete, 2);
+
+        assert_eq!(results.len(), 48);
+        assert_eq!(
+            results.iter().map(|(_, n)| n).sum::<u64>(),
+            perft(&kiwipete, 2)
+        );
+        assert!(results.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        for (san, _) in &results {
+            assert!(kiwipete.parse_san(san).is_some(), "{}", san);
+        }
+        assert!(results.iter().any(|(san, _)| san == "O-O-O"));
+    }
 }
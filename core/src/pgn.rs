@@ -0,0 +1,305 @@
+use crate::fen::FenError;
+use crate::game_state::{replay_from, GameState};
+use crate::types::Move;
+use std::fmt;
+
+/// A game imported from Portable Game Notation (PGN).
+#[derive(Debug, Clone)]
+pub struct Pgn {
+    /// Tag pairs in file order, e.g. ("White", "Carlsen")
+    pub tags: Vec<(String, String)>,
+    /// Starting position: the FEN tag if present, otherwise the standard start
+    pub start: GameState,
+    /// Mainline moves played from `start`
+    pub moves: Vec<Move>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PgnError {
+    InvalidFen(FenError),
+    /// A movetext token that is not a legal move; `ply` is its 0-based index
+    IllegalMove {
+        ply: usize,
+        san: String,
+    },
+    UnterminatedComment,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::InvalidFen(e) => write!(f, "Invalid FEN tag: {e}"),
+            PgnError::IllegalMove { ply, san } => {
+                write!(f, "Illegal move '{san}' at ply {}", ply + 1)
+            }
+            PgnError::UnterminatedComment => write!(f, "Unterminated comment or variation"),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<FenError> for PgnError {
+    fn from(e: FenError) -> Self {
+        PgnError::InvalidFen(e)
+    }
+}
+
+impl Pgn {
+    /// Parses the first game in `text`. Comments, variations, NAGs and move
+    /// numbers are skipped; only the mainline is kept.
+    pub fn from_pgn(text: &str) -> Result<Self, PgnError> {
+        let mut tags = Vec::new();
+        let mut movetext = String::new();
+
+        for line in text.lines() {
+            let line = line.trim();
+            if let Some(tag) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                // Tags after the movetext belong to the next game
+                if !movetext.trim().is_empty() {
+                    break;
+                }
+                if let Some(pair) = parse_tag(tag) {
+                    tags.push(pair);
+                }
+            } else if !line.starts_with('%') {
+                movetext.push_str(line);
+                movetext.push('\n');
+            }
+        }
+
+        let start = match tags.iter().find(|(key, _)| key == "FEN") {
+            Some((_, fen)) => GameState::from_fen(fen)?,
+            None => GameState::new(),
+        };
+
+        let mut state = start.clone();
+        let mut moves = Vec::new();
+        for token in movetext_tokens(&movetext)? {
+            let Some(mv) = state.parse_san(token) else {
+                return Err(PgnError::IllegalMove {
+                    ply: moves.len(),
+                    san: token.to_string(),
+                });
+            };
+            state = state.apply_move(mv);
+            moves.push(mv);
+        }
+
+        Ok(Self { tags, start, moves })
+    }
+
+    /// Parses every game in `text`, one result per game, so a bad game does
+    /// not hide the ones after it.
+    pub fn from_pgn_all(text: &str) -> Vec<Result<Self, PgnError>> {
+        split_games(text)
+            .iter()
+            .map(|game| Self::from_pgn(game))
+            .collect()
+    }
+
+    /// Value of the first tag named `key`.
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Position after all mainline moves.
+    pub fn final_state(&self) -> GameState {
+        replay_from(&self.start, &self.moves)
+    }
+}
+
+/// Splits a multi-game file at each tag section that follows movetext.
+fn split_games(text: &str) -> Vec<String> {
+    let mut games = Vec::new();
+    let mut current = String::new();
+    let mut seen_movetext = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            if seen_movetext {
+                games.push(std::mem::take(&mut current));
+                seen_movetext = false;
+            }
+        } else if !trimmed.is_empty() {
+            seen_movetext = true;
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+
+    if !current.trim().is_empty() {
+        games.push(current);
+    }
+    games
+}
+
+/// Splits `Key "Value"` into its parts, unescaping quotes in the value.
+fn parse_tag(tag: &str) -> Option<(String, String)> {
+    let (key, rest) = tag.split_once(char::is_whitespace)?;
+    let value = rest.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((key.to_string(), value.replace("\\\"", "\"")))
+}
+
+/// Returns the SAN tokens of the mainline, dropping everything else.
+fn movetext_tokens(movetext: &str) -> Result<Vec<&str>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut variation_depth = 0;
+    let mut rest = movetext;
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '{' => {
+                let end = rest.find('}').ok_or(PgnError::UnterminatedComment)?;
+                rest = &rest[end + 1..];
+            }
+            ';' => {
+                let end = rest.find('\n').unwrap_or(rest.len());
+                rest = &rest[end..];
+            }
+            '(' => {
+                variation_depth += 1;
+                rest = &rest[1..];
+            }
+            ')' => {
+                variation_depth -= 1;
+                rest = &rest[1..];
+            }
+            c if c.is_whitespace() => rest = &rest[c.len_utf8()..],
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || "{};()".contains(c))
+                    .unwrap_or(rest.len());
+                let token = &rest[..end];
+                rest = &rest[end..];
+
+                if variation_depth > 0 {
+                    continue;
+                }
+                if is_result(token) {
+                    break;
+                }
+                let token = strip_move_number(token);
+                if !token.is_empty() && !token.starts_with('$') {
+                    tokens.push(token);
+                }
+            }
+        }
+    }
+
+    if variation_depth != 0 {
+        return Err(PgnError::UnterminatedComment);
+    }
+    Ok(tokens)
+}
+
+/// Strips a move number such as "12." or "12..." that may be glued to the
+/// move that follows. Digits not followed by a dot, as in "0-0", are kept.
+fn strip_move_number(token: &str) -> &str {
+    let after_digits = token.trim_start_matches(|c: char| c.is_ascii_digit());
+    if after_digits.len() < token.len() && after_digits.starts_with('.') {
+        after_digits.trim_start_matches('.')
+    } else {
+        token
+    }
+}
+
+fn is_result(token: &str) -> bool {
+    matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple_game() {
+        let pgn = Pgn::from_pgn(
+            "[Event \"Casual\"]\n[White \"A\"]\n[Black \"B\"]\n\n\
+             1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 1-0\n",
+        )
+        .unwrap();
+
+        assert_eq!(pgn.tag("White"), Some("A"));
+        assert_eq!(pgn.moves.len(), 6);
+        assert_eq!(
+            pgn.final_state().to_fen(),
+            "r1bqkbnr/1ppp1ppp/p1n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 4"
+        );
+    }
+
+    #[test]
+    fn test_skips_comments_variations_and_nags() {
+        let pgn = Pgn::from_pgn(
+            "1.e4 {best by test} e5 (1...c5 2.Nf3 (2.c3)) 2.Nf3 $1 ; a comment\n2...Nc6 *",
+        )
+        .unwrap();
+
+        assert_eq!(pgn.moves.len(), 4);
+    }
+
+    #[test]
+    fn test_zero_castling_is_not_read_as_a_move_number() {
+        let pgn = Pgn::from_pgn(
+            "1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. 0-0 d6 5. d3 Bg4 6. Nc3 Qd7 \
+             7. a3 0-0-0 *",
+        )
+        .unwrap();
+
+        assert_eq!(pgn.moves.len(), 14);
+        assert_eq!(
+            pgn.final_state().to_fen(),
+            "2kr2nr/pppq1ppp/2np4/2b1p3/2B1P1b1/P1NP1N2/1PP2PPP/R1BQ1RK1 w - - 1 8"
+        );
+    }
+
+    #[test]
+    fn test_fen_tag_sets_start_position() {
+        let fen = "6k1/5ppp/8/8/8/8/8/R5K1 b - - 3 40";
+        let pgn = Pgn::from_pgn(&format!(
+            "[SetUp \"1\"]\n[FEN \"{fen}\"]\n\n40... h6 41. Ra8+ Kh7 1/2-1/2\n"
+        ))
+        .unwrap();
+
+        assert_eq!(pgn.start.to_fen(), fen);
+        assert_eq!(pgn.moves.len(), 3);
+        assert_eq!(pgn.final_state().fullmove_number, 42);
+    }
+
+    #[test]
+    fn test_reports_illegal_move() {
+        let err = Pgn::from_pgn("1. e4 e5 2. Ke3").unwrap_err();
+        assert_eq!(
+            err,
+            PgnError::IllegalMove {
+                ply: 2,
+                san: "Ke3".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_only_first_game_is_read() {
+        let pgn =
+            Pgn::from_pgn("[Event \"1\"]\n\n1. d4 *\n\n[Event \"2\"]\n\n1. e4 e5 *\n").unwrap();
+        assert_eq!(pgn.tag("Event"), Some("1"));
+        assert_eq!(pgn.moves.len(), 1);
+    }
+
+    #[test]
+    fn test_from_pgn_all_reads_every_game() {
+        let games = Pgn::from_pgn_all(
+            "[Event \"1\"]\n\n1. d4 *\n\n[Event \"2\"]\n\n1. e4 Ke7 *\n\n1. c4 c5 *\n",
+        );
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].as_ref().unwrap().moves.len(), 1);
+        assert!(matches!(
+            games[1],
+            Err(PgnError::IllegalMove { ply: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::game_state::GameState;
+use crate::types::{CastlingRights, Color, Piece, PieceType, Square};
+
+/// Builds a `GameState` piece by piece, keeping the array board and the
+/// bitboards in sync. Starts from an empty board with White to move and no
+/// castling rights.
+///
+/// ```
+/// use chess_core::{Color, PieceType, PositionBuilder};
+/// let state = PositionBuilder::new()
+///     .place(PieceType::King, Color::White, "e1")
+///     .place(PieceType::King, Color::Black, "e8")
+///     .turn(Color::Black)
+///     .build();
+/// assert_eq!(state.to_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+/// ```
+#[derive(Debug, Clone)]
+pub struct PositionBuilder {
+    state: GameState,
+}
+
+impl PositionBuilder {
+    pub fn new() -> Self {
+        Self {
+            state: GameState::empty(),
+        }
+    }
+
+    /// Places a piece on a square given in algebraic notation, e.g. "e4".
+    ///
+    /// # Panics
+    /// Panics if `square` is not a valid square name.
+    pub fn place(self, piece_type: PieceType, color: Color, square: &str) -> Self {
+        let square = Square::from_algebraic(square)
+            .unwrap_or_else(|| panic!("invalid square name {square:?}"));
+        self.place_at(Piece::new(piece_type, color), square)
+    }
+
+    /// Places a piece on a square, replacing anything already there.
+    pub fn place_at(mut self, piece: Piece, square: Square) -> Self {
+        self.state.board.set_square(square, Some(piece));
+        self
+    }
+
+    pub fn turn(mut self, color: Color) -> Self {
+        self.state.turn = color;
+        self
+    }
+
+    pub fn castling(mut self, rights: CastlingRights) -> Self {
+        self.state.castling = rights;
+        self
+    }
+
+    pub fn en_passant(mut self, square: Option<Square>) -> Self {
+        self.state.en_passant = square;
+        self
+    }
+
+    pub fn build(self) -> GameState {
+        self.state
+    }
+}
+
+impl Default for PositionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_queen_endgame() {
+        let state = PositionBuilder::new()
+            .place(PieceType::King, Color::White, "e1")
+            .place(PieceType::Queen, Color::White, "d1")
+            .place(PieceType::King, Color::Black, "e8")
+            .build();
+
+        #[cfg(debug_assertions)]
+        assert!(state.board.is_consistent());
+        assert_eq!(
+            state.board.piece_at(Square::from_algebraic("d1").unwrap()),
+            Some(Piece::new(PieceType::Queen, Color::White))
+        );
+        assert_eq!(
+            state.board.piece_at(Square::from_algebraic("e8").unwrap()),
+            Some(Piece::new(PieceType::King, Color::Black))
+        );
+        assert_eq!(
+            state.board.piece_at(Square::from_algebraic("e2").unwrap()),
+            None
+        );
+        assert_eq!(state.to_fen(), "4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid square name")]
+    fn test_rejects_bad_square() {
+        PositionBuilder::new().place(PieceType::King, Color::White, "i9");
+    }
+}
@@ -0,0 +1,83 @@
+use crate::game::Game;
+use crate::game_state::GameState;
+use crate::move_gen::generate_legal_moves;
+use crate::types::Move;
+
+/// Plays a reproducible game of uniformly random legal moves from the
+/// starting position, for tests and benchmarks that want realistic positions.
+///
+/// The game stops at checkmate, stalemate, the fifty-move rule, insufficient
+/// material, threefold repetition, or after `max_plies` moves. The same seed
+/// always produces the same game.
+pub fn random_game(seed: u64, max_plies: usize) -> (Vec<Move>, GameState) {
+    let mut rng = SplitMix64(seed);
+    let mut game = Game::new();
+    let mut moves = Vec::new();
+
+    while moves.len() < max_plies && !is_terminal(&game) {
+        let legal_moves = generate_legal_moves(game.state());
+        if legal_moves.is_empty() {
+            break;
+        }
+
+        let index = (rng.next() % legal_moves.len() as u64) as usize;
+        let mv = legal_moves.iter().nth(index).copied().unwrap();
+        game.apply_move(mv);
+        moves.push(mv);
+    }
+
+    (moves, game.state().clone())
+}
+
+fn is_terminal(game: &Game) -> bool {
+    let state = game.state();
+    state.is_fifty_move_draw() || state.is_insufficient_material() || game.is_threefold_repetition()
+}
+
+/// SplitMix64: tiny, well-mixed, and happy with a zero seed.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_game() {
+        let (moves, state) = random_game(42, 200);
+        let (again, again_state) = random_game(42, 200);
+        assert_eq!(moves, again);
+        assert_eq!(state.to_fen(), again_state.to_fen());
+
+        let (other, _) = random_game(43, 200);
+        assert_ne!(moves, other);
+    }
+
+    #[test]
+    fn test_every_move_is_legal() {
+        for seed in 0..5 {
+            let (moves, final_state) = random_game(seed, 300);
+            assert!(moves.len() <= 300);
+
+            let mut state = GameState::new();
+            for mv in &moves {
+                assert!(
+                    generate_legal_moves(&state).iter().any(|legal| legal == mv),
+                    "seed {seed}: {mv} is illegal in {}",
+                    state.to_fen()
+                );
+                state = state.apply_move(*mv);
+            }
+            assert_eq!(state.to_fen(), final_state.to_fen());
+        }
+    }
+}
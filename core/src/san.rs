@@ -0,0 +1,264 @@
+use crate::game_state::GameState;
+use crate::move_gen::generate_legal_moves;
+use crate::types::{Color, Move, PieceType};
+
+/// Standard Algebraic Notation (SAN) output, e.g. "Nf3", "exd5", "O-O", "e8=Q+".
+impl GameState {
+    /// Converts a legal move in this position to SAN.
+    /// The move is assumed to be legal; use `moves_to_san` for unchecked input.
+    pub fn move_to_san(&self, mv: Move) -> String {
+        let piece = self
+            .board
+            .piece_at(mv.from)
+            .expect("No piece at source square");
+
+        let mut san = String::new();
+
+        if mv.is_castle(piece) {
+            if mv.to.file().index() > mv.from.file().index() {
+                san.push_str("O-O");
+            } else {
+                san.push_str("O-O-O");
+            }
+        } else {
+            let is_capture = self.board.piece_at(mv.to).is_some()
+                || (piece.piece_type == PieceType::Pawn && mv.from.file() != mv.to.file());
+
+            if piece.piece_type == PieceType::Pawn {
+                if is_capture {
+                    san.push(mv.from.file().to_char());
+                }
+            } else {
+                san.push(piece_letter(piece.piece_type));
+                san.push_str(&self.disambiguation(mv, piece.piece_type));
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&mv.to.to_string());
+
+            if let Some(promotion) = mv.promotion {
+                san.push('=');
+                san.push(piece_letter(promotion));
+            }
+        }
+
+        if self.gives_checkmate(mv) {
+            san.push('#');
+        } else if self.gives_check(mv) {
+            san.push('+');
+        }
+
+        san
+    }
+
+    /// Parses a move in SAN for this position, returning `None` if it does not
+    /// name exactly one legal move. Check/mate marks and annotations ("!", "?")
+    /// are optional, castling may be written with zeros, and the "=" before a
+    /// promotion piece may be omitted.
+    pub fn parse_san(&self, san: &str) -> Option<Move> {
+        let wanted = normalize_san(san);
+        if wanted.is_empty() {
+            return None;
+        }
+
+        let legal_moves = generate_legal_moves(self);
+        let mut matches = legal_moves
+            .iter()
+            .copied()
+            .filter(|&mv| normalize_san(&self.move_to_san(mv)) == wanted);
+
+        let mv = matches.next()?;
+        if matches.next().is_some() {
+            return None;
+        }
+        Some(mv)
+    }
+
+    /// Returns the file and/or rank needed to tell `mv` apart from other
+    /// moves of the same piece type to the same square.
+    fn disambiguation(&self, mv: Move, piece_type: PieceType) -> String {
+        let rivals: Vec<Move> = generate_legal_moves(self)
+            .iter()
+            .copied()
+            .filter(|other| {
+                other.to == mv.to
+                    && other.from != mv.from
+                    && self
+                        .board
+                        .piece_at(other.from)
+                        .is_some_and(|p| p.piece_type == piece_type)
+            })
+            .collect();
+
+        if rivals.is_empty() {
+            String::new()
+        } else if rivals.iter().all(|r| r.from.file() != mv.from.file()) {
+            mv.from.file().to_char().to_string()
+        } else if rivals.iter().all(|r| r.from.rank() != mv.from.rank()) {
+            mv.from.rank().to_char().to_string()
+        } else {
+            mv.from.to_string()
+        }
+    }
+}
+
+/// Converts a sequence of moves played from `state` into SAN.
+/// Stops at the first move that is not legal in the position it is played from.
+pub fn moves_to_san(state: &GameState, moves: &[Move]) -> Vec<String> {
+    let mut current = state.clone();
+    let mut sans = Vec::new();
+
+    for &mv in moves {
+        if !generate_legal_moves(&current)
+            .iter()
+            .any(|&legal| legal == mv)
+        {
+            break;
+        }
+        sans.push(current.move_to_san(mv));
+        current = current.apply_move(mv);
+    }
+
+    sans
+}
+
+/// Formats a line as numbered SAN, e.g. "1. e4 e5 2. Nf3" or "3... Nc6 4. Bb5".
+pub fn format_san_line(state: &GameState, moves: &[Move]) -> String {
+    let mut line = String::new();
+    let mut move_number = state.fullmove_number;
+    let mut turn = state.turn;
+
+    for (i, san) in moves_to_san(state, moves).iter().enumerate() {
+        if i > 0 {
+            line.push(' ');
+        }
+        match turn {
+            Color::White => line.push_str(&format!("{move_number}. ")),
+            Color::Black if i == 0 => line.push_str(&format!("{move_number}... ")),
+            Color::Black => {}
+        }
+        line.push_str(san);
+
+        if turn == Color::Black {
+            move_number += 1;
+        }
+        turn = turn.opponent();
+    }
+
+    line
+}
+
+/// Reduces SAN to the part that identifies the move, so that input written
+/// with or without check marks, annotations or "=" compares equal.
+fn normalize_san(san: &str) -> String {
+    san.trim()
+        .trim_end_matches(['+', '#', '!', '?'])
+        .trim_end_matches("e.p.")
+        .replace('0', "O")
+        .replace('=', "")
+}
+
+/// Uppercase SAN letter for a piece type (pawns have no letter in SAN, 'P' is returned).
+fn piece_letter(piece_type: PieceType) -> char {
+    match piece_type {
+        PieceType::Pawn => 'P',
+        PieceType::Knight => 'N',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        PieceType::Queen => 'Q',
+        PieceType::King => 'K',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Square;
+
+    fn sq(name: &str) -> Square {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn test_basic_san() {
+        let state = GameState::new();
+        assert_eq!(state.move_to_san(Move::new(sq("e2"), sq("e4"))), "e4");
+        assert_eq!(state.move_to_san(Move::new(sq("g1"), sq("f3"))), "Nf3");
+    }
+
+    #[test]
+    fn test_captures_castling_and_promotion() {
+        let state = GameState::from_fen("r3k2r/1P6/8/3p4/4P3/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(state.move_to_san(Move::new(sq("e4"), sq("d5"))), "exd5");
+        assert_eq!(state.move_to_san(Move::new(sq("e1"), sq("g1"))), "O-O");
+        assert_eq!(state.move_to_san(Move::new(sq("e1"), sq("c1"))), "O-O-O");
+        assert_eq!(
+            state.move_to_san(Move::new_promotion(sq("b7"), sq("a8"), PieceType::Queen)),
+            "bxa8=Q+"
+        );
+    }
+
+    #[test]
+    fn test_disambiguation() {
+        let state = GameState::from_fen("4k3/8/8/R7/8/8/4K3/R6R w - - 0 1").unwrap();
+        assert_eq!(state.move_to_san(Move::new(sq("a1"), sq("d1"))), "Rad1");
+        assert_eq!(state.move_to_san(Move::new(sq("h1"), sq("d1"))), "Rhd1");
+        assert_eq!(state.move_to_san(Move::new(sq("a1"), sq("a3"))), "R1a3");
+        assert_eq!(state.move_to_san(Move::new(sq("a5"), sq("a3"))), "R5a3");
+    }
+
+    #[test]
+    fn test_checkmate_suffix() {
+        let state = GameState::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert_eq!(state.move_to_san(Move::new(sq("a1"), sq("a8"))), "Ra8#");
+    }
+
+    #[test]
+    fn test_parse_san() {
+        let state = GameState::new();
+        assert_eq!(state.parse_san("e4"), Some(Move::new(sq("e2"), sq("e4"))));
+        assert_eq!(state.parse_san("Nf3"), Some(Move::new(sq("g1"), sq("f3"))));
+        assert_eq!(
+            state.parse_san("Nf3!?"),
+            Some(Move::new(sq("g1"), sq("f3")))
+        );
+        assert_eq!(state.parse_san("e5"), None);
+        assert_eq!(state.parse_san("Qh5"), None);
+
+        let state = GameState::from_fen("r3k2r/1P6/8/3p4/4P3/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(state.parse_san("O-O"), Some(Move::new(sq("e1"), sq("g1"))));
+        assert_eq!(
+            state.parse_san("0-0-0"),
+            Some(Move::new(sq("e1"), sq("c1")))
+        );
+        assert_eq!(
+            state.parse_san("bxa8=Q+"),
+            Some(Move::new_promotion(sq("b7"), sq("a8"), PieceType::Queen))
+        );
+        assert_eq!(
+            state.parse_san("bxa8N"),
+            Some(Move::new_promotion(sq("b7"), sq("a8"), PieceType::Knight))
+        );
+
+        // Ambiguous without a disambiguating file
+        let state = GameState::from_fen("4k3/8/8/R7/8/8/4K3/R6R w - - 0 1").unwrap();
+        assert_eq!(state.parse_san("Rd1"), None);
+        assert_eq!(state.parse_san("Rhd1"), Some(Move::new(sq("h1"), sq("d1"))));
+    }
+
+    #[test]
+    fn test_format_san_line() {
+        let state = GameState::new();
+        let line = [
+            Move::new(sq("e2"), sq("e4")),
+            Move::new(sq("e7"), sq("e5")),
+            Move::new(sq("g1"), sq("f3")),
+        ];
+        assert_eq!(format_san_line(&state, &line), "1. e4 e5 2. Nf3");
+
+        let after_e4 = state.apply_move(line[0]);
+        assert_eq!(format_san_line(&after_e4, &line[1..]), "1... e5 2. Nf3");
+    }
+}
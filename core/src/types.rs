@@ -1,4 +1,7 @@
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+use std::str::FromStr;
 
 /// Chess player color.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
@@ -17,6 +20,14 @@ impl fmt::Display for Color {
 }
 
 impl Color {
+    /// Index into per-color tables: 0 for White, 1 for Black.
+    pub const fn index(self) -> usize {
+        match self {
+            Color::White => 0,
+            Color::Black => 1,
+        }
+    }
+
     /// Returns the opposite color.
     pub const fn opponent(self) -> Self {
         match self {
@@ -64,6 +75,18 @@ pub enum PieceType {
 }
 
 impl PieceType {
+    /// Index into per-piece tables, from 0 for pawns to 5 for kings.
+    pub const fn index(self) -> usize {
+        match self {
+            PieceType::Pawn => 0,
+            PieceType::Knight => 1,
+            PieceType::Bishop => 2,
+            PieceType::Rook => 3,
+            PieceType::Queen => 4,
+            PieceType::King => 5,
+        }
+    }
+
     /// Material value in centipawns.
     pub const fn value(self) -> u16 {
         match self {
@@ -94,6 +117,41 @@ impl Piece {
     pub const fn new(piece_type: PieceType, color: Color) -> Self {
         Self { piece_type, color }
     }
+
+    /// FEN letter: uppercase for White, lowercase for Black.
+    pub const fn fen_char(self) -> char {
+        let ch = match self.piece_type {
+            PieceType::Pawn => 'p',
+            PieceType::Knight => 'n',
+            PieceType::Bishop => 'b',
+            PieceType::Rook => 'r',
+            PieceType::Queen => 'q',
+            PieceType::King => 'k',
+        };
+
+        match self.color {
+            Color::White => ch.to_ascii_uppercase(),
+            Color::Black => ch,
+        }
+    }
+
+    /// Unicode chess glyph, e.g. '♕' for a white queen.
+    pub const fn unicode(self) -> char {
+        match (self.piece_type, self.color) {
+            (PieceType::King, Color::White) => '♔',
+            (PieceType::Queen, Color::White) => '♕',
+            (PieceType::Rook, Color::White) => '♖',
+            (PieceType::Bishop, Color::White) => '♗',
+            (PieceType::Knight, Color::White) => '♘',
+            (PieceType::Pawn, Color::White) => '♙',
+            (PieceType::King, Color::Black) => '♚',
+            (PieceType::Queen, Color::Black) => '♛',
+            (PieceType::Rook, Color::Black) => '♜',
+            (PieceType::Bishop, Color::Black) => '♝',
+            (PieceType::Knight, Color::Black) => '♞',
+            (PieceType::Pawn, Color::Black) => '♟',
+        }
+    }
 }
 
 /// Board file (a-h columns).
@@ -257,6 +315,17 @@ impl Square {
         }
     }
 
+    /// Parses a square name such as "e4".
+    pub fn from_algebraic(name: &str) -> Option<Self> {
+        let mut chars = name.chars();
+        let file = File::from_char(chars.next()?)?;
+        let rank = Rank::from_char(chars.next()?)?;
+        if chars.next().is_some() {
+            return None;
+        }
+        Some(Square::new(file, rank))
+    }
+
     /// File of this square.
     pub const fn file(self) -> File {
         File(self.0 % 8)
@@ -272,6 +341,20 @@ impl Square {
         self.0
     }
 
+    /// The same square seen from the other side, so e2 becomes e7.
+    pub const fn flip_vertical(self) -> Self {
+        Square(self.0 ^ 56)
+    }
+
+    /// This square as seen by `color`: unchanged for White, flipped for
+    /// Black, so tables written from White's side serve both colors.
+    pub const fn mirror_for(self, color: Color) -> Self {
+        match color {
+            Color::White => self,
+            Color::Black => self.flip_vertical(),
+        }
+    }
+
     /// Square color (alternating pattern).
     pub const fn color(self) -> Color {
         if (self.file().0 + self.rank().0) % 2 == 0 {
@@ -309,6 +392,102 @@ impl fmt::Display for Square {
     }
 }
 
+/// Reasons a file, rank or square name fails to parse. Each variant carries
+/// the rejected text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseSquareError {
+    InvalidFile(String),
+    InvalidRank(String),
+    InvalidSquare(String),
+}
+
+impl fmt::Display for ParseSquareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseSquareError::InvalidFile(text) => {
+                write!(f, "Invalid file \"{text}\": expected a letter a-h")
+            }
+            ParseSquareError::InvalidRank(text) => {
+                write!(f, "Invalid rank \"{text}\": expected a digit 1-8")
+            }
+            ParseSquareError::InvalidSquare(text) => {
+                write!(f, "Invalid square \"{text}\": expected a name like e4")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseSquareError {}
+
+/// The single character of `text`, if it has exactly one.
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    let c = chars.next()?;
+    chars.next().is_none().then_some(c)
+}
+
+impl TryFrom<&str> for File {
+    type Error = ParseSquareError;
+
+    fn try_from(text: &str) -> Result<Self, Self::Error> {
+        single_char(text)
+            .and_then(File::from_char)
+            .ok_or_else(|| ParseSquareError::InvalidFile(text.to_string()))
+    }
+}
+
+impl FromStr for File {
+    type Err = ParseSquareError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        File::try_from(text)
+    }
+}
+
+impl TryFrom<&str> for Rank {
+    type Error = ParseSquareError;
+
+    fn try_from(text: &str) -> Result<Self, Self::Error> {
+        single_char(text)
+            .and_then(Rank::from_char)
+            .ok_or_else(|| ParseSquareError::InvalidRank(text.to_string()))
+    }
+}
+
+impl FromStr for Rank {
+    type Err = ParseSquareError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Rank::try_from(text)
+    }
+}
+
+impl TryFrom<&str> for Square {
+    type Error = ParseSquareError;
+
+    /// Parses a square name such as "e4".
+    ///
+    /// # Example
+    /// ```
+    /// use chess_core::Square;
+    /// let e4: Square = "e4".parse().unwrap();
+    /// assert_eq!(e4.to_string(), "e4");
+    /// assert!("e9".parse::<Square>().is_err());
+    /// ```
+    fn try_from(text: &str) -> Result<Self, Self::Error> {
+        Square::from_algebraic(text)
+            .ok_or_else(|| ParseSquareError::InvalidSquare(text.to_string()))
+    }
+}
+
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Square::try_from(text)
+    }
+}
+
 /// Castling rights for one color.
 /// Using a struct with booleans ensures clear semantics.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
@@ -373,6 +552,12 @@ impl CastlingRights {
     }
 
     /// Updates rights after a move (handles king/rook moves and captures).
+    ///
+    /// Only the squares are needed: a right can only be held while its king
+    /// and rook are still on their home squares, so any move from or onto
+    /// e1, a1, h1, e8, a8 or h8 moves or captures exactly the piece the
+    /// right depends on. A move there with the right already gone changes
+    /// nothing.
     pub fn update_after_move(self, from: Square, to: Square) -> Self {
         let mut rights = self;
 
@@ -407,22 +592,64 @@ impl CastlingRights {
     }
 }
 
+/// Markers for moves that touch more than their two squares, packed into a
+/// byte. The move generator sets them; moves built by hand carry none.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct MoveFlags(u8);
+
+impl MoveFlags {
+    pub const NONE: Self = Self(0);
+    /// The captured pawn stands beside the destination, not on it.
+    pub const EN_PASSANT: Self = Self(1);
+    /// The king moves two squares and a rook jumps over it.
+    pub const CASTLE: Self = Self(2);
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
 /// Chess move from one square to another.
 /// Includes all information needed to make and unmake the move.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+///
+/// `flags` marks en passant captures and castling so an unmake can restore
+/// the board without consulting the position. Equality and hashing ignore
+/// them: within a position the squares and promotion already name the move,
+/// so a move parsed from text equals the generated one it refers to.
+#[derive(Clone, Copy, Debug, Eq)]
 pub struct Move {
     pub from: Square,
     pub to: Square,
     pub promotion: Option<PieceType>,
+    pub flags: MoveFlags,
+}
+
+impl PartialEq for Move {
+    fn eq(&self, other: &Self) -> bool {
+        self.from == other.from && self.to == other.to && self.promotion == other.promotion
+    }
+}
+
+impl Hash for Move {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.from.hash(state);
+        self.to.hash(state);
+        self.promotion.hash(state);
+    }
 }
 
 impl Move {
+    /// Placeholder for "no move", e.g. in unused move-list slots or a
+    /// table entry without a best move. Written "0000" as in UCI.
+    pub const NULL: Self = Self::new(Square(0), Square(0));
+
     /// Normal move.
     pub const fn new(from: Square, to: Square) -> Self {
         Self {
             from,
             to,
             promotion: None,
+            flags: MoveFlags::NONE,
         }
     }
 
@@ -432,6 +659,67 @@ impl Move {
             from,
             to,
             promotion: Some(promotion),
+            flags: MoveFlags::NONE,
+        }
+    }
+
+    /// En passant capture onto the en passant square `to`.
+    pub const fn new_en_passant(from: Square, to: Square) -> Self {
+        Self {
+            flags: MoveFlags::EN_PASSANT,
+            ..Self::new(from, to)
+        }
+    }
+
+    /// Castling, written as the king's move.
+    pub const fn new_castle(from: Square, to: Square) -> Self {
+        Self {
+            flags: MoveFlags::CASTLE,
+            ..Self::new(from, to)
+        }
+    }
+
+    /// Returns true if the move generator flagged this as an en passant
+    /// capture.
+    pub const fn is_en_passant(self) -> bool {
+        self.flags.contains(MoveFlags::EN_PASSANT)
+    }
+
+    /// Returns true if the move generator flagged this as castling. Unlike
+    /// `is_castle`, this needs no piece, but is false for unflagged moves.
+    pub const fn is_castling(self) -> bool {
+        self.flags.contains(MoveFlags::CASTLE)
+    }
+
+    /// Packs the move into 16 bits: the source square in the top six, the
+    /// destination in the next six, and the promotion piece or flag in the
+    /// low four.
+    pub const fn to_u16(self) -> u16 {
+        let kind = match self.promotion {
+            Some(PieceType::Queen) => 1,
+            Some(PieceType::Rook) => 2,
+            Some(PieceType::Bishop) => 3,
+            Some(PieceType::Knight) => 4,
+            _ if self.is_en_passant() => 5,
+            _ if self.is_castling() => 6,
+            _ => 0,
+        };
+        ((self.from.0 as u16) << 10) | ((self.to.0 as u16) << 4) | kind
+    }
+
+    /// Unpacks a move written by `to_u16`. Unused low-bit values decode as
+    /// a plain move.
+    pub const fn from_u16(bits: u16) -> Self {
+        let from = Square(((bits >> 10) & 0x3F) as u8);
+        let to = Square(((bits >> 4) & 0x3F) as u8);
+        match bits & 0xF {
+            1 => Self::new_promotion(from, to, PieceType::Queen),
+            2 => Self::new_promotion(from, to, PieceType::Rook),
+            3 => Self::new_promotion(from, to, PieceType::Bishop),
+            4 => Self::new_promotion(from, to, PieceType::Knight),
+            5 => Self::new_en_passant(from, to),
+            6 => Self::new_castle(from, to),
+            _ => Self::new(from, to),
         }
     }
 
@@ -444,10 +732,19 @@ impl Move {
     pub const fn is_promotion(self) -> bool {
         self.promotion.is_some()
     }
+
+    /// Returns true if this is `Move::NULL`. No legal move starts and ends
+    /// on the same square.
+    pub const fn is_null(self) -> bool {
+        self.from.0 == self.to.0
+    }
 }
 
 impl fmt::Display for Move {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_null() {
+            return write!(f, "0000");
+        }
         if let Some(promo) = self.promotion {
             let promo_char = match promo {
                 PieceType::Queen => 'q',
@@ -519,6 +816,129 @@ impl BitBoard {
     pub const fn complement(self) -> Self {
         BitBoard(!self.0)
     }
+
+    /// All squares on the given file.
+    pub const fn file_mask(file: File) -> Self {
+        BitBoard(0x0101_0101_0101_0101 << file.index())
+    }
+
+    /// All squares on the given rank.
+    pub const fn rank_mask(rank: Rank) -> Self {
+        BitBoard(0xFF << (rank.index() * 8))
+    }
+
+    /// Shifts every square by `delta` square indices (+8 is one rank up,
+    /// +1 one file to the right, +9 diagonally up-right). Squares that
+    /// would leave the board, including by wrapping around the a- or
+    /// h-file, are dropped. `delta` should move at most three files; a
+    /// shift of 64 or more squares either way leaves the board empty.
+    pub const fn shift(self, delta: i8) -> Self {
+        if delta.unsigned_abs() >= 64 {
+            return BitBoard::EMPTY;
+        }
+        let shifted = if delta >= 0 {
+            self.0 << delta
+        } else {
+            self.0 >> -delta
+        };
+
+        // Sideways part of the shift: 7 mod 8 is one file left, 1 is one right
+        let file_delta = match delta.rem_euclid(8) {
+            d @ 0..=3 => d,
+            d => d - 8,
+        };
+
+        // Clear the files that squares wrapped onto
+        let mut mask = 0u64;
+        let mut i = 0;
+        while i < file_delta.unsigned_abs() {
+            let file = if file_delta > 0 { i } else { 7 - i };
+            mask |= 0x0101_0101_0101_0101 << file;
+            i += 1;
+        }
+
+        BitBoard(shifted & !mask)
+    }
+
+    /// Mirrors the board top to bottom, so a1 becomes a8.
+    pub const fn flip_vertical(self) -> Self {
+        BitBoard(self.0.swap_bytes())
+    }
+
+    /// Mirrors the board left to right, so a1 becomes h1.
+    pub const fn flip_horizontal(self) -> Self {
+        let mut bits = self.0;
+        bits = ((bits >> 1) & 0x5555_5555_5555_5555) | ((bits & 0x5555_5555_5555_5555) << 1);
+        bits = ((bits >> 2) & 0x3333_3333_3333_3333) | ((bits & 0x3333_3333_3333_3333) << 2);
+        bits = ((bits >> 4) & 0x0F0F_0F0F_0F0F_0F0F) | ((bits & 0x0F0F_0F0F_0F0F_0F0F) << 4);
+        BitBoard(bits)
+    }
+
+    /// Returns the lowest set square (a1 first, then b1, ..., h8).
+    pub const fn lsb(self) -> Option<Square> {
+        if self.0 == 0 {
+            None
+        } else {
+            Square::from_index(self.0.trailing_zeros() as u8)
+        }
+    }
+
+    /// Removes and returns the lowest set square.
+    pub fn pop_lsb(&mut self) -> Option<Square> {
+        let square = self.lsb()?;
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+}
+
+impl BitAnd for BitBoard {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        BitBoard(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for BitBoard {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        BitBoard(self.0 | rhs.0)
+    }
+}
+
+impl BitXor for BitBoard {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self {
+        BitBoard(self.0 ^ rhs.0)
+    }
+}
+
+impl Not for BitBoard {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        BitBoard(!self.0)
+    }
+}
+
+impl BitAndAssign for BitBoard {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl BitOrAssign for BitBoard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitXorAssign for BitBoard {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        self.0 ^= rhs.0;
+    }
 }
 
 /// Iterator over set squares in a bitboard.
@@ -551,6 +971,37 @@ impl BitBoard {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_table_indices() {
+        assert_eq!(Color::White.index(), 0);
+        assert_eq!(Color::Black.index(), 1);
+
+        let piece_types = [
+            PieceType::Pawn,
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+            PieceType::King,
+        ];
+        for (index, piece_type) in piece_types.into_iter().enumerate() {
+            assert_eq!(piece_type.index(), index, "{piece_type:?}");
+            // The discriminants agree, so a stray cast still indexes correctly
+            assert_eq!(piece_type as usize, index, "{piece_type:?}");
+        }
+    }
+
+    #[test]
+    fn test_piece_symbols() {
+        assert_eq!(
+            Piece::new(PieceType::Queen, Color::White).unicode(),
+            '\u{2655}'
+        );
+        assert_eq!(Piece::new(PieceType::Pawn, Color::Black).fen_char(), 'p');
+        assert_eq!(Piece::new(PieceType::Knight, Color::White).fen_char(), 'N');
+        assert_eq!(Piece::new(PieceType::King, Color::Black).unicode(), '♚');
+    }
+
     #[test]
     fn test_color_opponent() {
         assert_eq!(Color::White.opponent(), Color::Black);
@@ -564,6 +1015,70 @@ mod tests {
         assert_eq!(format!("{}", e4), "e4");
     }
 
+    #[test]
+    fn test_parse_squares() {
+        assert_eq!("a1".parse::<Square>(), Ok(Square::from_index(0).unwrap()));
+        assert_eq!("h8".parse::<Square>(), Ok(Square::from_index(63).unwrap()));
+        assert_eq!(File::try_from("c"), Ok(File::new(2).unwrap()));
+        assert_eq!(Rank::try_from("7"), Ok(Rank::SEVENTH));
+
+        for bad in ["i9", "e", "e4x", ""] {
+            assert_eq!(
+                bad.parse::<Square>(),
+                Err(ParseSquareError::InvalidSquare(bad.to_string()))
+            );
+        }
+        assert_eq!(
+            File::try_from("ab"),
+            Err(ParseSquareError::InvalidFile("ab".to_string()))
+        );
+        assert_eq!(
+            "9".parse::<Rank>(),
+            Err(ParseSquareError::InvalidRank("9".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_square_round_trips_and_colors() {
+        for index in 0..64 {
+            let square = Square::from_index(index).unwrap();
+            let name = square.to_string();
+            assert_eq!(name.parse::<Square>(), Ok(square), "{name}");
+            assert_eq!(Square::from_algebraic(&name), Some(square));
+
+            // Neighbors along a rank or file always differ in color
+            if let Some(right) = square.file().offset(1) {
+                let right = Square::new(right, square.rank());
+                assert_ne!(square.color(), right.color(), "{name}");
+            }
+            if let Some(up) = Square::from_index(index + 8) {
+                assert_ne!(square.color(), up.color(), "{name}");
+            }
+        }
+
+        // a1 is dark and h1 light, so each player has a light square on
+        // their right; the queens start on their own color
+        let color = |name: &str| name.parse::<Square>().unwrap().color();
+        assert_eq!(color("a1"), Color::Black);
+        assert_eq!(color("h1"), Color::White);
+        assert_eq!(color("a8"), Color::White);
+        assert_eq!(color("h8"), Color::Black);
+        assert_eq!(color("d1"), Color::White);
+        assert_eq!(color("d8"), Color::Black);
+        assert_eq!(color("e4"), Color::White);
+        assert_eq!(color("d4"), Color::Black);
+    }
+
+    #[test]
+    fn test_null_move() {
+        assert!(Move::NULL.is_null());
+        assert_eq!(Move::NULL.to_string(), "0000");
+
+        let e2e4 = Move::new("e2".parse().unwrap(), "e4".parse().unwrap());
+        assert!(!e2e4.is_null());
+        assert_eq!(e2e4.to_string(), "e2e4");
+    }
+
     #[test]
     fn test_bitboard_operations() {
         let bb1 = BitBoard::from_square(Square::from_index(0).unwrap());
@@ -573,4 +1088,100 @@ mod tests {
         assert_eq!(bb1.union(bb2).count(), 2);
         assert!(bb1.intersection(bb2).is_empty());
     }
+
+    fn bb(squares: &[u8]) -> BitBoard {
+        squares.iter().fold(BitBoard::EMPTY, |bb, &i| {
+            bb.set(Square::from_index(i).unwrap())
+        })
+    }
+
+    #[test]
+    fn test_bitboard_operators() {
+        let a = bb(&[0, 1, 2]);
+        let b = bb(&[2, 3]);
+
+        assert_eq!(a & b, bb(&[2]));
+        assert_eq!(a | b, bb(&[0, 1, 2, 3]));
+        assert_eq!(a ^ b, bb(&[0, 1, 3]));
+        assert_eq!(!BitBoard::EMPTY, BitBoard::FULL);
+        assert_eq!((!a).count(), 61);
+
+        let mut c = a;
+        c &= b;
+        c |= bb(&[63]);
+        c ^= bb(&[2]);
+        assert_eq!(c, bb(&[63]));
+    }
+
+    #[test]
+    fn test_bitboard_shift_masks_file_edges() {
+        // a1, h1, e4
+        let squares = bb(&[0, 7, 28]);
+
+        assert_eq!(squares.shift(8), bb(&[8, 15, 36]));
+        assert_eq!(squares.shift(-8), bb(&[20]));
+        // h1 must not wrap onto a2, a1 must not wrap onto h-file
+        assert_eq!(squares.shift(1), bb(&[1, 29]));
+        assert_eq!(squares.shift(-1), bb(&[6, 27]));
+        assert_eq!(squares.shift(9), bb(&[9, 37]));
+        assert_eq!(squares.shift(7), bb(&[14, 35]));
+        assert_eq!(squares.shift(-7), bb(&[21]));
+        assert_eq!(squares.shift(-9), bb(&[19]));
+        // Knight-style jumps
+        assert_eq!(squares.shift(17), bb(&[17, 45]));
+        assert_eq!(squares.shift(6), bb(&[13, 34]));
+        assert_eq!(
+            BitBoard::FULL.shift(2) & BitBoard::file_mask(File::new(1).unwrap()),
+            BitBoard::EMPTY
+        );
+        // Shifting by a whole board or more leaves nothing behind
+        for delta in [64, -64, 127, -128] {
+            assert_eq!(BitBoard::FULL.shift(delta), BitBoard::EMPTY);
+        }
+        assert_eq!(squares.shift(63), BitBoard::EMPTY);
+        assert_eq!(squares.shift(-63), BitBoard::EMPTY);
+    }
+
+    #[test]
+    fn test_flips() {
+        let a1 = "a1".parse::<Square>().unwrap();
+        assert_eq!(
+            BitBoard::from_square(a1).flip_vertical(),
+            BitBoard::from_square("a8".parse().unwrap())
+        );
+        assert_eq!(
+            BitBoard::from_square(a1).flip_horizontal(),
+            BitBoard::from_square("h1".parse().unwrap())
+        );
+        assert_eq!(
+            "e2".parse::<Square>().unwrap().flip_vertical(),
+            "e7".parse().unwrap()
+        );
+        assert_eq!(a1.mirror_for(Color::White), a1);
+        assert_eq!(a1.mirror_for(Color::Black), "a8".parse().unwrap());
+
+        // a1, h1, e4
+        let squares = bb(&[0, 7, 28]);
+        assert_eq!(squares.flip_vertical(), bb(&[56, 63, 36]));
+        assert_eq!(squares.flip_horizontal(), bb(&[7, 0, 27]));
+        assert_eq!(squares.flip_vertical().flip_vertical(), squares);
+        for sq in squares.iter() {
+            assert!(squares.flip_vertical().contains(sq.flip_vertical()));
+        }
+    }
+
+    #[test]
+    fn test_pop_lsb_iterates_in_order() {
+        let mut squares = bb(&[63, 5, 12, 0, 40]);
+        assert_eq!(squares.lsb(), Square::from_index(0));
+
+        let mut popped = Vec::new();
+        while let Some(square) = squares.pop_lsb() {
+            popped.push(square.index());
+        }
+
+        assert_eq!(popped, vec![0, 5, 12, 40, 63]);
+        assert!(squares.is_empty());
+        assert_eq!(squares.lsb(), None);
+    }
 }
@@ -1,4 +1,8 @@
-use crate::types::{CastlingRights, Color, Piece, PieceType, Square};
+use crate::types::{CastlingRights, Color, Piece, Square};
+
+/// Seed for the Zobrist key generator.
+/// Fixed so hashes are identical across program runs and machines.
+pub const ZOBRIST_SEED: u64 = 0x123456789ABCDEF;
 
 /// Zobrist hashing for chess positions.
 /// Uses pre-computed random numbers for each piece-square combination.
@@ -18,8 +22,8 @@ impl ZobristKeys {
     /// Creates a new set of Zobrist keys with deterministic random values.
     /// Uses a fixed seed for reproducibility.
     pub fn new() -> Self {
-        // Use a simple linear congruential generator for deterministic randomness
-        let mut rng = 0x123456789ABCDEFu64;
+        // Use a simple xorshift generator for deterministic randomness
+        let mut rng = ZOBRIST_SEED;
         let mut next_random = || {
             rng ^= rng << 13;
             rng ^= rng >> 7;
@@ -58,16 +62,7 @@ impl ZobristKeys {
 
     /// Gets the Zobrist key for a piece on a square.
     pub fn piece_square_key(&self, piece: Piece, square: Square) -> u64 {
-        let color_idx = piece.color as usize;
-        let piece_idx = match piece.piece_type {
-            PieceType::Pawn => 0,
-            PieceType::Knight => 1,
-            PieceType::Bishop => 2,
-            PieceType::Rook => 3,
-            PieceType::Queen => 4,
-            PieceType::King => 5,
-        };
-        self.piece_square[color_idx][piece_idx][square.index() as usize]
+        self.piece_square[piece.color.index()][piece.piece_type.index()][square.index() as usize]
     }
 
     /// Gets the Zobrist key for the side to move.
@@ -114,3 +109,135 @@ impl Default for ZobristKeys {
 /// Global Zobrist keys instance.
 /// Initialized once and shared across the application.
 pub static ZOBRIST: std::sync::LazyLock<ZobristKeys> = std::sync::LazyLock::new(ZobristKeys::new);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::GameState;
+    use crate::move_gen::generate_legal_moves;
+    use crate::types::{Move, PieceType};
+    use std::collections::{HashMap, HashSet};
+
+    const STARTING_POSITION_HASH: u64 = 0xD2795A9B4C47648F;
+
+    /// FEN without the move counters, which are not part of the hash.
+    fn position_key(state: &GameState) -> String {
+        state.to_fen().rsplitn(3, ' ').nth(2).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_hash_is_stable_across_runs() {
+        let state = GameState::new();
+        let keys = ZobristKeys::new();
+        let piece = Piece::new(PieceType::Queen, Color::Black);
+        let square = Square::from_index(59).unwrap();
+
+        assert_eq!(
+            keys.piece_square_key(piece, square),
+            ZOBRIST.piece_square_key(piece, square)
+        );
+        assert_eq!(state.zobrist_hash(), GameState::new().zobrist_hash());
+        // Pinned so any change to key generation is noticed
+        assert_eq!(state.zobrist_hash(), STARTING_POSITION_HASH);
+    }
+
+    #[test]
+    fn test_no_collisions_within_depth_four() {
+        let mut layer = vec![GameState::new()];
+        let mut hashes: HashMap<u64, String> = HashMap::new();
+        let mut seen: HashSet<String> = HashSet::new();
+
+        for depth in 0..=4 {
+            let mut next_layer = Vec::new();
+
+            for state in &layer {
+                let key = position_key(state);
+                if !seen.insert(key.clone()) {
+                    continue;
+                }
+
+                let hash = state.zobrist_hash();
+                if let Some(existing) = hashes.insert(hash, key.clone()) {
+                    panic!("Hash collision between {existing} and {key}");
+                }
+
+                if depth < 4 {
+                    for mv in generate_legal_moves(state).iter() {
+                        next_layer.push(state.apply_move(*mv));
+                    }
+                }
+            }
+
+            layer = next_layer;
+        }
+
+        assert_eq!(hashes.len(), seen.len());
+    }
+
+    fn mv(from: &str, to: &str) -> Move {
+        Move::new(from.parse().unwrap(), to.parse().unwrap())
+    }
+
+    #[test]
+    fn test_incremental_hash_updates_castling_rights() {
+        let state = GameState::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let (after, hash) = state.apply_move_with_hash(mv("h1", "h2"), state.zobrist_hash());
+
+        let rook = Piece::new(PieceType::Rook, Color::White);
+        let expected = state.zobrist_hash()
+            ^ ZOBRIST.piece_square_key(rook, "h1".parse().unwrap())
+            ^ ZOBRIST.piece_square_key(rook, "h2".parse().unwrap())
+            ^ ZOBRIST.castling_key(state.castling)
+            ^ ZOBRIST.castling_key(after.castling)
+            ^ ZOBRIST.side_to_move_key(Color::Black);
+        assert_ne!(state.castling, after.castling);
+        assert_eq!(hash, expected);
+        assert_eq!(hash, after.zobrist_hash());
+    }
+
+    #[test]
+    fn test_incremental_hash_drops_en_passant() {
+        let state = GameState::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+        let (after, hash) = state.apply_move_with_hash(mv("e8", "d7"), state.zobrist_hash());
+
+        let king = Piece::new(PieceType::King, Color::Black);
+        let expected = state.zobrist_hash()
+            ^ ZOBRIST.piece_square_key(king, "e8".parse().unwrap())
+            ^ ZOBRIST.piece_square_key(king, "d7".parse().unwrap())
+            ^ ZOBRIST.en_passant_key(state.en_passant)
+            ^ ZOBRIST.side_to_move_key(Color::Black);
+        assert_eq!(after.en_passant, None);
+        assert_eq!(hash, expected);
+        assert_eq!(hash, after.zobrist_hash());
+    }
+
+    #[test]
+    fn test_incremental_hash_matches_full_hash() {
+        // Kiwipete has castles and en passant captures within two plies;
+        // the random games below reach promotions
+        for fen in [
+            crate::fen::positions::KIWIPETE,
+            crate::fen::positions::STARTING,
+        ] {
+            let root = GameState::from_fen(fen).unwrap();
+            for first in generate_legal_moves(&root).iter() {
+                let (state, hash) = root.apply_move_with_hash(*first, root.zobrist_hash());
+                assert_eq!(hash, state.zobrist_hash(), "{first}");
+                for second in generate_legal_moves(&state).iter() {
+                    let (next, hash) = state.apply_move_with_hash(*second, hash);
+                    assert_eq!(hash, next.zobrist_hash(), "{first} {second}");
+                }
+            }
+        }
+
+        for seed in 0..20 {
+            let (moves, _) = crate::random_game(seed, 300);
+            let mut state = GameState::new();
+            let mut hash = state.zobrist_hash();
+            for &mv in &moves {
+                (state, hash) = state.apply_move_with_hash(mv, hash);
+                assert_eq!(hash, state.zobrist_hash(), "seed {seed}, {mv}");
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use chess_core::Square;
+
+/// Squares and arrows drawn with the right mouse button while analyzing.
+///
+/// They are purely cosmetic: nothing here touches the game, and the board
+/// clears them whenever the position changes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Annotations {
+    squares: Vec<Square>,
+    arrows: Vec<(Square, Square)>,
+}
+
+impl Annotations {
+    pub fn squares(&self) -> &[Square] {
+        &self.squares
+    }
+
+    pub fn arrows(&self) -> &[(Square, Square)] {
+        &self.arrows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.squares.is_empty() && self.arrows.is_empty()
+    }
+
+    /// Applies a finished right-button gesture: releasing on the pressed
+    /// square toggles its highlight, releasing elsewhere toggles an arrow.
+    pub fn toggle(&mut self, from: Square, to: Square) {
+        if from == to {
+            toggle_item(&mut self.squares, from);
+        } else {
+            toggle_item(&mut self.arrows, (from, to));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.squares.clear();
+        self.arrows.clear();
+    }
+}
+
+fn toggle_item<T: PartialEq>(items: &mut Vec<T>, item: T) {
+    if let Some(index) = items.iter().position(|existing| *existing == item) {
+        items.remove(index);
+    } else {
+        items.push(item);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+
+    #[test]
+    fn test_gestures_toggle_squares_and_arrows() {
+        let mut annotations = Annotations::default();
+        assert!(annotations.is_empty());
+
+        annotations.toggle(square("e4"), square("e4"));
+        annotations.toggle(square("g1"), square("f3"));
+        assert_eq!(annotations.squares(), [square("e4")]);
+        assert_eq!(annotations.arrows(), [(square("g1"), square("f3"))]);
+
+        // The reverse arrow is a different annotation
+        annotations.toggle(square("f3"), square("g1"));
+        assert_eq!(annotations.arrows().len(), 2);
+
+        // Repeating a gesture removes what it drew
+        annotations.toggle(square("e4"), square("e4"));
+        annotations.toggle(square("g1"), square("f3"));
+        assert!(annotations.squares().is_empty());
+        assert_eq!(annotations.arrows(), [(square("f3"), square("g1"))]);
+
+        annotations.clear();
+        assert!(annotations.is_empty());
+    }
+}
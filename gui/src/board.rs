@@ -1,41 +1,37 @@
+use crate::annotations::Annotations;
+use crate::layout::Layout;
 use crate::renderer::Vertex;
-use chess_core::{Move, Square};
+use crate::theme::{BoardTheme, ThemeColors};
+use chess_core::{BitBoard, Move, Square};
 
 pub struct BoardRenderer {
     vertices: Vec<Vertex>,
-    light_color: [f32; 4],
-    dark_color: [f32; 4],
-    selected_color: [f32; 4],
-    valid_move_color: [f32; 4],
-    last_move_color: [f32; 4],
-    board_size: f32,
-    square_size: f32,
+    colors: ThemeColors,
+    layout: Layout,
     selected_square: Option<Square>,
     valid_moves: Vec<Square>,
     last_move: Option<Move>,
+    arrow: Option<(Square, Square)>,
+    threats: BitBoard,
+    premove: Vec<Square>,
+    annotations: Annotations,
+    rejected: Option<Square>,
 }
 
 impl BoardRenderer {
-    pub fn new(board_size: f32) -> Self {
-        let light_color = [0.93, 0.93, 0.82, 1.0]; // Light beige
-        let dark_color = [0.54, 0.27, 0.07, 1.0]; // Dark brown
-        let selected_color = [0.7, 0.7, 0.3, 1.0]; // Yellow highlight
-        let valid_move_color = [0.3, 0.7, 0.3, 0.5]; // Semi-transparent green
-        let last_move_color = [0.5, 0.3, 0.7, 0.3]; // Semi-transparent purple
-        let square_size = board_size / 8.0;
-
+    pub fn new(layout: Layout) -> Self {
         Self {
             vertices: Vec::with_capacity(8 * 8 * 6), // 6 vertices per square
-            light_color,
-            dark_color,
-            selected_color,
-            valid_move_color,
-            last_move_color,
-            board_size,
-            square_size,
+            colors: BoardTheme::default().colors(),
+            layout,
             selected_square: None,
             valid_moves: Vec::new(),
             last_move: None,
+            arrow: None,
+            threats: BitBoard::EMPTY,
+            premove: Vec::new(),
+            annotations: Annotations::default(),
+            rejected: None,
         }
     }
 
@@ -48,184 +44,161 @@ impl BoardRenderer {
         self.last_move = last_move;
     }
 
+    pub fn set_theme(&mut self, theme: BoardTheme) {
+        self.colors = theme.colors();
+    }
+
+    pub fn set_arrow(&mut self, arrow: Option<(Square, Square)>) {
+        self.arrow = arrow;
+    }
+
+    pub fn set_threats(&mut self, threats: BitBoard) {
+        self.threats = threats;
+    }
+
+    /// Squares of a queued pre-move, or of the piece picked for one.
+    pub fn set_premove(&mut self, squares: Vec<Square>) {
+        self.premove = squares;
+    }
+
+    /// Right-click highlights and arrows, plus the arrow being dragged.
+    pub fn set_annotations(&mut self, annotations: Annotations) {
+        self.annotations = annotations;
+    }
+
+    /// Source square of a move that was just rejected, flashed briefly.
+    pub fn set_rejected(&mut self, rejected: Option<Square>) {
+        self.rejected = rejected;
+    }
+
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+    }
+
     pub fn generate_vertices(&mut self) -> &[Vertex] {
         self.vertices.clear();
 
-        for row in 0..8 {
-            for col in 0..8 {
-                // Convert to chess square for checking selection
-                let rank = 7 - row;
-                let square = if let (Some(file), Some(rank)) = (
-                    chess_core::File::new(col as u8),
-                    chess_core::Rank::new(rank as u8),
-                ) {
-                    Some(Square::new(file, rank))
-                } else {
-                    None
-                };
-
-                // Determine base color
-                let mut color = if (row + col) % 2 == 0 {
-                    self.light_color
-                } else {
-                    self.dark_color
-                };
-
-                // Apply selection highlight
-                if let Some(sq) = square {
-                    if Some(sq) == self.selected_square {
-                        color = self.selected_color;
-                    }
-                }
+        for index in 0..64 {
+            let Some(square) = Square::from_index(index) else {
+                continue;
+            };
+
+            // Base color, or the selection highlight
+            let color = if Some(square) == self.selected_square {
+                self.colors.selected
+            } else if (square.file().index() + square.rank().index()) % 2 == 1 {
+                self.colors.light
+            } else {
+                self.colors.dark
+            };
+
+            self.push_square_overlay(square, color);
+        }
 
-                let x = col as f32 * self.square_size;
-                let y = row as f32 * self.square_size;
-
-                // Convert to normalized device coordinates [-1, 1]
-                // Board takes up left 80% of window (from -1.0 to 0.6)
-                let board_width = 1.6; // 80% of NDC width
-                let ndc_x = (x / self.board_size) * board_width - 1.0;
-                let ndc_y = 1.0 - (y / self.board_size) * 2.0; // Flip Y
-                let ndc_x2 = ((x + self.square_size) / self.board_size) * board_width - 1.0;
-                let ndc_y2 = 1.0 - ((y + self.square_size) / self.board_size) * 2.0;
-
-                // Create two triangles for a square
-                // Triangle 1
-                self.vertices.push(Vertex {
-                    position: [ndc_x, ndc_y],
-                    color,
-                });
-                self.vertices.push(Vertex {
-                    position: [ndc_x2, ndc_y],
-                    color,
-                });
-                self.vertices.push(Vertex {
-                    position: [ndc_x, ndc_y2],
-                    color,
-                });
-
-                // Triangle 2
-                self.vertices.push(Vertex {
-                    position: [ndc_x2, ndc_y],
-                    color,
-                });
-                self.vertices.push(Vertex {
-                    position: [ndc_x2, ndc_y2],
-                    color,
-                });
-                self.vertices.push(Vertex {
-                    position: [ndc_x, ndc_y2],
-                    color,
-                });
+        // Tint squares attacked by the opponent when the threat overlay is on
+        for index in 0..64 {
+            if let Some(square) = Square::from_index(index) {
+                if self.threats.contains(square) {
+                    self.push_square_overlay(square, self.colors.threat);
+                }
             }
         }
 
         // Add semi-transparent overlay for last move
         if let Some(last_move) = self.last_move {
-            for &square in &[last_move.from, last_move.to] {
-                let col = square.file().index() as usize;
-                let row = 7 - square.rank().index() as usize;
-
-                let x = col as f32 * self.square_size;
-                let y = row as f32 * self.square_size;
-
-                let board_width = 1.6;
-                let ndc_x = (x / self.board_size) * board_width - 1.0;
-                let ndc_y = 1.0 - (y / self.board_size) * 2.0;
-                let ndc_x2 = ((x + self.square_size) / self.board_size) * board_width - 1.0;
-                let ndc_y2 = 1.0 - ((y + self.square_size) / self.board_size) * 2.0;
-
-                let color = self.last_move_color;
-
-                self.vertices.push(Vertex {
-                    position: [ndc_x, ndc_y],
-                    color,
-                });
-                self.vertices.push(Vertex {
-                    position: [ndc_x2, ndc_y],
-                    color,
-                });
-                self.vertices.push(Vertex {
-                    position: [ndc_x, ndc_y2],
-                    color,
-                });
-
-                self.vertices.push(Vertex {
-                    position: [ndc_x2, ndc_y],
-                    color,
-                });
-                self.vertices.push(Vertex {
-                    position: [ndc_x2, ndc_y2],
-                    color,
-                });
-                self.vertices.push(Vertex {
-                    position: [ndc_x, ndc_y2],
-                    color,
-                });
+            for square in [last_move.from, last_move.to] {
+                self.push_square_overlay(square, self.colors.last_move);
             }
         }
 
+        if let Some(square) = self.rejected {
+            self.push_square_overlay(square, self.colors.rejected);
+        }
+
+        for square in self.premove.clone() {
+            self.push_square_overlay(square, self.colors.premove);
+        }
+
         // Add semi-transparent overlays for valid moves
-        for &valid_square in &self.valid_moves {
-            let col = valid_square.file().index() as usize;
-            let row = 7 - valid_square.rank().index() as usize; // Convert chess rank to board row
-
-            let x = col as f32 * self.square_size;
-            let y = row as f32 * self.square_size;
-
-            // Convert to normalized device coordinates [-1, 1]
-            // Board takes up left 80% of window (from -1.0 to 0.6)
-            let board_width = 1.6; // 80% of NDC width
-            let ndc_x = (x / self.board_size) * board_width - 1.0;
-            let ndc_y = 1.0 - (y / self.board_size) * 2.0; // Flip Y
-            let ndc_x2 = ((x + self.square_size) / self.board_size) * board_width - 1.0;
-            let ndc_y2 = 1.0 - ((y + self.square_size) / self.board_size) * 2.0;
-
-            let color = self.valid_move_color;
-
-            // Create two triangles for the overlay
-            self.vertices.push(Vertex {
-                position: [ndc_x, ndc_y],
-                color,
-            });
-            self.vertices.push(Vertex {
-                position: [ndc_x2, ndc_y],
-                color,
-            });
-            self.vertices.push(Vertex {
-                position: [ndc_x, ndc_y2],
-                color,
-            });
-
-            self.vertices.push(Vertex {
-                position: [ndc_x2, ndc_y],
-                color,
-            });
-            self.vertices.push(Vertex {
-                position: [ndc_x2, ndc_y2],
-                color,
-            });
-            self.vertices.push(Vertex {
-                position: [ndc_x, ndc_y2],
-                color,
-            });
+        for square in self.valid_moves.clone() {
+            self.push_square_overlay(square, self.colors.valid_move);
+        }
+
+        for square in self.annotations.squares().to_vec() {
+            self.push_square_overlay(square, self.colors.annotation);
+        }
+
+        // Draw arrows last so they sit on top of every square overlay
+        for (from, to) in self.annotations.arrows().to_vec() {
+            self.push_arrow(from, to, self.colors.annotation);
+        }
+        if let Some((from, to)) = self.arrow {
+            self.push_arrow(from, to, self.colors.arrow);
         }
 
         &self.vertices
     }
 
-    pub fn get_square_at(&self, x: f32, y: f32) -> Option<(usize, usize)> {
-        if x < 0.0 || x >= self.board_size || y < 0.0 || y >= self.board_size {
-            return None;
+    /// Adds a quad covering one square.
+    fn push_square_overlay(&mut self, square: Square, color: [f32; 4]) {
+        let square_size = self.layout.square_size();
+        let (x, y) = self.layout.square_origin(square);
+        let [ndc_x, ndc_y] = self.layout.pixel_to_ndc(x, y);
+        let [ndc_x2, ndc_y2] = self.layout.pixel_to_ndc(x + square_size, y + square_size);
+
+        for position in [
+            [ndc_x, ndc_y],
+            [ndc_x2, ndc_y],
+            [ndc_x, ndc_y2],
+            [ndc_x2, ndc_y],
+            [ndc_x2, ndc_y2],
+            [ndc_x, ndc_y2],
+        ] {
+            self.vertices.push(Vertex { position, color });
         }
+    }
+
+    /// Adds an arrow between the centers of two squares: a quad for the shaft
+    /// and a triangle for the head, built in board pixels and mapped to NDC.
+    fn push_arrow(&mut self, from: Square, to: Square, color: [f32; 4]) {
+        let (x1, y1) = self.layout.square_center(from);
+        let (x2, y2) = self.layout.square_center(to);
 
-        let col = (x / self.square_size) as usize;
-        let row = (y / self.square_size) as usize;
+        let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+        if length == 0.0 {
+            return;
+        }
 
-        if col < 8 && row < 8 {
-            Some((row, col))
-        } else {
-            None
+        // Unit direction along the arrow and its perpendicular
+        let (dx, dy) = ((x2 - x1) / length, (y2 - y1) / length);
+        let (px, py) = (-dy, dx);
+
+        let square_size = self.layout.square_size();
+        let shaft_half_width = square_size * 0.08;
+        let head_half_width = square_size * 0.22;
+        let head_length = (square_size * 0.4).min(length);
+
+        // Where the shaft ends and the head begins
+        let bx = x2 - dx * head_length;
+        let by = y2 - dy * head_length;
+
+        let shaft = [
+            (x1 + px * shaft_half_width, y1 + py * shaft_half_width),
+            (x1 - px * shaft_half_width, y1 - py * shaft_half_width),
+            (bx + px * shaft_half_width, by + py * shaft_half_width),
+            (x1 - px * shaft_half_width, y1 - py * shaft_half_width),
+            (bx - px * shaft_half_width, by - py * shaft_half_width),
+            (bx + px * shaft_half_width, by + py * shaft_half_width),
+        ];
+        let head = [
+            (bx + px * head_half_width, by + py * head_half_width),
+            (bx - px * head_half_width, by - py * head_half_width),
+            (x2, y2),
+        ];
+
+        for (x, y) in shaft.into_iter().chain(head) {
+            let position = self.layout.pixel_to_ndc(x, y);
+            self.vertices.push(Vertex { position, color });
         }
     }
 }
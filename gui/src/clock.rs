@@ -0,0 +1,202 @@
+use chess_core::Color;
+use std::time::{Duration, Instant};
+
+/// Time controls offered on the mode selection screen.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TimeControl {
+    #[default]
+    Untimed,
+    ThreePlusTwo,
+    FivePlusZero,
+    TenPlusFive,
+}
+
+impl TimeControl {
+    pub fn name(self) -> &'static str {
+        match self {
+            TimeControl::Untimed => "None",
+            TimeControl::ThreePlusTwo => "3+2",
+            TimeControl::FivePlusZero => "5+0",
+            TimeControl::TenPlusFive => "10+5",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            TimeControl::Untimed => TimeControl::ThreePlusTwo,
+            TimeControl::ThreePlusTwo => TimeControl::FivePlusZero,
+            TimeControl::FivePlusZero => TimeControl::TenPlusFive,
+            TimeControl::TenPlusFive => TimeControl::Untimed,
+        }
+    }
+
+    /// A fresh clock for this control, or `None` for untimed games.
+    pub fn clock(self) -> Option<Clock> {
+        let (minutes, increment) = match self {
+            TimeControl::Untimed => return None,
+            TimeControl::ThreePlusTwo => (3, 2),
+            TimeControl::FivePlusZero => (5, 0),
+            TimeControl::TenPlusFive => (10, 5),
+        };
+        Some(Clock::new(
+            Duration::from_secs(minutes * 60),
+            Duration::from_secs(increment),
+        ))
+    }
+}
+
+/// Remaining time for both sides. At most one side runs at a time, and
+/// pausing banks the time it used so far.
+#[derive(Debug, Clone)]
+pub struct Clock {
+    remaining: [Duration; 2],
+    increment: Duration,
+    running: Option<(Color, Instant)>,
+}
+
+impl Clock {
+    pub fn new(base: Duration, increment: Duration) -> Self {
+        Self {
+            remaining: [base; 2],
+            increment,
+            running: None,
+        }
+    }
+
+    /// Divides `giver`'s remaining time by `divisor`, for time odds.
+    pub fn with_odds(mut self, giver: Color, divisor: u32) -> Self {
+        self.remaining[giver.index()] /= divisor.max(1);
+        self
+    }
+
+    pub fn increment(&self) -> Duration {
+        self.increment
+    }
+
+    pub fn remaining(&self, color: Color, now: Instant) -> Duration {
+        let banked = self.remaining[color.index()];
+        match self.running {
+            Some((running, since)) if running == color => {
+                banked.saturating_sub(now.saturating_duration_since(since))
+            }
+            _ => banked,
+        }
+    }
+
+    /// The side whose time is running, if any.
+    pub fn running(&self) -> Option<Color> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// Runs `color`'s time, stopping the other side's.
+    pub fn start(&mut self, color: Color, now: Instant) {
+        self.pause(now);
+        self.running = Some((color, now));
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        if let Some((color, _)) = self.running {
+            self.remaining[color.index()] = self.remaining(color, now);
+            self.running = None;
+        }
+    }
+
+    /// Ends `mover`'s turn: banks their time, adds the increment and starts
+    /// the opponent's time.
+    pub fn press(&mut self, mover: Color, now: Instant) {
+        self.pause(now);
+        self.remaining[mover.index()] += self.increment;
+        self.running = Some((mover.opponent(), now));
+    }
+
+    /// The side that has run out of time, if any.
+    pub fn flagged(&self, now: Instant) -> Option<Color> {
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|&color| self.remaining(color, now).is_zero())
+    }
+}
+
+/// Formats a clock reading as `m:ss`, with tenths in the last ten seconds.
+pub fn format_clock(time: Duration) -> String {
+    if time < Duration::from_secs(10) {
+        format!("{}.{}", time.as_secs(), time.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", time.as_secs() / 60, time.as_secs() % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_runs_only_for_the_side_to_move() {
+        let start = Instant::now();
+        let mut clock = TimeControl::ThreePlusTwo.clock().unwrap();
+        assert_eq!(clock.running(), None);
+
+        clock.start(Color::White, start);
+        let after_move = start + Duration::from_secs(10);
+        assert_eq!(
+            clock.remaining(Color::White, after_move),
+            Duration::from_secs(170)
+        );
+        assert_eq!(
+            clock.remaining(Color::Black, after_move),
+            Duration::from_secs(180)
+        );
+
+        // White's move banks the time used and earns the increment
+        clock.press(Color::White, after_move);
+        assert_eq!(clock.running(), Some(Color::Black));
+        let later = after_move + Duration::from_secs(30);
+        assert_eq!(
+            clock.remaining(Color::White, later),
+            Duration::from_secs(172)
+        );
+        assert_eq!(
+            clock.remaining(Color::Black, later),
+            Duration::from_secs(150)
+        );
+
+        // Nothing runs while paused
+        clock.pause(later);
+        let much_later = later + Duration::from_secs(600);
+        assert_eq!(
+            clock.remaining(Color::Black, much_later),
+            Duration::from_secs(150)
+        );
+        assert_eq!(clock.flagged(much_later), None);
+    }
+
+    #[test]
+    fn test_flag_fall() {
+        let start = Instant::now();
+        let mut clock = Clock::new(Duration::from_secs(5), Duration::ZERO);
+        clock.start(Color::Black, start);
+
+        assert_eq!(clock.flagged(start + Duration::from_secs(4)), None);
+        let flag = start + Duration::from_secs(6);
+        assert_eq!(clock.flagged(flag), Some(Color::Black));
+        assert_eq!(clock.remaining(Color::Black, flag), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_time_odds_shorten_one_side() {
+        let now = Instant::now();
+        let clock = TimeControl::FivePlusZero
+            .clock()
+            .unwrap()
+            .with_odds(Color::Black, 2);
+        assert_eq!(clock.remaining(Color::White, now), Duration::from_secs(300));
+        assert_eq!(clock.remaining(Color::Black, now), Duration::from_secs(150));
+    }
+
+    #[test]
+    fn test_format_clock() {
+        assert_eq!(format_clock(Duration::from_secs(300)), "5:00");
+        assert_eq!(format_clock(Duration::from_secs(61)), "1:01");
+        assert_eq!(format_clock(Duration::from_millis(9_450)), "9.4");
+    }
+}
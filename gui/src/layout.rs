@@ -0,0 +1,167 @@
+use chess_core::{File, Rank, Square};
+use winit::dpi::PhysicalSize;
+
+/// Share of the window width given to the board; the side panel takes the rest.
+pub const BOARD_FRACTION: f32 = 0.8;
+
+/// Left edge of the side panel in NDC.
+pub const PANEL_LEFT_NDC: f32 = BOARD_FRACTION * 2.0 - 1.0;
+
+/// Screen geometry for one window size.
+///
+/// The board is a square anchored at the top-left corner of the window, as
+/// large as fits in its share of the width, so window pixels and board
+/// pixels share an origin. Everything that draws or hit-tests the board goes
+/// through here, which keeps rendering and click mapping in agreement at any
+/// aspect ratio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    width: f32,
+    height: f32,
+    board_size: f32,
+}
+
+impl Layout {
+    pub fn new(width: f32, height: f32) -> Self {
+        // A minimized window reports a zero size; keep the transforms finite
+        let width = width.max(1.0);
+        let height = height.max(1.0);
+
+        Self {
+            width,
+            height,
+            board_size: (width * BOARD_FRACTION).min(height),
+        }
+    }
+
+    pub fn from_size(size: PhysicalSize<u32>) -> Self {
+        Self::new(size.width as f32, size.height as f32)
+    }
+
+    /// Side length of the board in pixels.
+    pub fn board_size(&self) -> f32 {
+        self.board_size
+    }
+
+    pub fn square_size(&self) -> f32 {
+        self.board_size / 8.0
+    }
+
+    /// Converts window pixel coordinates to NDC.
+    pub fn pixel_to_ndc(&self, x: f32, y: f32) -> [f32; 2] {
+        [
+            (x / self.width) * 2.0 - 1.0,
+            1.0 - (y / self.height) * 2.0, // Flip Y
+        ]
+    }
+
+    /// Converts NDC to window pixel coordinates; the inverse of `pixel_to_ndc`.
+    pub fn ndc_to_pixel(&self, x: f32, y: f32) -> (f32, f32) {
+        ((x + 1.0) / 2.0 * self.width, (1.0 - y) / 2.0 * self.height)
+    }
+
+    /// Top-left corner of a square in pixels; rank 8 is at the top.
+    pub fn square_origin(&self, square: Square) -> (f32, f32) {
+        let col = square.file().index() as f32;
+        let row = 7.0 - square.rank().index() as f32;
+        (col * self.square_size(), row * self.square_size())
+    }
+
+    /// Center of a square in pixels.
+    pub fn square_center(&self, square: Square) -> (f32, f32) {
+        let (x, y) = self.square_origin(square);
+        let half = self.square_size() / 2.0;
+        (x + half, y + half)
+    }
+
+    /// The square under a pixel, or `None` outside the board.
+    pub fn square_at(&self, x: f32, y: f32) -> Option<Square> {
+        if x < 0.0 || x >= self.board_size || y < 0.0 || y >= self.board_size {
+            return None;
+        }
+
+        let col = (x / self.square_size()) as u8;
+        let row = (y / self.square_size()) as u8;
+        Some(Square::new(File::new(col)?, Rank::new(7 - row.min(7))?))
+    }
+
+    /// Top-left corner of promotion choice `index` (queen, rook, bishop,
+    /// knight), drawn as a strip of four squares centered on `to`.
+    pub fn promotion_choice_origin(&self, to: Square, index: usize) -> (f32, f32) {
+        let (x, y) = self.square_origin(to);
+        (x + (index as f32 - 1.5) * self.square_size(), y)
+    }
+
+    /// The promotion choice under a pixel, or `None` outside the strip.
+    pub fn promotion_choice_at(&self, to: Square, x: f32, y: f32) -> Option<usize> {
+        let (left, top) = self.promotion_choice_origin(to, 0);
+        if x < left || y < top || y >= top + self.square_size() {
+            return None;
+        }
+
+        let index = ((x - left) / self.square_size()) as usize;
+        (index < 4).then_some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+
+    #[test]
+    fn test_square_pixel_mapping() {
+        // Default window: the board fills the height and 80% of the width
+        let layout = Layout::new(1000.0, 800.0);
+        assert_eq!(layout.square_size(), 100.0);
+        assert_eq!(layout.square_at(50.0, 50.0), Some(square("a8")));
+        assert_eq!(layout.square_at(799.0, 799.0), Some(square("h1")));
+        assert_eq!(layout.square_at(850.0, 50.0), None);
+        assert_eq!(layout.square_center(square("e4")), (450.0, 450.0));
+        assert_eq!(layout.pixel_to_ndc(800.0, 800.0), [PANEL_LEFT_NDC, -1.0]);
+        assert_eq!(layout.ndc_to_pixel(PANEL_LEFT_NDC, -1.0), (800.0, 800.0));
+
+        // A tall window: the board stays square and leaves space below it
+        let layout = Layout::new(500.0, 1000.0);
+        assert_eq!(layout.board_size(), 400.0);
+        assert_eq!(layout.square_at(399.0, 399.0), Some(square("h1")));
+        assert_eq!(layout.square_at(10.0, 450.0), None);
+
+        for index in 0..64 {
+            let sq = Square::from_index(index).unwrap();
+            for layout in [Layout::new(1000.0, 800.0), Layout::new(1280.0, 600.0)] {
+                let (x, y) = layout.square_center(sq);
+                assert_eq!(layout.square_at(x, y), Some(sq));
+            }
+        }
+    }
+
+    #[test]
+    fn test_promotion_choices_agree_with_drawing() {
+        for layout in [
+            Layout::new(1000.0, 800.0),
+            Layout::new(1920.0, 1080.0),
+            Layout::new(600.0, 900.0),
+        ] {
+            let half = layout.square_size() / 2.0;
+            for to in [square("a8"), square("e8"), square("h1")] {
+                for index in 0..4 {
+                    let (x, y) = layout.promotion_choice_origin(to, index);
+                    assert_eq!(
+                        layout.promotion_choice_at(to, x + half, y + half),
+                        Some(index)
+                    );
+                }
+
+                // The strip is one square tall and four wide
+                let (x, y) = layout.square_origin(to);
+                assert_eq!(layout.promotion_choice_at(to, x + 1.0, y + 1.0), Some(1));
+                assert_eq!(layout.promotion_choice_at(to, x, y + half * 2.0), None);
+                assert_eq!(layout.promotion_choice_at(to, x + half * 6.0, y), None);
+            }
+        }
+    }
+}
@@ -1,24 +1,38 @@
+mod annotations;
 mod board;
+mod clock;
+mod layout;
+mod pieces;
 mod renderer;
+mod selection;
 mod sound;
 mod text_renderer;
+mod theme;
 
+use annotations::Annotations;
 use board::BoardRenderer;
-use chess_agents::{Agent, MinimaxAgent};
+use chess_agents::{mate_in, search_with_callback_and_stop, SearchLimits, SearchProgress};
 use chess_core::{
-    generate_legal_moves, is_checkmate, is_stalemate, Color, File, GameState, Move, PieceType,
-    Rank, Square,
+    generate_legal_moves, is_checkmate, is_stalemate, replay_from, BitBoard, Color, File,
+    GameState, Handicap, Move, Pgn, Piece, PieceType, Rank, Square,
 };
+use clock::{format_clock, Clock, TimeControl};
+use layout::{Layout, PANEL_LEFT_NDC};
+use pieces::{PieceRenderer, PieceSprite, PieceStyle};
 use renderer::{Renderer, Vertex};
+use selection::compute_valid_moves;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Receiver};
 use std::sync::Arc;
 use std::thread;
 use text_renderer::{TextRenderer, UiText};
+use theme::BoardTheme;
 use wgpu::util::DeviceExt;
 use winit::{
     dpi::PhysicalPosition,
-    event::{ElementState, Event, MouseButton, WindowEvent},
+    event::{ElementState, Event, KeyEvent, MouseButton, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
+    keyboard::{Key, NamedKey},
     window::{Window, WindowBuilder},
 };
 
@@ -27,6 +41,10 @@ struct ChessGUI {
     renderer: Renderer,
     board: BoardRenderer,
     text_renderer: Option<TextRenderer>,
+    /// Draws the pieces in `PieceStyle::Shapes`, or when `text_renderer`
+    /// is unavailable
+    piece_renderer: PieceRenderer,
+    piece_style: PieceStyle,
     game_state: GameState,
     mouse_position: PhysicalPosition<f64>,
     selected_square: Option<Square>,
@@ -38,13 +56,110 @@ struct ChessGUI {
     mode_selection_active: bool,
     difficulty_selection_active: bool,
     last_move: Option<Move>,
-    ai_move_receiver: Option<Receiver<Move>>,
+    ai_move_receiver: Option<Receiver<AiMessage>>,
+    // Stop flag of the AI search thread, set when its result is abandoned
+    ai_stop: Option<Arc<AtomicBool>>,
+    // Source and destination of the move the engine currently intends to play
+    engine_hint: Option<(Square, Square)>,
+    // Analysis mode: the background search's progress, its stop flag, and
+    // the latest completed iteration
+    analysis_receiver: Option<Receiver<SearchProgress>>,
+    analysis_stop: Option<Arc<AtomicBool>>,
+    analysis: Option<SearchProgress>,
+    // Pre-move queued while the AI thinks, and the piece picked for one
+    pending_premove: Option<Move>,
+    premove_from: Option<Square>,
+    // Right-click highlights and arrows, and the square a right drag began on
+    annotations: Annotations,
+    annotation_start: Option<Square>,
+    // Shown in place of the game status, e.g. when a dropped file fails to load
+    status_message: Option<String>,
+    theme: BoardTheme,
+    // Tint squares attacked by the side not to move (toggled with T)
+    show_threats: bool,
+    // Panel checkboxes: dots on the selected piece's legal destinations, and
+    // file and rank labels. Hidden moves are still the only ones accepted
+    show_legal_moves: bool,
+    show_coordinates: bool,
+    // Material odds given by the AI (or by Black in human games) at the start
+    handicap: Handicap,
+    // Time control picked on the mode selection screen, and its clock
+    time_control: TimeControl,
+    clock: Option<Clock>,
+    // Side that ran out of time, which ends the game
+    flag_fall: Option<Color>,
     animating_move: Option<AnimationState>,
+    /// Source square of the last rejected move and when it was rejected
+    rejected_move: Option<(Square, std::time::Instant)>,
     last_frame_time: std::time::Instant,
     sound_manager: Option<sound::SoundManager>,
-    // Undo/redo support
-    game_state_history: Vec<GameState>,
-    redo_stack: Vec<GameState>,
+    // Review support: the whole game line and which ply is on the board
+    start_state: GameState,
+    moves: Vec<Move>,
+    view_ply: usize,
+}
+
+/// Review navigation buttons shown in the side panel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NavAction {
+    Start,
+    Back,
+    Forward,
+    End,
+}
+
+// Navigation button layout in NDC (left edge of each button)
+const NAV_BUTTON_Y: f32 = 0.35; // Position between game mode and status
+const NAV_BUTTON_WIDTH: f32 = 0.07;
+const NAV_BUTTON_HEIGHT: f32 = 0.08;
+const NAV_BUTTONS: [(NavAction, f32); 4] = [
+    (NavAction::Start, 0.63),
+    (NavAction::Back, 0.72),
+    (NavAction::Forward, 0.81),
+    (NavAction::End, 0.90),
+];
+
+/// Display settings shown as checkboxes in the side panel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Toggle {
+    LegalMoves,
+    Coordinates,
+}
+
+// Checkbox layout in NDC, between the theme button and the first divider.
+// Each toggle is a box followed by its label, and the whole row is clickable
+const TOGGLE_Y: f32 = 0.54;
+const TOGGLE_WIDTH: f32 = 0.16;
+const TOGGLE_BOX_WIDTH: f32 = 0.035;
+const TOGGLE_BOX_HEIGHT: f32 = 0.05;
+const TOGGLES: [(Toggle, f32); 2] = [(Toggle::LegalMoves, 0.63), (Toggle::Coordinates, 0.81)];
+
+// Theme button layout in NDC, below the game mode text
+const THEME_BUTTON_Y: f32 = 0.62;
+const THEME_BUTTON_LEFT: f32 = 0.63;
+const THEME_BUTTON_RIGHT: f32 = 0.97;
+const THEME_BUTTON_HEIGHT: f32 = 0.08;
+// Handicap button shares the theme button's width and height
+const HANDICAP_BUTTON_Y: f32 = 0.74;
+// Piece style button, at the bottom of the panel below the move history
+const PIECE_STYLE_BUTTON_Y: f32 = -0.9;
+
+/// How long the source square flashes after an illegal destination click
+const REJECTION_FLASH: std::time::Duration = std::time::Duration::from_millis(400);
+
+// Time control button on the mode selection screen, centered horizontally
+const CLOCK_BUTTON_TOP: f32 = -0.3;
+const CLOCK_BUTTON_BOTTOM: f32 = -0.5;
+// Analysis mode button, below the time control button
+const ANALYSIS_BUTTON_TOP: f32 = -0.6;
+const ANALYSIS_BUTTON_BOTTOM: f32 = -0.8;
+
+/// Messages sent from the AI search thread to the GUI.
+enum AiMessage {
+    /// The first move of the principal variation after a completed iteration
+    Thinking(Move),
+    /// The final move chosen when the search ends
+    BestMove(Option<Move>),
 }
 
 struct AnimationState {
@@ -52,6 +167,8 @@ struct AnimationState {
     to: Square,
     piece: PieceType,
     color: Color,
+    // Captured piece and its square, faded out while the mover slides in
+    captured: Option<(Square, Piece)>,
     start_time: std::time::Instant,
     duration: std::time::Duration,
 }
@@ -61,6 +178,8 @@ struct AnimationState {
 enum GameMode {
     HumanVsHuman,
     HumanVsAI(Color, AIDifficulty), // AI plays this color with difficulty
+    // Humans move both sides while a background search shows the best move
+    Analysis,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -87,10 +206,13 @@ impl ChessGUI {
         );
 
         let renderer = Renderer::new(window.clone()).await;
-        let board = BoardRenderer::new(800.0);
+        let board = BoardRenderer::new(Layout::from_size(window.inner_size()));
         let game_state = GameState::new();
         let text_renderer =
             TextRenderer::new(&renderer.device, &renderer.queue, renderer.config.format);
+        if text_renderer.is_none() {
+            eprintln!("Text rendering unavailable; drawing pieces as shapes");
+        }
 
         // Initialize sound manager (optional - don't fail if audio isn't available)
         let sound_manager = sound::SoundManager::new().ok();
@@ -99,7 +221,9 @@ impl ChessGUI {
             window,
             renderer,
             board,
-            text_renderer: Some(text_renderer),
+            text_renderer,
+            piece_renderer: PieceRenderer::new(),
+            piece_style: PieceStyle::default(),
             game_state: game_state.clone(),
             mouse_position: PhysicalPosition::new(0.0, 0.0),
             selected_square: None,
@@ -112,13 +236,38 @@ impl ChessGUI {
             difficulty_selection_active: false,
             last_move: None,
             ai_move_receiver: None,
+            ai_stop: None,
+            engine_hint: None,
+            analysis_receiver: None,
+            analysis_stop: None,
+            analysis: None,
+            pending_premove: None,
+            premove_from: None,
+            annotations: Annotations::default(),
+            annotation_start: None,
+            status_message: None,
+            theme: BoardTheme::default(),
+            show_threats: false,
+            show_legal_moves: true,
+            show_coordinates: true,
+            handicap: Handicap::default(),
+            time_control: TimeControl::default(),
+            clock: None,
+            flag_fall: None,
             animating_move: None,
+            rejected_move: None,
             last_frame_time: std::time::Instant::now(),
             sound_manager,
-            game_state_history: vec![game_state],
-            redo_stack: Vec::new(),
+            start_state: game_state,
+            moves: Vec::new(),
+            view_ply: 0,
         }
     }
+
+    /// Geometry for the current window size.
+    fn layout(&self) -> Layout {
+        Layout::from_size(self.window.inner_size())
+    }
 }
 
 pub fn run() {
@@ -139,6 +288,8 @@ pub fn run() {
                     event: WindowEvent::Resized(physical_size),
                 } if window_id == app.window.id() => {
                     app.renderer.resize(physical_size);
+                    app.board.set_layout(Layout::from_size(physical_size));
+                    update_display(&mut app);
                 }
                 Event::WindowEvent {
                     window_id,
@@ -153,11 +304,62 @@ pub fn run() {
                 } if window_id == app.window.id() => {
                     render_frame(&mut app);
                 }
+                Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::DroppedFile(path),
+                } if window_id == app.window.id() => {
+                    handle_dropped_file(&mut app, &path);
+                }
                 Event::WindowEvent {
                     window_id,
                     event: WindowEvent::CursorMoved { position, .. },
                 } if window_id == app.window.id() => {
                     app.mouse_position = position;
+                    if app.annotation_start.is_some() {
+                        update_display(&mut app);
+                    }
+                }
+                Event::WindowEvent {
+                    window_id,
+                    event:
+                        WindowEvent::KeyboardInput {
+                            event:
+                                KeyEvent {
+                                    logical_key: Key::Named(key),
+                                    state: ElementState::Pressed,
+                                    ..
+                                },
+                            ..
+                        },
+                } if window_id == app.window.id() => {
+                    let action = match key {
+                        NamedKey::ArrowLeft => Some(NavAction::Back),
+                        NamedKey::ArrowRight => Some(NavAction::Forward),
+                        NamedKey::Home => Some(NavAction::Start),
+                        NamedKey::End => Some(NavAction::End),
+                        _ => None,
+                    };
+                    if let Some(action) = action {
+                        if !app.mode_selection_active && !app.difficulty_selection_active {
+                            handle_navigation(&mut app, action);
+                        }
+                    }
+                }
+                Event::WindowEvent {
+                    window_id,
+                    event:
+                        WindowEvent::KeyboardInput {
+                            event:
+                                KeyEvent {
+                                    logical_key: Key::Character(text),
+                                    state: ElementState::Pressed,
+                                    ..
+                                },
+                            ..
+                        },
+                } if window_id == app.window.id() && text.eq_ignore_ascii_case("t") => {
+                    app.show_threats = !app.show_threats;
+                    update_display(&mut app);
                 }
                 Event::WindowEvent {
                     window_id,
@@ -170,6 +372,17 @@ pub fn run() {
                 } if window_id == app.window.id() => {
                     handle_mouse_click(&mut app);
                 }
+                Event::WindowEvent {
+                    window_id,
+                    event:
+                        WindowEvent::MouseInput {
+                            state,
+                            button: MouseButton::Right,
+                            ..
+                        },
+                } if window_id == app.window.id() => {
+                    handle_right_button(&mut app, state);
+                }
                 Event::AboutToWait => {
                     // Update animation progress
                     let now = std::time::Instant::now();
@@ -180,55 +393,63 @@ pub fn run() {
                         if elapsed >= anim.duration {
                             // Animation complete
                             app.animating_move = None;
+                            // A pre-move waits for the AI's reply to land
+                            if app.pending_premove.is_some() && !app.ai_thinking {
+                                play_premove(&mut app, now);
+                            }
                             update_display(&mut app);
                         }
                     }
 
-                    // Check for AI move completion
-                    if let Some(receiver) = &app.ai_move_receiver {
-                        if let Ok(ai_move) = receiver.try_recv() {
-                            // Start animation for AI move
-                            if let Some(piece) = app.game_state.board.piece_at(ai_move.from) {
-                                app.animating_move = Some(AnimationState {
-                                    from: ai_move.from,
-                                    to: ai_move.to,
-                                    piece: piece.piece_type,
-                                    color: piece.color,
-                                    start_time: now,
-                                    duration: std::time::Duration::from_millis(300),
-                                });
-                            }
+                    if app
+                        .rejected_move
+                        .is_some_and(|(_, at)| now.duration_since(at) >= REJECTION_FLASH)
+                    {
+                        app.rejected_move = None;
+                        update_display(&mut app);
+                    }
 
-                            // Check if AI move is a capture
-                            let is_capture = app.game_state.board.piece_at(ai_move.to).is_some();
-
-                            // Apply AI move
-                            let move_notation = format_move(&app.game_state, ai_move);
-                            app.game_state = app.game_state.apply_move(ai_move);
-                            app.game_state_history.push(app.game_state.clone());
-                            app.redo_stack.clear(); // Clear redo stack on new move
-                            app.move_history.push(move_notation);
-                            app.last_move = Some(ai_move);
-                            app.ai_thinking = false;
-                            app.ai_move_receiver = None;
-
-                            // Play appropriate sound
-                            if let Some(sound_manager) = &app.sound_manager {
-                                if is_game_over(&app.game_state) {
-                                    sound_manager.play_game_over();
-                                } else if app.game_state.is_in_check() {
-                                    sound_manager.play_check();
-                                } else if is_capture {
-                                    sound_manager.play_capture();
-                                } else {
-                                    sound_manager.play_move();
+                    update_clock(&mut app, now);
+
+                    // Check for AI progress and move completion
+                    let messages: Vec<AiMessage> = app
+                        .ai_move_receiver
+                        .as_ref()
+                        .map(|receiver| receiver.try_iter().collect())
+                        .unwrap_or_default();
+                    for message in messages {
+                        match message {
+                            AiMessage::Thinking(mv) => {
+                                app.engine_hint = Some((mv.from, mv.to));
+                                update_display(&mut app);
+                            }
+                            AiMessage::BestMove(best_move) => {
+                                app.engine_hint = None;
+                                app.ai_thinking = false;
+                                app.ai_move_receiver = None;
+                                app.ai_stop = None;
+                                if let Some(ai_move) = best_move {
+                                    play_move(&mut app, ai_move, now);
+                                    app.premove_from = None;
+                                    if app.animating_move.is_none() {
+                                        play_premove(&mut app, now);
+                                    }
                                 }
+                                update_display(&mut app);
                             }
-
-                            update_display(&mut app);
                         }
                     }
 
+                    let progress = app
+                        .analysis_receiver
+                        .as_ref()
+                        .and_then(|receiver| receiver.try_iter().last());
+                    if let Some(progress) = progress {
+                        app.engine_hint = progress.pv.first().map(|mv| (mv.from, mv.to));
+                        app.analysis = Some(progress);
+                        update_display(&mut app);
+                    }
+
                     app.last_frame_time = now;
 
                     // Request a redraw if animating or before waiting
@@ -244,11 +465,46 @@ pub fn run() {
         .unwrap();
 }
 
+/// Deselects after a click on an illegal destination and flashes the
+/// source square, so the click reads as rejected rather than ignored.
+fn reject_move(app: &mut ChessGUI, from: Square) {
+    app.rejected_move = Some((from, std::time::Instant::now()));
+    app.selected_square = None;
+    app.valid_moves.clear();
+    update_display(app);
+}
+
 fn update_display(app: &mut ChessGUI) {
-    // Update board selection state
-    app.board
-        .set_selection(app.selected_square, app.valid_moves.clone());
+    // Update board selection state; `valid_moves` is kept either way, so
+    // hiding the dots does not change which clicks are accepted
+    let shown_moves = if app.show_legal_moves {
+        app.valid_moves.clone()
+    } else {
+        Vec::new()
+    };
+    app.board.set_selection(app.selected_square, shown_moves);
     app.board.set_last_move(app.last_move);
+    app.board
+        .set_rejected(app.rejected_move.map(|(square, _)| square));
+    app.board.set_arrow(app.engine_hint);
+    app.board.set_theme(app.theme);
+    app.board.set_premove(
+        app.premove_from
+            .into_iter()
+            .chain(
+                app.pending_premove
+                    .into_iter()
+                    .flat_map(|mv| [mv.from, mv.to]),
+            )
+            .collect(),
+    );
+    app.board.set_annotations(annotations_with_drag(app));
+    app.board.set_threats(if app.show_threats {
+        app.game_state
+            .attacked_squares(app.game_state.turn.opponent())
+    } else {
+        BitBoard::EMPTY
+    });
 
     // Update board vertices with highlights
     let mut all_vertices = app.board.generate_vertices().to_vec();
@@ -259,7 +515,7 @@ fn update_display(app: &mut ChessGUI) {
     all_vertices.extend_from_slice(&[
         // Panel background (right side) with gradient
         Vertex {
-            position: [0.6, -1.0],
+            position: [PANEL_LEFT_NDC, -1.0],
             color: panel_bg_color,
         },
         Vertex {
@@ -267,7 +523,7 @@ fn update_display(app: &mut ChessGUI) {
             color: panel_bg_color2,
         },
         Vertex {
-            position: [0.6, 1.0],
+            position: [PANEL_LEFT_NDC, 1.0],
             color: panel_bg_color,
         },
         Vertex {
@@ -279,96 +535,140 @@ fn update_display(app: &mut ChessGUI) {
             color: panel_bg_color2,
         },
         Vertex {
-            position: [0.6, 1.0],
+            position: [PANEL_LEFT_NDC, 1.0],
             color: panel_bg_color,
         },
     ]);
 
-    // Add undo/redo buttons
-    let button_y = 0.35; // Position between game mode and status
-    let button_width = 0.08;
-    let button_height = 0.08;
-    let button_spacing = 0.02;
+    // Add theme, handicap and piece style buttons
+    let button_color = [0.25, 0.25, 0.25, 1.0];
+    for button_y in [THEME_BUTTON_Y, HANDICAP_BUTTON_Y, PIECE_STYLE_BUTTON_Y] {
+        let top = button_y + THEME_BUTTON_HEIGHT / 2.0;
+        let bottom = button_y - THEME_BUTTON_HEIGHT / 2.0;
+        all_vertices.extend_from_slice(&[
+            Vertex {
+                position: [THEME_BUTTON_LEFT, bottom],
+                color: button_color,
+            },
+            Vertex {
+                position: [THEME_BUTTON_RIGHT, bottom],
+                color: button_color,
+            },
+            Vertex {
+                position: [THEME_BUTTON_LEFT, top],
+                color: button_color,
+            },
+            Vertex {
+                position: [THEME_BUTTON_RIGHT, bottom],
+                color: button_color,
+            },
+            Vertex {
+                position: [THEME_BUTTON_RIGHT, top],
+                color: button_color,
+            },
+            Vertex {
+                position: [THEME_BUTTON_LEFT, top],
+                color: button_color,
+            },
+        ]);
+    }
 
-    // Undo button
-    let undo_enabled = app.game_state_history.len() > 1 && !app.ai_thinking;
-    let undo_color = if undo_enabled {
-        [0.3, 0.5, 0.7, 1.0]
-    } else {
-        [0.2, 0.2, 0.2, 0.5]
-    };
-    let undo_x = 0.7;
-    all_vertices.extend_from_slice(&[
-        Vertex {
-            position: [undo_x, button_y - button_height / 2.0],
-            color: undo_color,
-        },
-        Vertex {
-            position: [undo_x + button_width, button_y - button_height / 2.0],
-            color: undo_color,
-        },
-        Vertex {
-            position: [undo_x, button_y + button_height / 2.0],
-            color: undo_color,
-        },
-        Vertex {
-            position: [undo_x + button_width, button_y - button_height / 2.0],
-            color: undo_color,
-        },
-        Vertex {
-            position: [undo_x + button_width, button_y + button_height / 2.0],
-            color: undo_color,
-        },
-        Vertex {
-            position: [undo_x, button_y + button_height / 2.0],
-            color: undo_color,
-        },
-    ]);
+    // Add review navigation buttons
+    for (action, button_x) in NAV_BUTTONS {
+        let color = if nav_enabled(app, action) {
+            [0.3, 0.5, 0.7, 1.0]
+        } else {
+            [0.2, 0.2, 0.2, 0.5]
+        };
+        let top = NAV_BUTTON_Y + NAV_BUTTON_HEIGHT / 2.0;
+        let bottom = NAV_BUTTON_Y - NAV_BUTTON_HEIGHT / 2.0;
+        let right = button_x + NAV_BUTTON_WIDTH;
+        all_vertices.extend_from_slice(&[
+            Vertex {
+                position: [button_x, bottom],
+                color,
+            },
+            Vertex {
+                position: [right, bottom],
+                color,
+            },
+            Vertex {
+                position: [button_x, top],
+                color,
+            },
+            Vertex {
+                position: [right, bottom],
+                color,
+            },
+            Vertex {
+                position: [right, top],
+                color,
+            },
+            Vertex {
+                position: [button_x, top],
+                color,
+            },
+        ]);
+    }
 
-    // Redo button
-    let redo_enabled = !app.redo_stack.is_empty() && !app.ai_thinking;
-    let redo_color = if redo_enabled {
-        [0.3, 0.5, 0.7, 1.0]
-    } else {
-        [0.2, 0.2, 0.2, 0.5]
-    };
-    let redo_x = undo_x + button_width + button_spacing;
-    all_vertices.extend_from_slice(&[
-        Vertex {
-            position: [redo_x, button_y - button_height / 2.0],
-            color: redo_color,
-        },
-        Vertex {
-            position: [redo_x + button_width, button_y - button_height / 2.0],
-            color: redo_color,
-        },
-        Vertex {
-            position: [redo_x, button_y + button_height / 2.0],
-            color: redo_color,
-        },
-        Vertex {
-            position: [redo_x + button_width, button_y - button_height / 2.0],
-            color: redo_color,
-        },
-        Vertex {
-            position: [redo_x + button_width, button_y + button_height / 2.0],
-            color: redo_color,
-        },
-        Vertex {
-            position: [redo_x, button_y + button_height / 2.0],
-            color: redo_color,
-        },
-    ]);
+    // Add display toggle checkboxes, filled when on
+    for (toggle, left) in TOGGLES {
+        let top = TOGGLE_Y + TOGGLE_BOX_HEIGHT / 2.0;
+        let bottom = TOGGLE_Y - TOGGLE_BOX_HEIGHT / 2.0;
+        let right = left + TOGGLE_BOX_WIDTH;
+        let (inset_x, inset_y) = (TOGGLE_BOX_WIDTH * 0.25, TOGGLE_BOX_HEIGHT * 0.25);
+        let mut quads = vec![([left, bottom, right, top], [0.25, 0.25, 0.25, 1.0])];
+        if toggle_enabled(app, toggle) {
+            quads.push((
+                [
+                    left + inset_x,
+                    bottom + inset_y,
+                    right - inset_x,
+                    top - inset_y,
+                ],
+                [0.3, 0.5, 0.7, 1.0],
+            ));
+        }
+        for ([x0, y0, x1, y1], color) in quads {
+            all_vertices.extend_from_slice(&[
+                Vertex {
+                    position: [x0, y0],
+                    color,
+                },
+                Vertex {
+                    position: [x1, y0],
+                    color,
+                },
+                Vertex {
+                    position: [x0, y1],
+                    color,
+                },
+                Vertex {
+                    position: [x1, y0],
+                    color,
+                },
+                Vertex {
+                    position: [x1, y1],
+                    color,
+                },
+                Vertex {
+                    position: [x0, y1],
+                    color,
+                },
+            ]);
+        }
+    }
 
     // Add section dividers
     let divider_color = [0.3, 0.3, 0.3, 1.0];
     let divider_y1 = 0.5; // Between game mode and status
     let divider_y2 = 0.2; // Between status and move history
+    let divider_left = PANEL_LEFT_NDC + 0.02;
 
     // First divider
     all_vertices.extend_from_slice(&[
         Vertex {
-            position: [0.62, divider_y1],
+            position: [divider_left, divider_y1],
             color: divider_color,
         },
         Vertex {
@@ -376,7 +676,7 @@ fn update_display(app: &mut ChessGUI) {
             color: divider_color,
         },
         Vertex {
-            position: [0.62, divider_y1 - 0.005],
+            position: [divider_left, divider_y1 - 0.005],
             color: divider_color,
         },
         Vertex {
@@ -388,7 +688,7 @@ fn update_display(app: &mut ChessGUI) {
             color: divider_color,
         },
         Vertex {
-            position: [0.62, divider_y1 - 0.005],
+            position: [divider_left, divider_y1 - 0.005],
             color: divider_color,
         },
     ]);
@@ -396,7 +696,7 @@ fn update_display(app: &mut ChessGUI) {
     // Second divider
     all_vertices.extend_from_slice(&[
         Vertex {
-            position: [0.62, divider_y2],
+            position: [divider_left, divider_y2],
             color: divider_color,
         },
         Vertex {
@@ -404,7 +704,7 @@ fn update_display(app: &mut ChessGUI) {
             color: divider_color,
         },
         Vertex {
-            position: [0.62, divider_y2 - 0.005],
+            position: [divider_left, divider_y2 - 0.005],
             color: divider_color,
         },
         Vertex {
@@ -416,7 +716,7 @@ fn update_display(app: &mut ChessGUI) {
             color: divider_color,
         },
         Vertex {
-            position: [0.62, divider_y2 - 0.005],
+            position: [divider_left, divider_y2 - 0.005],
             color: divider_color,
         },
     ]);
@@ -437,81 +737,90 @@ fn handle_mouse_click(app: &mut ChessGUI) {
         return;
     }
 
-    // Handle undo/redo button clicks
     let x = app.mouse_position.x as f32;
     let y = app.mouse_position.y as f32;
-    let window_size = app.window.inner_size();
-
-    // Convert to NDC
-    let ndc_x = (x / window_size.width as f32) * 2.0 - 1.0;
-    let ndc_y = 1.0 - (y / window_size.height as f32) * 2.0;
-
-    // Check if clicking on undo button
-    let button_y = 0.35;
-    let button_width = 0.08;
-    let button_height = 0.08;
-    let undo_x = 0.7;
-
-    if ndc_x >= undo_x
-        && ndc_x <= undo_x + button_width
-        && ndc_y >= button_y - button_height / 2.0
-        && ndc_y <= button_y + button_height / 2.0
+    let [ndc_x, ndc_y] = app.layout().pixel_to_ndc(x, y);
+
+    // A left-click on the board wipes the analysis annotations
+    if app.layout().square_at(x, y).is_some() && !app.annotations.is_empty() {
+        app.annotations.clear();
+        update_display(app);
+    }
+
+    // Handle review navigation button clicks
+    if (NAV_BUTTON_Y - NAV_BUTTON_HEIGHT / 2.0..=NAV_BUTTON_Y + NAV_BUTTON_HEIGHT / 2.0)
+        .contains(&ndc_y)
     {
-        if app.game_state_history.len() > 1 && !app.ai_thinking {
-            handle_undo(app);
-            return;
+        for (action, button_x) in NAV_BUTTONS {
+            if (button_x..=button_x + NAV_BUTTON_WIDTH).contains(&ndc_x) {
+                if nav_enabled(app, action) {
+                    handle_navigation(app, action);
+                }
+                return;
+            }
         }
     }
 
-    // Check if clicking on redo button
-    let redo_x = undo_x + button_width + 0.02;
-    if ndc_x >= redo_x
-        && ndc_x <= redo_x + button_width
-        && ndc_y >= button_y - button_height / 2.0
-        && ndc_y <= button_y + button_height / 2.0
-    {
-        if !app.redo_stack.is_empty() && !app.ai_thinking {
-            handle_redo(app);
-            return;
+    // Handle display toggle clicks
+    if (TOGGLE_Y - TOGGLE_BOX_HEIGHT / 2.0..=TOGGLE_Y + TOGGLE_BOX_HEIGHT / 2.0).contains(&ndc_y) {
+        for (toggle, left) in TOGGLES {
+            if (left..=left + TOGGLE_WIDTH).contains(&ndc_x) {
+                flip_toggle(app, toggle);
+                return;
+            }
         }
     }
 
+    // Check if clicking on the theme button
+    let theme_rows =
+        THEME_BUTTON_Y - THEME_BUTTON_HEIGHT / 2.0..=THEME_BUTTON_Y + THEME_BUTTON_HEIGHT / 2.0;
+    if (THEME_BUTTON_LEFT..=THEME_BUTTON_RIGHT).contains(&ndc_x) && theme_rows.contains(&ndc_y) {
+        app.theme = app.theme.next();
+        update_display(app);
+        return;
+    }
+
+    // Check if clicking on the handicap button
+    let handicap_rows = HANDICAP_BUTTON_Y - THEME_BUTTON_HEIGHT / 2.0
+        ..=HANDICAP_BUTTON_Y + THEME_BUTTON_HEIGHT / 2.0;
+    if (THEME_BUTTON_LEFT..=THEME_BUTTON_RIGHT).contains(&ndc_x) && handicap_rows.contains(&ndc_y) {
+        cycle_handicap(app);
+        return;
+    }
+
+    // Check if clicking on the piece style button
+    let piece_style_rows = PIECE_STYLE_BUTTON_Y - THEME_BUTTON_HEIGHT / 2.0
+        ..=PIECE_STYLE_BUTTON_Y + THEME_BUTTON_HEIGHT / 2.0;
+    if (THEME_BUTTON_LEFT..=THEME_BUTTON_RIGHT).contains(&ndc_x)
+        && piece_style_rows.contains(&ndc_y)
+    {
+        app.piece_style = app.piece_style.next();
+        update_display(app);
+        return;
+    }
+
     // Handle game over click
-    if is_game_over(&app.game_state) {
+    if is_game_over(app) {
         handle_game_over_click(app);
         return;
     }
 
-    // Don't allow moves if AI is thinking
+    // While the AI is thinking, clicks queue a pre-move instead
     if app.ai_thinking {
+        handle_premove_click(app);
+        return;
+    }
+    // A queued pre-move is about to be played
+    if app.pending_premove.is_some() {
         return;
     }
     // Handle promotion selection first
     if let Some(promo_state) = &app.promotion_pending {
-        let board_size = 800.0;
         let x = app.mouse_position.x as f32;
         let y = app.mouse_position.y as f32;
 
-        // Check if clicking on promotion selection area
-        // We'll show 4 pieces horizontally centered on the promotion square
-        let square_size = board_size / 8.0;
-        let promo_col = promo_state.to.file().index() as f32;
-        let promo_row = if promo_state.color == Color::White {
-            0.0
-        } else {
-            7.0
-        };
-
-        let promo_x = promo_col * square_size;
-        let promo_y = promo_row * square_size;
-
-        // Check if within the promotion selection area (4 squares wide)
-        if y >= promo_y
-            && y < promo_y + square_size
-            && x >= promo_x - 1.5 * square_size
-            && x < promo_x + 2.5 * square_size
-        {
-            let selection_index = ((x - (promo_x - 1.5 * square_size)) / square_size) as usize;
+        // Check if clicking on one of the 4 choices centered on the promotion square
+        if let Some(selection_index) = app.layout().promotion_choice_at(promo_state.to, x, y) {
             let promotion_piece = match selection_index {
                 0 => Some(PieceType::Queen),
                 1 => Some(PieceType::Rook),
@@ -523,34 +832,23 @@ fn handle_mouse_click(app: &mut ChessGUI) {
             if let Some(piece_type) = promotion_piece {
                 let promo_state = app.promotion_pending.take().unwrap();
 
-                // Start animation for promotion move
-                app.animating_move = Some(AnimationState {
-                    from: promo_state.from,
-                    to: promo_state.to,
-                    piece: piece_type, // Use the promoted piece type
-                    color: promo_state.color,
-                    start_time: std::time::Instant::now(),
-                    duration: std::time::Duration::from_millis(300),
-                });
-
                 let promotion_move =
                     chess_core::Move::new_promotion(promo_state.from, promo_state.to, piece_type);
 
+                // Start animation for promotion move
+                app.animating_move =
+                    move_animation(&app.game_state, promotion_move, std::time::Instant::now());
+
                 // Check if this is a capture promotion
-                let is_capture = app.game_state.board.piece_at(promo_state.to).is_some();
-
-                let move_notation = format_move(&app.game_state, promotion_move);
-                app.game_state = app.game_state.apply_move(promotion_move);
-                app.game_state_history.push(app.game_state.clone());
-                app.redo_stack.clear(); // Clear redo stack on new move
-                app.move_history.push(move_notation);
-                app.last_move = Some(promotion_move);
+                let is_capture = app.game_state.captured_piece(promotion_move).is_some();
+
+                record_move(app, promotion_move);
                 app.selected_square = None;
                 app.valid_moves.clear();
 
                 // Play appropriate sound
                 if let Some(sound_manager) = &app.sound_manager {
-                    if is_game_over(&app.game_state) {
+                    if is_game_over(app) {
                         sound_manager.play_game_over();
                     } else if app.game_state.is_in_check() {
                         sound_manager.play_check();
@@ -575,134 +873,100 @@ fn handle_mouse_click(app: &mut ChessGUI) {
     let y = app.mouse_position.y as f32;
 
     // Get the square under the mouse
-    if let Some((row, col)) = app.board.get_square_at(x, y) {
-        // Convert board row/col to chess square
-        // Note: board row 0 is at top, but chess rank 0 is at bottom
-        let rank = 7 - row;
-        if let (Some(file), Some(rank)) = (File::new(col as u8), Rank::new(rank as u8)) {
-            let clicked_square = Square::new(file, rank);
-
-            // If no piece selected yet
-            if app.selected_square.is_none() {
-                // Check if there's a piece at this square of the current player's color
-                if let Some(piece) = app.game_state.board.piece_at(clicked_square) {
-                    if piece.color == app.game_state.turn {
-                        // Select this piece
-                        app.selected_square = Some(clicked_square);
-                        // Generate legal moves for this piece
-                        // Generate legal moves for this piece
-                        let all_moves = generate_legal_moves(&app.game_state);
-                        app.valid_moves = all_moves
-                            .iter()
-                            .filter(|m| m.from == clicked_square)
-                            .copied()
-                            .collect();
-                        update_display(app);
-                    }
-                }
-            } else {
-                // We have a selected piece
-                let from_square = app.selected_square.unwrap();
-
-                // Check if clicking on the same square (deselect)
-                if clicked_square == from_square {
-                    app.selected_square = None;
-                    app.valid_moves.clear();
+    // Hit-test against the live window size, which may have changed since
+    // the board vertices were generated
+    if let Some(clicked_square) = app.layout().square_at(x, y) {
+        // If no piece selected yet
+        if app.selected_square.is_none() {
+            // Check if there's a piece at this square of the current player's color
+            if let Some(piece) = app.game_state.board.piece_at(clicked_square) {
+                if piece.color == app.game_state.turn {
+                    // Select this piece
+                    app.selected_square = Some(clicked_square);
+                    app.valid_moves = compute_valid_moves(&app.game_state, clicked_square);
                     update_display(app);
-                    return;
                 }
+            }
+        } else {
+            // We have a selected piece
+            let from_square = app.selected_square.unwrap();
 
-                // Check if this is a valid move
-                if let Some(chess_move) = app.valid_moves.iter().find(|m| m.to == clicked_square) {
-                    let chess_move = *chess_move;
-
-                    // Check if this is a pawn promotion move
-                    if let Some(piece) = app.game_state.board.piece_at(from_square) {
-                        if piece.piece_type == PieceType::Pawn {
-                            let promotion_rank = if piece.color == Color::White {
-                                Rank::EIGHTH
-                            } else {
-                                Rank::FIRST
-                            };
-                            if clicked_square.rank() == promotion_rank {
-                                // Show promotion selection
-                                app.promotion_pending = Some(PromotionState {
-                                    from: from_square,
-                                    to: clicked_square,
-                                    color: piece.color,
-                                });
-                                update_display(app);
-                                return;
-                            }
-                        }
-                    }
+            // Check if clicking on the same square (deselect)
+            if clicked_square == from_square {
+                app.selected_square = None;
+                app.valid_moves.clear();
+                update_display(app);
+                return;
+            }
 
-                    // Start animation for the move
-                    if let Some(piece) = app.game_state.board.piece_at(from_square) {
-                        app.animating_move = Some(AnimationState {
-                            from: from_square,
-                            to: clicked_square,
-                            piece: piece.piece_type,
-                            color: piece.color,
-                            start_time: std::time::Instant::now(),
-                            duration: std::time::Duration::from_millis(300),
-                        });
-                    }
+            // Check if this is a valid move
+            if let Some(chess_move) = app.valid_moves.iter().find(|m| m.to == clicked_square) {
+                let chess_move = *chess_move;
 
-                    // Check if this is a capture move before applying
-                    let is_capture = app.game_state.board.piece_at(clicked_square).is_some();
-
-                    // Apply the move
-                    let move_notation = format_move(&app.game_state, chess_move);
-                    app.game_state = app.game_state.apply_move(chess_move);
-                    app.game_state_history.push(app.game_state.clone());
-                    app.redo_stack.clear(); // Clear redo stack on new move
-                    app.move_history.push(move_notation);
-                    app.last_move = Some(chess_move);
-                    app.selected_square = None;
-                    app.valid_moves.clear();
-
-                    // Play appropriate sound
-                    if let Some(sound_manager) = &app.sound_manager {
-                        if is_game_over(&app.game_state) {
-                            sound_manager.play_game_over();
-                        } else if app.game_state.is_in_check() {
-                            sound_manager.play_check();
-                        } else if is_capture {
-                            sound_manager.play_capture();
+                // Check if this is a pawn promotion move
+                if let Some(piece) = app.game_state.board.piece_at(from_square) {
+                    if piece.piece_type == PieceType::Pawn {
+                        let promotion_rank = if piece.color == Color::White {
+                            Rank::EIGHTH
                         } else {
-                            sound_manager.play_move();
+                            Rank::FIRST
+                        };
+                        if clicked_square.rank() == promotion_rank {
+                            // Show promotion selection
+                            app.promotion_pending = Some(PromotionState {
+                                from: from_square,
+                                to: clicked_square,
+                                color: piece.color,
+                            });
+                            update_display(app);
+                            return;
                         }
                     }
+                }
 
-                    update_display(app);
+                // Start animation for the move
+                app.animating_move =
+                    move_animation(&app.game_state, chess_move, std::time::Instant::now());
 
-                    // Trigger AI move if applicable
-                    trigger_ai_move(app);
-                } else {
-                    // Check if selecting a different piece of the same color
-                    if let Some(piece) = app.game_state.board.piece_at(clicked_square) {
-                        if piece.color == app.game_state.turn {
-                            app.selected_square = Some(clicked_square);
-                            let all_moves = generate_legal_moves(&app.game_state);
-                            app.valid_moves = all_moves
-                                .iter()
-                                .filter(|m| m.from == clicked_square)
-                                .copied()
-                                .collect();
-                            update_display(app);
-                        } else {
-                            // Clicked on opponent piece, deselect
-                            app.selected_square = None;
-                            app.valid_moves.clear();
-                            update_display(app);
-                        }
+                // Check if this is a capture move before applying
+                let is_capture = app.game_state.captured_piece(chess_move).is_some();
+
+                // Apply the move
+                record_move(app, chess_move);
+                app.selected_square = None;
+                app.valid_moves.clear();
+
+                // Play appropriate sound
+                if let Some(sound_manager) = &app.sound_manager {
+                    if is_game_over(app) {
+                        sound_manager.play_game_over();
+                    } else if app.game_state.is_in_check() {
+                        sound_manager.play_check();
+                    } else if is_capture {
+                        sound_manager.play_capture();
                     } else {
-                        // Clicked on empty square that's not a valid move, deselect
-                        app.selected_square = None;
-                        app.valid_moves.clear();
+                        sound_manager.play_move();
+                    }
+                }
+
+                update_display(app);
+
+                // Trigger AI move if applicable
+                trigger_ai_move(app);
+            } else {
+                // Check if selecting a different piece of the same color
+                if let Some(piece) = app.game_state.board.piece_at(clicked_square) {
+                    if piece.color == app.game_state.turn {
+                        app.selected_square = Some(clicked_square);
+                        app.valid_moves = compute_valid_moves(&app.game_state, clicked_square);
                         update_display(app);
+                    } else {
+                        // Clicked on an opponent piece the selection cannot take
+                        reject_move(app, from_square);
                     }
+                } else {
+                    // Clicked on an empty square that's not a valid move
+                    reject_move(app, from_square);
                 }
             }
         }
@@ -725,6 +989,20 @@ fn render_frame(app: &mut ChessGUI) {
                 app.renderer.submit_frame(encoder, output);
                 return;
             }
+
+            // Shapes are drawn in the board pass, through the same layout;
+            // glyphs are drawn by the text renderer below
+            let pieces = piece_sprites(app, &app.layout());
+            let (shape_pieces, glyph_pieces) = if draws_shapes(app) {
+                (pieces.as_slice(), &[][..])
+            } else {
+                (&[][..], pieces.as_slice())
+            };
+            let vertices = app
+                .piece_renderer
+                .generate_vertices(shape_pieces, &app.layout());
+            app.renderer.update_overlay_vertices(vertices);
+
             // First render pass: render the board
             {
                 let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -755,84 +1033,28 @@ fn render_frame(app: &mut ChessGUI) {
             }
 
             // Second render pass: render pieces using text
+            let back_enabled = nav_enabled(app, NavAction::Back);
+            let forward_enabled = nav_enabled(app, NavAction::Forward);
+            let clock = clock_text(app);
             if let Some(text_renderer) = &mut app.text_renderer {
                 let window_size = app.window.inner_size();
-                let board_pixel_size =
-                    (window_size.width as f32 * 0.8).min(window_size.height as f32);
-                let square_size = board_pixel_size / 8.0;
-
-                // Collect all pieces to render
-                let mut pieces = Vec::new();
-                for rank in 0..8 {
-                    for file in 0..8 {
-                        if let (Some(f), Some(r)) = (File::new(file), Rank::new(rank)) {
-                            let square = Square::new(f, r);
-
-                            // Skip piece if it's being animated
-                            if let Some(anim) = &app.animating_move {
-                                if square == anim.from {
-                                    continue; // Don't render at original position
-                                }
-                                if square == anim.to && anim.start_time.elapsed() < anim.duration {
-                                    continue; // Don't render at destination yet
-                                }
-                            }
-
-                            if let Some(piece) = app.game_state.board.piece_at(square) {
-                                // Calculate piece position (center of square)
-                                // Note: rank 0 is at the bottom in chess, but top in screen coords
-                                let x = file as f32 * square_size + square_size / 2.0;
-                                let y = (7 - rank) as f32 * square_size + square_size / 2.0;
-
-                                // Convert to NDC (board takes left 80% of window)
-                                let board_width = 1.6; // 80% of NDC width
-                                let ndc_x = (x / board_pixel_size) * board_width - 1.0;
-                                let ndc_y = 1.0 - (y / board_pixel_size) * 2.0;
-
-                                pieces.push((piece.piece_type, piece.color, ndc_x, ndc_y));
-                            }
-                        }
-                    }
-                }
-
-                // Add animated piece if any
-                if let Some(anim) = &app.animating_move {
-                    let elapsed = anim.start_time.elapsed();
-                    if elapsed < anim.duration {
-                        let progress = elapsed.as_secs_f32() / anim.duration.as_secs_f32();
-                        let progress = progress.min(1.0);
-
-                        // Smooth easing function (ease-in-out)
-                        let t = if progress < 0.5 {
-                            2.0 * progress * progress
-                        } else {
-                            1.0 - (-2.0 * progress + 2.0).powi(2) / 2.0
-                        };
-
-                        // Calculate interpolated position
-                        let from_file = anim.from.file().index() as f32;
-                        let from_rank = anim.from.rank().index() as f32;
-                        let to_file = anim.to.file().index() as f32;
-                        let to_rank = anim.to.rank().index() as f32;
-
-                        let file_pos = from_file + (to_file - from_file) * t;
-                        let rank_pos = from_rank + (to_rank - from_rank) * t;
-
-                        let x = file_pos * square_size + square_size / 2.0;
-                        let y = (7.0 - rank_pos) * square_size + square_size / 2.0;
-
-                        // Convert to NDC
-                        let board_width = 1.6;
-                        let ndc_x = (x / board_pixel_size) * board_width - 1.0;
-                        let ndc_y = 1.0 - (y / board_pixel_size) * 2.0;
-
-                        pieces.push((anim.piece, anim.color, ndc_x, ndc_y));
-                    }
-                }
+                let layout = Layout::from_size(window_size);
+                let square_size = layout.square_size();
 
                 // Prepare UI text
-                let status_text = if app.ai_thinking {
+                let status_text = if let Some(message) = &app.status_message {
+                    message.clone()
+                } else if let Some(loser) = app.flag_fall {
+                    format!("{} wins on time", loser.opponent())
+                } else if app.ai_thinking {
                     "AI is thinking...".to_string()
+                } else if let Some(progress) = &app.analysis {
+                    format!(
+                        "{}  {} (depth {})",
+                        get_game_status_text(&app.game_state),
+                        format_eval(progress.score, &app.game_state),
+                        progress.depth
+                    )
                 } else {
                     get_game_status_text(&app.game_state)
                 };
@@ -846,17 +1068,23 @@ fn render_frame(app: &mut ChessGUI) {
                         GameMode::HumanVsAI(Color::Black, diff) => {
                             format!("Human vs AI ({:?})", diff)
                         }
+                        GameMode::Analysis => "Analysis".to_string(),
                     },
                     status: status_text,
                     move_history: app.move_history.clone(),
-                    undo_enabled: app.game_state_history.len() > 1 && !app.ai_thinking,
-                    redo_enabled: !app.redo_stack.is_empty() && !app.ai_thinking,
+                    theme: format!("Theme: {}", app.theme.name()),
+                    handicap: format!("Odds: {}", app.handicap.name()),
+                    piece_style: format!("Pieces: {}", app.piece_style.name()),
+                    clock,
+                    back_enabled,
+                    forward_enabled,
+                    show_coordinates: app.show_coordinates,
                 };
 
                 text_renderer.prepare_pieces(
                     &app.renderer.device,
                     &app.renderer.queue,
-                    &pieces,
+                    glyph_pieces,
                     square_size,
                     window_size.width as f32,
                     window_size.height as f32,
@@ -888,7 +1116,7 @@ fn render_frame(app: &mut ChessGUI) {
             }
 
             // Render game over overlay if game is finished
-            if is_game_over(&app.game_state) {
+            if is_game_over(app) {
                 render_game_over_overlay(app, &mut encoder, &view);
             }
 
@@ -900,64 +1128,128 @@ fn render_frame(app: &mut ChessGUI) {
     }
 }
 
+/// Whether pieces are drawn as vector shapes, by choice or because text
+/// rendering is unavailable.
+fn draws_shapes(app: &ChessGUI) -> bool {
+    app.piece_style == PieceStyle::Shapes || app.text_renderer.is_none()
+}
+
+/// Every piece on the board at its NDC center, with the moving piece of an
+/// animation part way along and the piece it captures fading out.
+fn piece_sprites(app: &ChessGUI, layout: &Layout) -> Vec<PieceSprite> {
+    let square_size = layout.square_size();
+
+    let mut pieces = Vec::new();
+    for rank in 0..8 {
+        for file in 0..8 {
+            if let (Some(f), Some(r)) = (File::new(file), Rank::new(rank)) {
+                let square = Square::new(f, r);
+
+                // Skip piece if it's being animated
+                if let Some(anim) = &app.animating_move {
+                    if square == anim.from {
+                        continue; // Don't render at original position
+                    }
+                    if square == anim.to && anim.start_time.elapsed() < anim.duration {
+                        continue; // Don't render at destination yet
+                    }
+                }
+
+                if let Some(piece) = app.game_state.board.piece_at(square) {
+                    let (x, y) = layout.square_center(square);
+                    let [ndc_x, ndc_y] = layout.pixel_to_ndc(x, y);
+
+                    pieces.push((piece.piece_type, piece.color, ndc_x, ndc_y, 1.0));
+                }
+            }
+        }
+    }
+
+    // Add animated piece if any
+    if let Some(anim) = &app.animating_move {
+        let elapsed = anim.start_time.elapsed();
+        if elapsed < anim.duration {
+            let progress = elapsed.as_secs_f32() / anim.duration.as_secs_f32();
+            let progress = progress.min(1.0);
+
+            // Smooth easing function (ease-in-out)
+            let t = if progress < 0.5 {
+                2.0 * progress * progress
+            } else {
+                1.0 - (-2.0 * progress + 2.0).powi(2) / 2.0
+            };
+
+            // Calculate interpolated position
+            let from_file = anim.from.file().index() as f32;
+            let from_rank = anim.from.rank().index() as f32;
+            let to_file = anim.to.file().index() as f32;
+            let to_rank = anim.to.rank().index() as f32;
+
+            let file_pos = from_file + (to_file - from_file) * t;
+            let rank_pos = from_rank + (to_rank - from_rank) * t;
+
+            let x = file_pos * square_size + square_size / 2.0;
+            let y = (7.0 - rank_pos) * square_size + square_size / 2.0;
+
+            let [ndc_x, ndc_y] = layout.pixel_to_ndc(x, y);
+
+            // The captured piece fades out on its own square,
+            // which differs from the destination for en passant
+            if let Some((square, captured)) = anim.captured {
+                let (x, y) = layout.square_center(square);
+                let [ndc_x, ndc_y] = layout.pixel_to_ndc(x, y);
+                pieces.push((
+                    captured.piece_type,
+                    captured.color,
+                    ndc_x,
+                    ndc_y,
+                    1.0 - progress,
+                ));
+            }
+
+            pieces.push((anim.piece, anim.color, ndc_x, ndc_y, 1.0));
+        }
+    }
+
+    pieces
+}
+
 fn render_promotion_selection(
     app: &mut ChessGUI,
     encoder: &mut wgpu::CommandEncoder,
     view: &wgpu::TextureView,
 ) {
     let promo_state = app.promotion_pending.as_ref().unwrap();
-    let window_size = app.window.inner_size();
-    let board_pixel_size = (window_size.width as f32 * 0.8).min(window_size.height as f32);
-    let square_size = board_pixel_size / 8.0;
+    let layout = app.layout();
+    let square_size = layout.square_size();
 
     // Generate vertices for promotion overlay background
     let mut vertices = Vec::new();
 
     // Dark overlay over board area only
-    vertices.extend_from_slice(&[
-        Vertex {
-            position: [-1.0, -1.0],
-            color: [0.0, 0.0, 0.0, 0.7],
-        },
-        Vertex {
-            position: [0.6, -1.0],
-            color: [0.0, 0.0, 0.0, 0.7],
-        },
-        Vertex {
-            position: [-1.0, 1.0],
-            color: [0.0, 0.0, 0.0, 0.7],
-        },
-        Vertex {
-            position: [0.6, -1.0],
-            color: [0.0, 0.0, 0.0, 0.7],
-        },
-        Vertex {
-            position: [0.6, 1.0],
-            color: [0.0, 0.0, 0.0, 0.7],
-        },
-        Vertex {
-            position: [-1.0, 1.0],
-            color: [0.0, 0.0, 0.0, 0.7],
-        },
-    ]);
+    let [left, top] = layout.pixel_to_ndc(0.0, 0.0);
+    let [right, bottom] = layout.pixel_to_ndc(layout.board_size(), layout.board_size());
+    let shade = [0.0, 0.0, 0.0, 0.7];
+    for position in [
+        [left, bottom],
+        [right, bottom],
+        [left, top],
+        [right, bottom],
+        [right, top],
+        [left, top],
+    ] {
+        vertices.push(Vertex {
+            position,
+            color: shade,
+        });
+    }
 
     // Light background for promotion choices
-    let promo_col = promo_state.to.file().index() as f32;
-    let promo_row = if promo_state.color == Color::White {
-        0.0
-    } else {
-        7.0
-    };
-
     for i in 0..4 {
-        let x = (promo_col - 1.5 + i as f32) * square_size;
-        let y = promo_row * square_size;
+        let (x, y) = layout.promotion_choice_origin(promo_state.to, i);
 
-        let board_width = 1.6; // 80% of NDC width
-        let ndc_x = (x / board_pixel_size) * board_width - 1.0;
-        let ndc_y = 1.0 - (y / board_pixel_size) * 2.0;
-        let ndc_x2 = ((x + square_size) / board_pixel_size) * board_width - 1.0;
-        let ndc_y2 = 1.0 - ((y + square_size) / board_pixel_size) * 2.0;
+        let [ndc_x, ndc_y] = layout.pixel_to_ndc(x, y);
+        let [ndc_x2, ndc_y2] = layout.pixel_to_ndc(x + square_size, y + square_size);
 
         let color = [0.9, 0.9, 0.9, 1.0];
 
@@ -989,6 +1281,30 @@ fn render_promotion_selection(
         ]);
     }
 
+    let choices = [
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+    ];
+    let mut piece_positions = Vec::new();
+    for (i, piece_type) in choices.into_iter().enumerate() {
+        let (x, y) = layout.promotion_choice_origin(promo_state.to, i);
+        let (x, y) = (x + square_size / 2.0, y + square_size / 2.0);
+
+        let [ndc_x, ndc_y] = layout.pixel_to_ndc(x, y);
+
+        piece_positions.push((piece_type, promo_state.color, ndc_x, ndc_y, 1.0));
+    }
+
+    let shapes = draws_shapes(app);
+    if shapes {
+        vertices.extend_from_slice(
+            app.piece_renderer
+                .generate_vertices(&piece_positions, &layout),
+        );
+    }
+
     // Create a temporary vertex buffer for the overlay
     let overlay_buffer =
         app.renderer
@@ -1022,26 +1338,8 @@ fn render_promotion_selection(
     }
 
     // Render promotion piece choices using text renderer
-    if let Some(text_renderer) = &mut app.text_renderer {
+    if let Some(text_renderer) = app.text_renderer.as_mut().filter(|_| !shapes) {
         let window_size = app.window.inner_size();
-        let pieces = [
-            (PieceType::Queen, promo_state.color),
-            (PieceType::Rook, promo_state.color),
-            (PieceType::Bishop, promo_state.color),
-            (PieceType::Knight, promo_state.color),
-        ];
-
-        let mut piece_positions = Vec::new();
-        for (i, (piece_type, color)) in pieces.iter().enumerate() {
-            let x = (promo_col - 1.5 + i as f32) * square_size + square_size / 2.0;
-            let y = promo_row * square_size + square_size / 2.0;
-
-            let board_width = 1.6; // 80% of NDC width
-            let ndc_x = (x / board_pixel_size) * board_width - 1.0;
-            let ndc_y = 1.0 - (y / board_pixel_size) * 2.0;
-
-            piece_positions.push((*piece_type, *color, ndc_x, ndc_y));
-        }
 
         text_renderer.prepare_pieces(
             &app.renderer.device,
@@ -1054,8 +1352,13 @@ fn render_promotion_selection(
                 game_mode: String::new(),
                 status: String::new(),
                 move_history: Vec::new(),
-                undo_enabled: false,
-                redo_enabled: false,
+                theme: String::new(),
+                handicap: String::new(),
+                piece_style: String::new(),
+                clock: String::new(),
+                back_enabled: false,
+                forward_enabled: false,
+                show_coordinates: false,
             }, // No UI text during promotion
         );
 
@@ -1132,11 +1435,7 @@ fn format_move(game_state: &GameState, chess_move: chess_core::Move) -> String {
 fn handle_mode_selection_click(app: &mut ChessGUI) {
     let x = app.mouse_position.x as f32;
     let y = app.mouse_position.y as f32;
-    let window_size = app.window.inner_size();
-
-    // Convert to NDC
-    let ndc_x = (x / window_size.width as f32) * 2.0 - 1.0;
-    let ndc_y = 1.0 - (y / window_size.height as f32) * 2.0;
+    let [ndc_x, ndc_y] = app.layout().pixel_to_ndc(x, y);
 
     // Check if clicking on one of the mode buttons
     // Buttons are centered at Y = 0.0
@@ -1145,79 +1444,376 @@ fn handle_mode_selection_click(app: &mut ChessGUI) {
             // Human vs Human
             app.game_mode = GameMode::HumanVsHuman;
             app.mode_selection_active = false;
-            update_display(app);
+            start_game(app);
         } else if ndc_x >= 0.1 && ndc_x <= 0.5 {
             // Human vs AI - show difficulty selection
             app.mode_selection_active = false;
             app.difficulty_selection_active = true;
             update_display(app);
         }
+    } else if (CLOCK_BUTTON_BOTTOM..=CLOCK_BUTTON_TOP).contains(&ndc_y)
+        && (-0.3..=0.3).contains(&ndc_x)
+    {
+        app.time_control = app.time_control.next();
+        app.clock = new_clock(app);
+    } else if (ANALYSIS_BUTTON_BOTTOM..=ANALYSIS_BUTTON_TOP).contains(&ndc_y)
+        && (-0.3..=0.3).contains(&ndc_x)
+    {
+        app.game_mode = GameMode::Analysis;
+        app.mode_selection_active = false;
+        app.clock = None;
+        start_analysis(app);
+        update_display(app);
+    }
+}
+
+/// Slide animation for `mv` from `state`, showing a promotion as the new
+/// piece and fading out any captured piece.
+fn move_animation(
+    state: &GameState,
+    mv: Move,
+    start_time: std::time::Instant,
+) -> Option<AnimationState> {
+    let piece = state.board.piece_at(mv.from)?;
+    Some(AnimationState {
+        from: mv.from,
+        to: mv.to,
+        piece: mv.promotion.unwrap_or(piece.piece_type),
+        color: piece.color,
+        captured: state.captured_piece(mv),
+        start_time,
+        duration: std::time::Duration::from_millis(300),
+    })
+}
+
+/// Animates, records and sounds a move that did not come from clicking the
+/// board: the AI's reply or a queued pre-move.
+fn play_move(app: &mut ChessGUI, mv: Move, now: std::time::Instant) {
+    // Start animation for the move
+    app.animating_move = move_animation(&app.game_state, mv, now);
+
+    // Check if the move is a capture
+    let is_capture = app.game_state.captured_piece(mv).is_some();
+
+    record_move(app, mv);
+
+    // Play appropriate sound
+    if let Some(sound_manager) = &app.sound_manager {
+        if is_game_over(app) {
+            sound_manager.play_game_over();
+        } else if app.game_state.is_in_check() {
+            sound_manager.play_check();
+        } else if is_capture {
+            sound_manager.play_capture();
+        } else {
+            sound_manager.play_move();
+        }
+    }
+}
+
+/// Picks a piece and then a target square for a pre-move. Any target is
+/// accepted, since the AI's reply may make it legal (a recapture, say);
+/// legality is checked when the pre-move is played.
+fn handle_premove_click(app: &mut ChessGUI) {
+    let x = app.mouse_position.x as f32;
+    let y = app.mouse_position.y as f32;
+    let Some(square) = app.layout().square_at(x, y) else {
+        return;
+    };
+
+    let human = app.game_state.turn.opponent();
+    match app.premove_from {
+        Some(from) if from == square => app.premove_from = None,
+        Some(from) => {
+            app.pending_premove = Some(Move::new(from, square));
+            app.premove_from = None;
+        }
+        None => {
+            if app
+                .game_state
+                .board
+                .piece_at(square)
+                .is_some_and(|piece| piece.color == human)
+            {
+                app.premove_from = Some(square);
+                app.pending_premove = None;
+            }
+        }
+    }
+    update_display(app);
+}
+
+/// Right-button press starts an annotation gesture (and cancels any
+/// pre-move); release finishes it on the square under the cursor.
+fn handle_right_button(app: &mut ChessGUI, state: ElementState) {
+    if app.mode_selection_active || app.difficulty_selection_active {
+        return;
+    }
+
+    let x = app.mouse_position.x as f32;
+    let y = app.mouse_position.y as f32;
+    let square = app.layout().square_at(x, y);
+    match state {
+        ElementState::Pressed => {
+            cancel_premove(app);
+            app.annotation_start = square;
+        }
+        ElementState::Released => {
+            if let (Some(from), Some(to)) = (app.annotation_start.take(), square) {
+                app.annotations.toggle(from, to);
+            }
+            update_display(app);
+        }
+    }
+}
+
+/// The drawn annotations plus a preview of the arrow being dragged.
+fn annotations_with_drag(app: &ChessGUI) -> Annotations {
+    let mut annotations = app.annotations.clone();
+    let x = app.mouse_position.x as f32;
+    let y = app.mouse_position.y as f32;
+    if let (Some(from), Some(to)) = (app.annotation_start, app.layout().square_at(x, y)) {
+        if from != to && !annotations.arrows().contains(&(from, to)) {
+            annotations.toggle(from, to);
+        }
+    }
+    annotations
+}
+
+fn cancel_premove(app: &mut ChessGUI) {
+    if app.pending_premove.is_some() || app.premove_from.is_some() {
+        app.pending_premove = None;
+        app.premove_from = None;
+        update_display(app);
+    }
+}
+
+/// Plays the queued pre-move once the AI's reply has finished animating, if
+/// it is legal in the new position, promoting to a queen; otherwise it is
+/// discarded.
+fn play_premove(app: &mut ChessGUI, now: std::time::Instant) {
+    app.premove_from = None;
+    let Some(premove) = app.pending_premove.take() else {
+        return;
+    };
+    if is_game_over(app) {
+        return;
+    }
+
+    let legal = generate_legal_moves(&app.game_state)
+        .iter()
+        .copied()
+        .find(|mv| {
+            mv.from == premove.from
+                && mv.to == premove.to
+                && matches!(mv.promotion, None | Some(PieceType::Queen))
+        });
+    if let Some(mv) = legal {
+        play_move(app, mv, now);
+        trigger_ai_move(app);
     }
 }
 
 fn trigger_ai_move(app: &mut ChessGUI) {
     if let GameMode::HumanVsAI(ai_color, difficulty) = app.game_mode {
-        if app.game_state.turn == ai_color && !is_game_over(&app.game_state) {
+        if app.game_state.turn == ai_color && !is_game_over(app) {
+            stop_ai(app);
             app.ai_thinking = true;
             update_display(app);
 
+            // Timed games budget from the remaining clock; otherwise the
+            // difficulty sets a flat move time
+            let limits = match &app.clock {
+                Some(clock) => {
+                    let now = std::time::Instant::now();
+                    SearchLimits::time_control(
+                        clock.remaining(Color::White, now),
+                        clock.remaining(Color::Black, now),
+                        clock.increment(),
+                        clock.increment(),
+                        None,
+                    )
+                }
+                None => SearchLimits::move_time(match difficulty {
+                    AIDifficulty::Easy => 100,
+                    AIDifficulty::Medium => 500,
+                    AIDifficulty::Hard => 2000,
+                }),
+            };
+
             // Clone the game state for the AI thread
             let game_state = app.game_state.clone();
+            let stop = Arc::new(AtomicBool::new(false));
             let (tx, rx) = channel();
             app.ai_move_receiver = Some(rx);
+            app.ai_stop = Some(Arc::clone(&stop));
 
             // Spawn thread for AI computation
             thread::spawn(move || {
-                let time_limit = match difficulty {
-                    AIDifficulty::Easy => 100,
-                    AIDifficulty::Medium => 500,
-                    AIDifficulty::Hard => 2000,
-                };
-                let mut ai_agent = MinimaxAgent::with_time_limit(time_limit);
-                if let Some(ai_move) = ai_agent.best_move(&game_state) {
-                    let _ = tx.send(ai_move);
-                }
+                // Report the first PV move of each iteration so the board can
+                // show where the engine is heading
+                let progress_tx = tx.clone();
+                let callback = Box::new(move |progress: &SearchProgress| {
+                    if let Some(&mv) = progress.pv.first() {
+                        let _ = progress_tx.send(AiMessage::Thinking(mv));
+                    }
+                });
+                let result = search_with_callback_and_stop(&game_state, limits, callback, stop);
+                let _ = tx.send(AiMessage::BestMove(result.best_move));
             });
         }
     }
 }
 
-fn is_game_over(game_state: &GameState) -> bool {
-    is_checkmate(game_state)
-        || is_stalemate(game_state)
+/// Cancels the AI search, if any. Its receiver is dropped with it, so the
+/// move an aborted search reports is never played.
+fn stop_ai(app: &mut ChessGUI) {
+    if let Some(stop) = app.ai_stop.take() {
+        stop.store(true, Ordering::Relaxed);
+    }
+    app.ai_thinking = false;
+    app.ai_move_receiver = None;
+}
+
+/// Restarts the analysis-mode search on the current position, cancelling
+/// the previous one. Nothing runs outside analysis mode or once the game is
+/// over. The old search thread is not joined: it notices its stop flag
+/// within a few thousand nodes, and its sender is simply dropped.
+fn start_analysis(app: &mut ChessGUI) {
+    stop_analysis(app);
+    if app.game_mode != GameMode::Analysis || is_game_over(app) {
+        return;
+    }
+
+    let game_state = app.game_state.clone();
+    let stop = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = channel();
+    app.analysis_receiver = Some(rx);
+    app.analysis_stop = Some(Arc::clone(&stop));
+
+    thread::spawn(move || {
+        let callback = Box::new(move |progress: &SearchProgress| {
+            let _ = tx.send(progress.clone());
+        });
+        search_with_callback_and_stop(&game_state, SearchLimits::infinite(), callback, stop);
+    });
+}
+
+/// Cancels the analysis search, if any, and clears what it showed.
+fn stop_analysis(app: &mut ChessGUI) {
+    if let Some(stop) = app.analysis_stop.take() {
+        stop.store(true, Ordering::Relaxed);
+        app.engine_hint = None;
+    }
+    app.analysis_receiver = None;
+    app.analysis = None;
+}
+
+/// Formats a side-to-move score from White's perspective, in pawns, with
+/// mates shown as `#N` (negative when Black mates).
+fn format_eval(score: i32, state: &GameState) -> String {
+    let sign = if state.turn == Color::White { 1 } else { -1 };
+    match mate_in(score, state) {
+        Some(moves) => format!("#{}", sign * moves),
+        None => format!("{:+.2}", f64::from(sign * score) / 100.0),
+    }
+}
+
+fn is_game_over(app: &ChessGUI) -> bool {
+    let game_state = &app.game_state;
+    app.flag_fall.is_some()
+        || !game_state.has_legal_move()
         || game_state.is_fifty_move_draw()
         || game_state.is_insufficient_material()
 }
 
+/// Runs the clock of the side to move. It is paused while a selection
+/// screen or promotion choice is up or an earlier position is being
+/// reviewed, and stops at game over. Running out of time loses the game.
+fn update_clock(app: &mut ChessGUI, now: std::time::Instant) {
+    let paused = app.mode_selection_active
+        || app.difficulty_selection_active
+        || app.promotion_pending.is_some()
+        || app.view_ply < app.moves.len()
+        || is_game_over(app);
+    let turn = app.game_state.turn;
+    let Some(clock) = &mut app.clock else {
+        return;
+    };
+
+    if paused {
+        clock.pause(now);
+        return;
+    }
+    if clock.running() != Some(turn) {
+        clock.start(turn, now);
+    }
+
+    let Some(loser) = clock.flagged(now) else {
+        return;
+    };
+    clock.pause(now);
+    app.flag_fall = Some(loser);
+
+    // A search still running for the flagged side is abandoned
+    stop_ai(app);
+    app.engine_hint = None;
+    app.pending_premove = None;
+    app.premove_from = None;
+    app.selected_square = None;
+    app.valid_moves.clear();
+    if let Some(sound_manager) = &app.sound_manager {
+        sound_manager.play_game_over();
+    }
+    update_display(app);
+}
+
+/// Both clocks for the side panel, or an empty string in untimed games.
+fn clock_text(app: &ChessGUI) -> String {
+    let Some(clock) = &app.clock else {
+        return String::new();
+    };
+    let now = std::time::Instant::now();
+    format!(
+        "White {}  Black {}",
+        format_clock(clock.remaining(Color::White, now)),
+        format_clock(clock.remaining(Color::Black, now))
+    )
+}
+
 fn handle_game_over_click(app: &mut ChessGUI) {
     let x = app.mouse_position.x as f32;
     let y = app.mouse_position.y as f32;
-    let window_size = app.window.inner_size();
-
-    // Convert to NDC
-    let ndc_x = (x / window_size.width as f32) * 2.0 - 1.0;
-    let ndc_y = 1.0 - (y / window_size.height as f32) * 2.0;
+    let [ndc_x, ndc_y] = app.layout().pixel_to_ndc(x, y);
 
     // Check if clicking on the new game button
     // Button is centered at Y = -0.2
     if ndc_y >= -0.35 && ndc_y <= -0.05 && ndc_x >= -0.2 && ndc_x <= 0.2 {
         // Reset the game
-        app.game_state = GameState::new();
-        app.game_state_history = vec![app.game_state.clone()];
-        app.redo_stack.clear();
+        app.game_state = starting_position(app);
+        app.start_state = app.game_state.clone();
+        app.moves.clear();
+        app.view_ply = 0;
         app.selected_square = None;
         app.valid_moves.clear();
         app.promotion_pending = None;
         app.move_history.clear();
-        app.ai_thinking = false;
+        stop_ai(app);
         app.last_move = None;
-        app.ai_move_receiver = None;
+        app.engine_hint = None;
+        app.status_message = None;
         app.animating_move = None;
+        app.clock = new_clock(app);
+        app.flag_fall = None;
+        app.pending_premove = None;
+        app.premove_from = None;
+        app.annotations.clear();
 
         // If playing against AI and AI plays white, trigger AI move
         if let GameMode::HumanVsAI(Color::White, _) = app.game_mode {
             trigger_ai_move(app);
         }
+        start_analysis(app);
 
         update_display(app);
     }
@@ -1353,7 +1949,9 @@ fn render_game_over_overlay(
         let window_size = app.window.inner_size();
 
         // Get game result text
-        let result_text = if is_checkmate(&app.game_state) {
+        let result_text = if let Some(loser) = app.flag_fall {
+            format!("{} wins on time", loser.opponent())
+        } else if is_checkmate(&app.game_state) {
             format!("{} wins by checkmate!", app.game_state.turn.opponent())
         } else if is_stalemate(&app.game_state) {
             "Stalemate - Draw".to_string()
@@ -1392,90 +1990,208 @@ fn render_game_over_overlay(
     }
 }
 
+/// Side that gives up material: the AI, or Black when two humans play.
+fn handicap_giver(app: &ChessGUI) -> Color {
+    match app.game_mode {
+        GameMode::HumanVsAI(ai_color, _) => ai_color,
+        GameMode::HumanVsHuman | GameMode::Analysis => Color::Black,
+    }
+}
+
+/// A fresh clock for the chosen time control, with any time odds taken from
+/// the handicap giver; analysis is never timed.
+fn new_clock(app: &ChessGUI) -> Option<Clock> {
+    match app.game_mode {
+        GameMode::Analysis => None,
+        _ => app
+            .time_control
+            .clock()
+            .map(|clock| clock.with_odds(handicap_giver(app), app.handicap.clock_divisor())),
+    }
+}
+
+fn starting_position(app: &ChessGUI) -> GameState {
+    app.handicap.starting_position(handicap_giver(app))
+}
+
+/// Selects the next handicap. It takes effect at once if no move has been
+/// played yet, otherwise from the next game.
+fn cycle_handicap(app: &mut ChessGUI) {
+    app.handicap = app.handicap.next();
+
+    if app.moves.is_empty() && !app.ai_thinking {
+        app.start_state = starting_position(app);
+        app.clock = new_clock(app);
+        view_ply(app, 0);
+    } else {
+        app.status_message = Some(format!(
+            "{} odds apply from the next game",
+            app.handicap.name()
+        ));
+        update_display(app);
+    }
+}
+
 fn handle_difficulty_selection_click(app: &mut ChessGUI) {
     let x = app.mouse_position.x as f32;
     let y = app.mouse_position.y as f32;
-    let window_size = app.window.inner_size();
-
-    // Convert to NDC
-    let ndc_x = (x / window_size.width as f32) * 2.0 - 1.0;
-    let ndc_y = 1.0 - (y / window_size.height as f32) * 2.0;
+    let [ndc_x, ndc_y] = app.layout().pixel_to_ndc(x, y);
 
     // Check if clicking on one of the difficulty buttons
     // Buttons are centered at Y = 0.0
     if ndc_y >= -0.15 && ndc_y <= 0.15 {
-        if ndc_x >= -0.6 && ndc_x <= -0.2 {
-            // Easy
-            app.game_mode = GameMode::HumanVsAI(Color::Black, AIDifficulty::Easy);
-            app.difficulty_selection_active = false;
-            update_display(app);
+        let difficulty = if ndc_x >= -0.6 && ndc_x <= -0.2 {
+            AIDifficulty::Easy
         } else if ndc_x >= -0.2 && ndc_x <= 0.2 {
-            // Medium
-            app.game_mode = GameMode::HumanVsAI(Color::Black, AIDifficulty::Medium);
-            app.difficulty_selection_active = false;
-            update_display(app);
+            AIDifficulty::Medium
         } else if ndc_x >= 0.2 && ndc_x <= 0.6 {
-            // Hard
-            app.game_mode = GameMode::HumanVsAI(Color::Black, AIDifficulty::Hard);
-            app.difficulty_selection_active = false;
-            update_display(app);
-        }
+            AIDifficulty::Hard
+        } else {
+            return;
+        };
+        app.game_mode = GameMode::HumanVsAI(Color::Black, difficulty);
+        app.difficulty_selection_active = false;
+        start_game(app);
     }
 }
 
-fn handle_undo(app: &mut ChessGUI) {
-    if app.game_state_history.len() <= 1 || app.ai_thinking {
-        return;
+/// Sets up the starting position and clock for the chosen mode. Both depend
+/// on the mode, since it decides which side gives the handicap.
+fn start_game(app: &mut ChessGUI) {
+    app.start_state = starting_position(app);
+    app.moves.clear();
+    app.clock = new_clock(app);
+    rebuild_move_history(app);
+    view_ply(app, 0);
+}
+
+/// Plays `mv` from the viewed position. If an earlier ply is being reviewed,
+/// the moves after it are discarded and the new move continues the line.
+fn record_move(app: &mut ChessGUI, mv: Move) {
+    if let Some(clock) = &mut app.clock {
+        clock.press(app.game_state.turn, std::time::Instant::now());
     }
+    app.moves.truncate(app.view_ply);
+    app.moves.push(mv);
+    app.view_ply = app.moves.len();
+    app.game_state = app.game_state.apply_move(mv);
+    app.last_move = Some(mv);
+    app.status_message = None;
+    app.annotations.clear();
+    rebuild_move_history(app);
+    start_analysis(app);
+}
 
-    // Push current state to redo stack
-    app.redo_stack.push(app.game_state.clone());
+/// Loads a dropped FEN or PGN file. PGN games keep their full move list so
+/// they can be reviewed, and are shown at their final position.
+fn handle_dropped_file(app: &mut ChessGUI, path: &std::path::Path) {
+    if app.ai_thinking {
+        return;
+    }
 
-    // Pop from history (keep at least one state)
-    app.game_state_history.pop();
-    app.game_state = app.game_state_history.last().unwrap().clone();
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            app.status_message = Some(format!("Could not read file: {e}"));
+            update_display(app);
+            return;
+        }
+    };
 
-    // Rebuild move history
-    rebuild_move_history(app);
+    // FEN files hold a single line; anything with tags or move numbers is PGN
+    let trimmed = text.trim();
+    let loaded = if trimmed.starts_with('[') || trimmed.contains('.') {
+        Pgn::from_pgn(trimmed)
+            .map(|pgn| (pgn.start, pgn.moves))
+            .map_err(|e| format!("Invalid PGN: {e}"))
+    } else {
+        GameState::from_fen(trimmed)
+            .map(|state| (state, Vec::new()))
+            .map_err(|e| format!("Invalid FEN: {e}"))
+    };
 
-    // Clear selections and update display
-    app.selected_square = None;
-    app.valid_moves.clear();
-    app.last_move = None;
-    app.ai_move_receiver = None;
+    match loaded {
+        Ok((start_state, moves)) => {
+            app.start_state = start_state;
+            app.moves = moves;
+            app.promotion_pending = None;
+            app.status_message = None;
+            app.clock = new_clock(app);
+            app.flag_fall = None;
+            rebuild_move_history(app);
+            view_ply(app, app.moves.len());
+        }
+        Err(message) => {
+            app.status_message = Some(message);
+            update_display(app);
+        }
+    }
+}
 
-    // Play a click sound
-    if let Some(sound_manager) = &app.sound_manager {
-        sound_manager.play_move();
+fn toggle_enabled(app: &ChessGUI, toggle: Toggle) -> bool {
+    match toggle {
+        Toggle::LegalMoves => app.show_legal_moves,
+        Toggle::Coordinates => app.show_coordinates,
     }
+}
 
+fn flip_toggle(app: &mut ChessGUI, toggle: Toggle) {
+    match toggle {
+        Toggle::LegalMoves => app.show_legal_moves = !app.show_legal_moves,
+        Toggle::Coordinates => app.show_coordinates = !app.show_coordinates,
+    }
+    // Coordinates are drawn with the text, so the frame must be redrawn as
+    // well as the vertices rebuilt
     update_display(app);
+    app.window.request_redraw();
+}
+
+fn nav_enabled(app: &ChessGUI, action: NavAction) -> bool {
+    if app.ai_thinking || app.promotion_pending.is_some() {
+        return false;
+    }
+    match action {
+        NavAction::Start | NavAction::Back => app.view_ply > 0,
+        NavAction::Forward | NavAction::End => app.view_ply < app.moves.len(),
+    }
 }
 
-fn handle_redo(app: &mut ChessGUI) {
-    if app.redo_stack.is_empty() || app.ai_thinking {
+fn handle_navigation(app: &mut ChessGUI, action: NavAction) {
+    if !nav_enabled(app, action) {
         return;
     }
+    let ply = match action {
+        NavAction::Start => 0,
+        NavAction::Back => app.view_ply - 1,
+        NavAction::Forward => app.view_ply + 1,
+        NavAction::End => app.moves.len(),
+    };
+    view_ply(app, ply);
 
-    // Pop from redo stack
-    let next_state = app.redo_stack.pop().unwrap();
+    // Play a click sound
+    if let Some(sound_manager) = &app.sound_manager {
+        sound_manager.play_move();
+    }
+}
 
-    // Push current state to history
-    app.game_state_history.push(app.game_state.clone());
-    app.game_state = next_state;
-
-    // Rebuild move history
-    rebuild_move_history(app);
+/// Shows the position after `ply` moves without changing the game line.
+/// Stepping is instant, so any running move animation is dropped.
+fn view_ply(app: &mut ChessGUI, ply: usize) {
+    let ply = ply.min(app.moves.len());
+    app.game_state = replay_from(&app.start_state, &app.moves[..ply]);
+    app.view_ply = ply;
+    app.last_move = ply.checked_sub(1).map(|i| app.moves[i]);
 
     // Clear selections and update display
     app.selected_square = None;
     app.valid_moves.clear();
-    app.last_move = None;
-
-    // Play a click sound
-    if let Some(sound_manager) = &app.sound_manager {
-        sound_manager.play_move();
-    }
+    app.animating_move = None;
+    stop_ai(app);
+    app.pending_premove = None;
+    app.premove_from = None;
+    app.engine_hint = None;
+    app.annotations.clear();
+    start_analysis(app);
 
     update_display(app);
 }
@@ -1483,27 +2199,10 @@ fn handle_redo(app: &mut ChessGUI) {
 fn rebuild_move_history(app: &mut ChessGUI) {
     app.move_history.clear();
 
-    for i in 1..app.game_state_history.len() {
-        // Find the move that was made between states i-1 and i
-        // This is a simplified approach - in production you might store the moves separately
-        let prev_state = &app.game_state_history[i - 1];
-        let curr_state = &app.game_state_history[i];
-
-        // Generate all legal moves from previous state
-        let legal_moves = generate_legal_moves(prev_state);
-
-        // Find which move leads to the current state
-        for mv in legal_moves.iter() {
-            let test_state = prev_state.apply_move(*mv);
-            // Simple comparison - you might want a more robust state comparison
-            if test_state.board.piece_at(mv.to) == curr_state.board.piece_at(mv.to)
-                && test_state.turn == curr_state.turn
-            {
-                let move_notation = format_move(prev_state, *mv);
-                app.move_history.push(move_notation);
-                break;
-            }
-        }
+    let mut state = app.start_state.clone();
+    for &mv in &app.moves {
+        app.move_history.push(format_move(&state, mv));
+        state = state.apply_move(mv);
     }
 }
 
@@ -1792,6 +2491,38 @@ fn render_mode_selection(
         },
     ]);
 
+    // Button 3: time control, cycled in place
+    let btn3_color = [0.35, 0.35, 0.35, 1.0];
+    for position in [
+        [-0.3, CLOCK_BUTTON_BOTTOM],
+        [0.3, CLOCK_BUTTON_BOTTOM],
+        [-0.3, CLOCK_BUTTON_TOP],
+        [0.3, CLOCK_BUTTON_BOTTOM],
+        [0.3, CLOCK_BUTTON_TOP],
+        [-0.3, CLOCK_BUTTON_TOP],
+    ] {
+        vertices.push(Vertex {
+            position,
+            color: btn3_color,
+        });
+    }
+
+    // Button 4: analysis mode
+    let btn4_color = [0.3, 0.55, 0.4, 1.0];
+    for position in [
+        [-0.3, ANALYSIS_BUTTON_BOTTOM],
+        [0.3, ANALYSIS_BUTTON_BOTTOM],
+        [-0.3, ANALYSIS_BUTTON_TOP],
+        [0.3, ANALYSIS_BUTTON_BOTTOM],
+        [0.3, ANALYSIS_BUTTON_TOP],
+        [-0.3, ANALYSIS_BUTTON_TOP],
+    ] {
+        vertices.push(Vertex {
+            position,
+            color: btn4_color,
+        });
+    }
+
     // Create temporary buffer
     let mode_buffer = app
         .renderer
@@ -1839,6 +2570,7 @@ fn render_mode_selection(
             &app.renderer.queue,
             window_size.width as f32,
             window_size.height as f32,
+            app.time_control.name(),
         );
 
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -0,0 +1,335 @@
+use crate::layout::Layout;
+use crate::renderer::Vertex;
+use chess_core::{Color, PieceType};
+
+/// A piece to draw: type, color, NDC center and opacity. The same list the
+/// text renderer takes.
+pub type PieceSprite = (PieceType, Color, f32, f32, f32);
+
+const WHITE_FILL: [f32; 4] = [0.95, 0.93, 0.88, 1.0];
+const WHITE_OUTLINE: [f32; 4] = [0.15, 0.15, 0.15, 1.0];
+const BLACK_FILL: [f32; 4] = [0.12, 0.12, 0.12, 1.0];
+const BLACK_OUTLINE: [f32; 4] = [0.85, 0.85, 0.85, 1.0];
+
+/// Outline width as a share of the square size
+const OUTLINE: f32 = 0.025;
+
+/// Segments used to approximate a circle
+const CIRCLE_SEGMENTS: usize = 16;
+
+/// One filled shape in square-relative units: the square is 1.0 wide with
+/// its center at the origin and y pointing down, as in window pixels.
+#[derive(Debug, Clone, Copy)]
+enum Shape {
+    Rect {
+        left: f32,
+        top: f32,
+        right: f32,
+        bottom: f32,
+    },
+    Circle {
+        x: f32,
+        y: f32,
+        radius: f32,
+    },
+    Triangle([(f32, f32); 3]),
+}
+
+impl Shape {
+    /// The shape grown by `amount` on every side, for its outline.
+    fn grown(self, amount: f32) -> Self {
+        match self {
+            Shape::Rect {
+                left,
+                top,
+                right,
+                bottom,
+            } => Shape::Rect {
+                left: left - amount,
+                top: top - amount,
+                right: right + amount,
+                bottom: bottom + amount,
+            },
+            Shape::Circle { x, y, radius } => Shape::Circle {
+                x,
+                y,
+                radius: radius + amount,
+            },
+            Shape::Triangle(points) => {
+                let cx = points.iter().map(|p| p.0).sum::<f32>() / 3.0;
+                let cy = points.iter().map(|p| p.1).sum::<f32>() / 3.0;
+                Shape::Triangle(points.map(|(x, y)| {
+                    let (dx, dy) = (x - cx, y - cy);
+                    let length = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+                    // A vertex must move about twice as far as the edges
+                    let scale = 1.0 + 2.0 * amount / length;
+                    (cx + dx * scale, cy + dy * scale)
+                }))
+            }
+        }
+    }
+
+    /// Triangles covering the shape, as points in square-relative units.
+    fn triangles(self) -> Vec<[(f32, f32); 3]> {
+        match self {
+            Shape::Rect {
+                left,
+                top,
+                right,
+                bottom,
+            } => vec![
+                [(left, top), (right, top), (left, bottom)],
+                [(right, top), (right, bottom), (left, bottom)],
+            ],
+            Shape::Circle { x, y, radius } => (0..CIRCLE_SEGMENTS)
+                .map(|i| {
+                    let angle =
+                        |i: usize| i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                    let (a, b) = (angle(i), angle(i + 1));
+                    [
+                        (x, y),
+                        (x + radius * a.cos(), y + radius * a.sin()),
+                        (x + radius * b.cos(), y + radius * b.sin()),
+                    ]
+                })
+                .collect(),
+            Shape::Triangle(points) => vec![points],
+        }
+    }
+}
+
+const fn rect(left: f32, top: f32, right: f32, bottom: f32) -> Shape {
+    Shape::Rect {
+        left,
+        top,
+        right,
+        bottom,
+    }
+}
+
+const fn circle(x: f32, y: f32, radius: f32) -> Shape {
+    Shape::Circle { x, y, radius }
+}
+
+/// Silhouette of a piece type, built on a shared base.
+fn silhouette(piece_type: PieceType) -> Vec<Shape> {
+    let mut shapes = vec![rect(-0.3, 0.28, 0.3, 0.38)];
+    match piece_type {
+        PieceType::Pawn => {
+            shapes.push(Shape::Triangle([(-0.18, 0.28), (0.18, 0.28), (0.0, -0.05)]));
+            shapes.push(circle(0.0, -0.1, 0.12));
+        }
+        PieceType::Knight => {
+            shapes.push(rect(-0.15, -0.05, 0.15, 0.28));
+            // Head and muzzle leaning to the left
+            shapes.push(Shape::Triangle([
+                (-0.15, -0.05),
+                (0.2, -0.05),
+                (0.1, -0.35),
+            ]));
+            shapes.push(Shape::Triangle([(0.0, -0.3), (-0.3, -0.02), (0.05, 0.02)]));
+        }
+        PieceType::Bishop => {
+            shapes.push(Shape::Triangle([(-0.2, 0.28), (0.2, 0.28), (0.0, -0.25)]));
+            shapes.push(circle(0.0, -0.12, 0.13));
+            shapes.push(circle(0.0, -0.33, 0.05));
+        }
+        PieceType::Rook => {
+            shapes.push(rect(-0.18, -0.15, 0.18, 0.28));
+            shapes.push(rect(-0.24, -0.22, 0.24, -0.1));
+            for x in [-0.24, -0.04, 0.16] {
+                shapes.push(rect(x, -0.34, x + 0.08, -0.22));
+            }
+        }
+        PieceType::Queen => {
+            shapes.push(Shape::Triangle([(-0.24, 0.28), (0.24, 0.28), (0.0, -0.2)]));
+            shapes.push(rect(-0.24, -0.2, 0.24, -0.1));
+            for x in [-0.24, -0.12, 0.0, 0.12, 0.24] {
+                shapes.push(circle(x, -0.25, 0.06));
+            }
+        }
+        PieceType::King => {
+            shapes.push(Shape::Triangle([(-0.24, 0.28), (0.24, 0.28), (0.0, -0.15)]));
+            shapes.push(rect(-0.2, -0.18, 0.2, -0.06));
+            shapes.push(rect(-0.04, -0.42, 0.04, -0.18));
+            shapes.push(rect(-0.12, -0.35, 0.12, -0.27));
+        }
+    }
+    shapes
+}
+
+/// How pieces are drawn, cycled from the side panel.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PieceStyle {
+    /// Unicode chess glyphs from the text renderer
+    #[default]
+    Glyphs,
+    /// Vector shapes from `PieceRenderer`
+    Shapes,
+}
+
+impl PieceStyle {
+    pub fn name(self) -> &'static str {
+        match self {
+            PieceStyle::Glyphs => "Glyphs",
+            PieceStyle::Shapes => "Shapes",
+        }
+    }
+
+    /// The style after this one, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            PieceStyle::Glyphs => PieceStyle::Shapes,
+            PieceStyle::Shapes => PieceStyle::Glyphs,
+        }
+    }
+}
+
+/// Draws pieces as filled vector shapes, either by choice or when text
+/// rendering is not available. Uses the board pipeline, so the pieces need
+/// no font.
+pub struct PieceRenderer {
+    vertices: Vec<Vertex>,
+}
+
+impl PieceRenderer {
+    pub fn new() -> Self {
+        Self {
+            vertices: Vec::new(),
+        }
+    }
+
+    /// Builds the triangles for every piece, each outlined in the opposite
+    /// color so it stays visible on both light and dark squares.
+    pub fn generate_vertices(&mut self, pieces: &[PieceSprite], layout: &Layout) -> &[Vertex] {
+        self.vertices.clear();
+
+        for &(piece_type, color, ndc_x, ndc_y, opacity) in pieces {
+            let (fill, outline) = match color {
+                Color::White => (WHITE_FILL, WHITE_OUTLINE),
+                Color::Black => (BLACK_FILL, BLACK_OUTLINE),
+            };
+            let center = layout.ndc_to_pixel(ndc_x, ndc_y);
+            let shapes = silhouette(piece_type);
+
+            // All outlines first so no outline covers a neighbouring fill
+            for shape in &shapes {
+                self.push_shape(shape.grown(OUTLINE), center, layout, outline, opacity);
+            }
+            for &shape in &shapes {
+                self.push_shape(shape, center, layout, fill, opacity);
+            }
+        }
+
+        &self.vertices
+    }
+
+    fn push_shape(
+        &mut self,
+        shape: Shape,
+        (center_x, center_y): (f32, f32),
+        layout: &Layout,
+        color: [f32; 4],
+        opacity: f32,
+    ) {
+        let size = layout.square_size();
+        let color = [color[0], color[1], color[2], color[3] * opacity];
+        for triangle in shape.triangles() {
+            for (x, y) in triangle {
+                let position = layout.pixel_to_ndc(center_x + x * size, center_y + y * size);
+                self.vertices.push(Vertex { position, color });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess_core::Square;
+
+    #[test]
+    fn test_piece_style_cycles() {
+        assert_eq!(PieceStyle::default(), PieceStyle::Glyphs);
+        assert_eq!(PieceStyle::Glyphs.next(), PieceStyle::Shapes);
+        assert_eq!(PieceStyle::Shapes.next(), PieceStyle::Glyphs);
+    }
+
+    #[test]
+    fn test_pieces_are_centered() {
+        let layout = Layout::new(1280.0, 720.0);
+        let square = Square::from_algebraic("c6").unwrap();
+        let (center_x, center_y) = layout.square_center(square);
+        let [ndc_x, ndc_y] = layout.pixel_to_ndc(center_x, center_y);
+        let mut renderer = PieceRenderer::new();
+
+        for piece_type in [
+            PieceType::Pawn,
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+            PieceType::King,
+        ] {
+            let vertices = renderer
+                .generate_vertices(&[(piece_type, Color::White, ndc_x, ndc_y, 1.0)], &layout);
+            let points: Vec<(f32, f32)> = vertices
+                .iter()
+                .map(|v| layout.ndc_to_pixel(v.position[0], v.position[1]))
+                .collect();
+            let min_x = points.iter().map(|p| p.0).fold(f32::MAX, f32::min);
+            let max_x = points.iter().map(|p| p.0).fold(f32::MIN, f32::max);
+            let min_y = points.iter().map(|p| p.1).fold(f32::MAX, f32::min);
+            let max_y = points.iter().map(|p| p.1).fold(f32::MIN, f32::max);
+
+            // The bounding box is centered on the square, within a tenth of it
+            let tolerance = layout.square_size() * 0.1;
+            assert!(
+                ((min_x + max_x) / 2.0 - center_x).abs() < tolerance,
+                "{piece_type:?}"
+            );
+            assert!(
+                ((min_y + max_y) / 2.0 - center_y).abs() < tolerance,
+                "{piece_type:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_pieces_stay_on_their_square_in_their_color() {
+        let layout = Layout::new(1000.0, 800.0);
+        let square = Square::from_algebraic("e4").unwrap();
+        let (x, y) = layout.square_center(square);
+        let [ndc_x, ndc_y] = layout.pixel_to_ndc(x, y);
+        let mut renderer = PieceRenderer::new();
+
+        for piece_type in [
+            PieceType::Pawn,
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+            PieceType::King,
+        ] {
+            for color in [Color::White, Color::Black] {
+                let vertices =
+                    renderer.generate_vertices(&[(piece_type, color, ndc_x, ndc_y, 0.5)], &layout);
+                assert!(!vertices.is_empty());
+                assert_eq!(vertices.len() % 3, 0);
+
+                let fill = match color {
+                    Color::White => WHITE_FILL,
+                    Color::Black => BLACK_FILL,
+                };
+                assert!(vertices
+                    .iter()
+                    .any(|v| v.color[..3] == fill[..3] && v.color[3] == 0.5));
+
+                for vertex in vertices {
+                    let (px, py) = layout.ndc_to_pixel(vertex.position[0], vertex.position[1]);
+                    assert_eq!(layout.square_at(px, py), Some(square), "{piece_type:?}");
+                }
+            }
+        }
+    }
+}
@@ -34,6 +34,8 @@ pub struct Renderer {
     pub vertex_buffer: wgpu::Buffer,
     pub num_vertices: u32,
     max_vertices: usize,
+    /// Vertices written by `update_vertices`; overlays go after them
+    base_vertices: u32,
 }
 
 impl Renderer {
@@ -143,7 +145,7 @@ impl Renderer {
         });
 
         // Create vertex buffer with capacity for many vertices
-        let max_vertices = 10000; // Enough for board + pieces
+        let max_vertices = 20000; // Enough for board + vector-drawn pieces
         let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Vertex Buffer"),
             size: (std::mem::size_of::<Vertex>() * max_vertices) as u64,
@@ -163,6 +165,7 @@ impl Renderer {
             vertex_buffer,
             num_vertices,
             max_vertices,
+            base_vertices: num_vertices,
         }
     }
 
@@ -179,6 +182,19 @@ impl Renderer {
             self.queue
                 .write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(vertices));
             self.num_vertices = vertices.len() as u32;
+            self.base_vertices = self.num_vertices;
+        }
+    }
+
+    /// Replaces the vertices drawn after those from `update_vertices`, e.g.
+    /// pieces that move every frame while the board stays put.
+    pub fn update_overlay_vertices(&mut self, vertices: &[Vertex]) {
+        let base = self.base_vertices as usize;
+        if base + vertices.len() <= self.max_vertices {
+            let offset = (base * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress;
+            self.queue
+                .write_buffer(&self.vertex_buffer, offset, bytemuck::cast_slice(vertices));
+            self.num_vertices = (base + vertices.len()) as u32;
         }
     }
 
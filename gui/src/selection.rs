@@ -0,0 +1,56 @@
+use chess_core::{GameState, Move, Square};
+
+/// Moves to highlight after clicking `square`: the legal moves of the piece
+/// there, or none if it is empty or belongs to the side not to move.
+///
+/// Kept apart from the event loop so the highlighting rules can be tested
+/// without a window.
+pub fn compute_valid_moves(state: &GameState, square: Square) -> Vec<Move> {
+    match state.board.piece_at(square) {
+        Some(piece) if piece.color == state.turn => state.legal_moves_from(square),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+
+    fn targets(fen: &str, from: &str) -> Vec<String> {
+        let state = GameState::from_fen(fen).unwrap();
+        let mut targets: Vec<String> = compute_valid_moves(&state, square(from))
+            .iter()
+            .map(|mv| mv.to.to_string())
+            .collect();
+        targets.sort();
+        targets
+    }
+
+    #[test]
+    fn test_pinned_pieces_only_move_along_the_pin() {
+        // Rook pinned on the e-file keeps its file moves, capture included
+        assert_eq!(
+            targets("4r2k/8/8/8/4R3/8/8/4K3 w - - 0 1", "e4"),
+            ["e2", "e3", "e5", "e6", "e7", "e8"]
+        );
+        // Bishop pinned on a file cannot move at all
+        assert!(targets("4r2k/8/8/8/8/8/4B3/4K3 w - - 0 1", "e2").is_empty());
+        // Bishop pinned on a diagonal slides along it
+        assert_eq!(
+            targets("7k/8/8/b7/8/8/3B4/4K3 w - - 0 1", "d2"),
+            ["a5", "b4", "c3"]
+        );
+    }
+
+    #[test]
+    fn test_only_side_to_move_gets_highlights() {
+        let state = GameState::new();
+        assert_eq!(compute_valid_moves(&state, square("g1")).len(), 2);
+        assert!(compute_valid_moves(&state, square("g8")).is_empty());
+        assert!(compute_valid_moves(&state, square("e4")).is_empty());
+    }
+}
@@ -1,4 +1,5 @@
-use chess_core::{Color, PieceType};
+use crate::layout::BOARD_FRACTION;
+use chess_core::{Color, Piece, PieceType};
 use glyphon::{
     Attrs, Buffer, Family, FontSystem, Metrics, Resolution, Shaping, SwashCache, TextArea,
     TextAtlas, TextBounds, TextRenderer as GlyphonRenderer,
@@ -10,8 +11,15 @@ pub struct UiText {
     pub game_mode: String,
     pub status: String,
     pub move_history: Vec<String>,
-    pub undo_enabled: bool,
-    pub redo_enabled: bool,
+    pub theme: String,
+    pub handicap: String,
+    pub piece_style: String,
+    /// Both clocks, or empty in untimed games
+    pub clock: String,
+    pub back_enabled: bool,
+    pub forward_enabled: bool,
+    /// Label the board's files and ranks along its bottom and left edges
+    pub show_coordinates: bool,
 }
 
 pub struct TextRenderer {
@@ -25,12 +33,20 @@ pub struct TextRenderer {
     game_mode_buffer: Option<Buffer>,
     status_buffer: Option<Buffer>,
     move_history_buffer: Option<Buffer>,
-    undo_buffer: Option<Buffer>,
-    redo_buffer: Option<Buffer>,
+    nav_buffers: Vec<Buffer>,
+    toggle_buffers: Vec<Buffer>,
+    // File and rank labels with the pixel position of each
+    coordinate_buffers: Vec<(Buffer, f32, f32)>,
+    theme_buffer: Option<Buffer>,
+    handicap_buffer: Option<Buffer>,
+    piece_style_buffer: Option<Buffer>,
+    clock_buffer: Option<Buffer>,
 }
 
 impl TextRenderer {
-    pub fn new(device: &Device, queue: &Queue, format: TextureFormat) -> Self {
+    /// Returns `None` if no font could be loaded, in which case the GUI
+    /// falls back to drawing pieces as shapes.
+    pub fn new(device: &Device, queue: &Queue, format: TextureFormat) -> Option<Self> {
         let mut font_system = FontSystem::new();
 
         // Load the DejaVu Sans font
@@ -38,12 +54,15 @@ impl TextRenderer {
         font_system
             .db_mut()
             .load_font_data(Vec::from(&font_data[..]));
+        if font_system.db().is_empty() {
+            return None;
+        }
 
         let swash_cache = SwashCache::new();
         let mut atlas = TextAtlas::new(device, queue, format);
         let renderer = GlyphonRenderer::new(&mut atlas, device, MultisampleState::default(), None);
 
-        Self {
+        Some(Self {
             font_system,
             swash_cache,
             atlas,
@@ -52,33 +71,23 @@ impl TextRenderer {
             game_mode_buffer: None,
             status_buffer: None,
             move_history_buffer: None,
-            undo_buffer: None,
-            redo_buffer: None,
-        }
-    }
-
-    pub fn get_piece_symbol(piece_type: PieceType, color: Color) -> &'static str {
-        match (piece_type, color) {
-            (PieceType::King, Color::White) => "♔",
-            (PieceType::Queen, Color::White) => "♕",
-            (PieceType::Rook, Color::White) => "♖",
-            (PieceType::Bishop, Color::White) => "♗",
-            (PieceType::Knight, Color::White) => "♘",
-            (PieceType::Pawn, Color::White) => "♙",
-            (PieceType::King, Color::Black) => "♚",
-            (PieceType::Queen, Color::Black) => "♛",
-            (PieceType::Rook, Color::Black) => "♜",
-            (PieceType::Bishop, Color::Black) => "♝",
-            (PieceType::Knight, Color::Black) => "♞",
-            (PieceType::Pawn, Color::Black) => "♟",
-        }
+            nav_buffers: Vec::new(),
+            toggle_buffers: Vec::new(),
+            coordinate_buffers: Vec::new(),
+            theme_buffer: None,
+            handicap_buffer: None,
+            piece_style_buffer: None,
+            clock_buffer: None,
+        })
     }
 
+    /// Lays out the pieces, each given as type, color, NDC center and
+    /// opacity, along with the side panel text.
     pub fn prepare_pieces(
         &mut self,
         device: &Device,
         queue: &Queue,
-        pieces: &[(PieceType, Color, f32, f32)],
+        pieces: &[(PieceType, Color, f32, f32, f32)],
         square_size: f32,
         screen_width: f32,
         screen_height: f32,
@@ -88,8 +97,8 @@ impl TextRenderer {
         self.piece_buffers.clear();
 
         // Create buffers for each piece
-        for &(piece_type, color, ndc_x, ndc_y) in pieces {
-            let symbol = Self::get_piece_symbol(piece_type, color);
+        for &(piece_type, color, ndc_x, ndc_y, _) in pieces {
+            let symbol = Piece::new(piece_type, color).unicode().to_string();
 
             // Convert from NDC to screen coordinates
             let screen_x = (ndc_x + 1.0) * screen_width / 2.0;
@@ -103,7 +112,7 @@ impl TextRenderer {
             buffer.set_size(&mut self.font_system, square_size, square_size);
             buffer.set_text(
                 &mut self.font_system,
-                symbol,
+                &symbol,
                 Attrs::new().family(Family::SansSerif),
                 Shaping::Advanced,
             );
@@ -162,38 +171,150 @@ impl TextRenderer {
             self.move_history_buffer = Some(buffer);
         }
 
-        // Undo button text
+        // Theme button text
         {
-            let mut buffer = Buffer::new(&mut self.font_system, Metrics::new(20.0, 24.0));
-            buffer.set_size(&mut self.font_system, 40.0, 30.0);
+            let mut buffer = Buffer::new(&mut self.font_system, Metrics::new(16.0, 20.0));
+            buffer.set_size(&mut self.font_system, screen_width * 0.17, 30.0);
             buffer.set_text(
                 &mut self.font_system,
-                "↶", // Unicode undo arrow
+                &ui_text.theme,
                 Attrs::new().family(Family::SansSerif),
                 Shaping::Advanced,
             );
             buffer.shape_until_scroll(&mut self.font_system);
-            self.undo_buffer = Some(buffer);
+            self.theme_buffer = Some(buffer);
         }
 
-        // Redo button text
+        // Handicap button text
         {
-            let mut buffer = Buffer::new(&mut self.font_system, Metrics::new(20.0, 24.0));
-            buffer.set_size(&mut self.font_system, 40.0, 30.0);
+            let mut buffer = Buffer::new(&mut self.font_system, Metrics::new(16.0, 20.0));
+            buffer.set_size(&mut self.font_system, screen_width * 0.17, 30.0);
+            buffer.set_text(
+                &mut self.font_system,
+                &ui_text.handicap,
+                Attrs::new().family(Family::SansSerif),
+                Shaping::Advanced,
+            );
+            buffer.shape_until_scroll(&mut self.font_system);
+            self.handicap_buffer = Some(buffer);
+        }
+
+        // Piece style button text
+        {
+            let mut buffer = Buffer::new(&mut self.font_system, Metrics::new(16.0, 20.0));
+            buffer.set_size(&mut self.font_system, screen_width * 0.17, 30.0);
             buffer.set_text(
                 &mut self.font_system,
-                "↷", // Unicode redo arrow
+                &ui_text.piece_style,
                 Attrs::new().family(Family::SansSerif),
                 Shaping::Advanced,
             );
             buffer.shape_until_scroll(&mut self.font_system);
-            self.redo_buffer = Some(buffer);
+            self.piece_style_buffer = Some(buffer);
+        }
+
+        // Clock text, below the game mode
+        self.clock_buffer = None;
+        if !ui_text.clock.is_empty() {
+            let mut buffer = Buffer::new(&mut self.font_system, Metrics::new(18.0, 22.0));
+            buffer.set_size(&mut self.font_system, screen_width * 0.2, 30.0);
+            buffer.set_text(
+                &mut self.font_system,
+                &ui_text.clock,
+                Attrs::new().family(Family::Monospace),
+                Shaping::Advanced,
+            );
+            buffer.shape_until_scroll(&mut self.font_system);
+            self.clock_buffer = Some(buffer);
+        }
+
+        // Review navigation button labels: start, back, forward, end
+        if self.nav_buffers.is_empty() {
+            for label in ["|◀", "◀", "▶", "▶|"] {
+                let mut buffer = Buffer::new(&mut self.font_system, Metrics::new(20.0, 24.0));
+                buffer.set_size(&mut self.font_system, 40.0, 30.0);
+                buffer.set_text(
+                    &mut self.font_system,
+                    label,
+                    Attrs::new().family(Family::SansSerif),
+                    Shaping::Advanced,
+                );
+                buffer.shape_until_scroll(&mut self.font_system);
+                self.nav_buffers.push(buffer);
+            }
+        }
+
+        // Display toggle labels, beside their checkboxes
+        if self.toggle_buffers.is_empty() {
+            for label in ["Moves", "Coords"] {
+                let mut buffer = Buffer::new(&mut self.font_system, Metrics::new(14.0, 18.0));
+                buffer.set_size(&mut self.font_system, 60.0, 24.0);
+                buffer.set_text(
+                    &mut self.font_system,
+                    label,
+                    Attrs::new().family(Family::SansSerif),
+                    Shaping::Advanced,
+                );
+                buffer.shape_until_scroll(&mut self.font_system);
+                self.toggle_buffers.push(buffer);
+            }
+        }
+
+        // Coordinates: files in the bottom-right corner of the first rank's
+        // squares, ranks in the top-left corner of the a-file's
+        self.coordinate_buffers.clear();
+        if ui_text.show_coordinates {
+            let font_size = (square_size * 0.2).max(8.0);
+            let board_size = square_size * 8.0;
+            for index in 0..8 {
+                let file = (b'a' + index) as char;
+                let rank = (b'8' - index) as char;
+                let offset = f32::from(index) * square_size;
+                let labels = [
+                    (
+                        file,
+                        offset + square_size - font_size * 0.75,
+                        board_size - font_size * 1.2,
+                    ),
+                    (rank, 2.0, offset + 1.0),
+                ];
+                for (label, left, top) in labels {
+                    let mut buffer =
+                        Buffer::new(&mut self.font_system, Metrics::new(font_size, font_size));
+                    buffer.set_size(&mut self.font_system, font_size, font_size * 1.2);
+                    buffer.set_text(
+                        &mut self.font_system,
+                        &label.to_string(),
+                        Attrs::new().family(Family::SansSerif),
+                        Shaping::Advanced,
+                    );
+                    buffer.shape_until_scroll(&mut self.font_system);
+                    self.coordinate_buffers.push((buffer, left, top));
+                }
+            }
         }
 
         // Build text areas from stored buffers
         let mut text_areas = Vec::new();
 
-        let panel_left = screen_width * 0.8 + 20.0; // Right side panel
+        // Coordinates first, so pieces are drawn over them
+        for (buffer, left, top) in &self.coordinate_buffers {
+            text_areas.push(TextArea {
+                buffer,
+                left: *left,
+                top: *top,
+                scale: 1.0,
+                bounds: TextBounds {
+                    left: *left as i32,
+                    top: *top as i32,
+                    right: (*left + square_size) as i32,
+                    bottom: (*top + square_size) as i32,
+                },
+                default_color: glyphon::Color::rgba(40, 40, 40, 200),
+            });
+        }
+
+        let panel_left = screen_width * BOARD_FRACTION + 20.0; // Right side panel
 
         // Add game mode text area
         if let Some(buffer) = &self.game_mode_buffer {
@@ -212,6 +333,23 @@ impl TextRenderer {
             });
         }
 
+        // Add clock text area
+        if let Some(buffer) = &self.clock_buffer {
+            text_areas.push(TextArea {
+                buffer,
+                left: panel_left,
+                top: 46.0,
+                scale: 1.0,
+                bounds: TextBounds {
+                    left: panel_left as i32,
+                    top: 40,
+                    right: screen_width as i32,
+                    bottom: 72,
+                },
+                default_color: glyphon::Color::rgb(255, 255, 255),
+            });
+        }
+
         // Add status text area
         if let Some(buffer) = &self.status_buffer {
             text_areas.push(TextArea {
@@ -240,49 +378,114 @@ impl TextRenderer {
                     left: panel_left as i32,
                     top: (screen_height * 0.4) as i32,
                     right: screen_width as i32,
-                    bottom: screen_height as i32,
+                    // Stops above the piece style button
+                    bottom: (screen_height * 0.93) as i32,
                 },
                 default_color: glyphon::Color::rgb(180, 180, 180),
             });
         }
 
-        // Add undo button label
-        if let Some(buffer) = &self.undo_buffer {
-            let button_y = screen_height * 0.325; // Matches button_y = 0.35 in NDC
+        // Add theme button label
+        if let Some(buffer) = &self.theme_buffer {
+            let button_y = screen_height * 0.19; // Matches THEME_BUTTON_Y = 0.62 in NDC
+            let button_left = screen_width * 0.815; // Matches THEME_BUTTON_LEFT = 0.63 in NDC
             text_areas.push(TextArea {
                 buffer,
-                left: screen_width * 0.85 - 12.0, // Center in button
-                top: button_y - 8.0,
+                left: button_left + 10.0,
+                top: button_y - 10.0,
                 scale: 1.0,
                 bounds: TextBounds {
-                    left: (screen_width * 0.85 - 20.0) as i32,
+                    left: button_left as i32,
                     top: (button_y - 20.0) as i32,
-                    right: (screen_width * 0.85 + 20.0) as i32,
+                    right: screen_width as i32,
                     bottom: (button_y + 20.0) as i32,
                 },
-                default_color: if ui_text.undo_enabled {
-                    glyphon::Color::rgb(255, 255, 255)
-                } else {
-                    glyphon::Color::rgb(100, 100, 100)
+                default_color: glyphon::Color::rgb(220, 220, 220),
+            });
+        }
+
+        // Add handicap button label
+        if let Some(buffer) = &self.handicap_buffer {
+            let button_y = screen_height * 0.13; // Matches HANDICAP_BUTTON_Y = 0.74 in NDC
+            let button_left = screen_width * 0.815; // Matches THEME_BUTTON_LEFT = 0.63 in NDC
+            text_areas.push(TextArea {
+                buffer,
+                left: button_left + 10.0,
+                top: button_y - 10.0,
+                scale: 1.0,
+                bounds: TextBounds {
+                    left: button_left as i32,
+                    top: (button_y - 20.0) as i32,
+                    right: screen_width as i32,
+                    bottom: (button_y + 20.0) as i32,
+                },
+                default_color: glyphon::Color::rgb(220, 220, 220),
+            });
+        }
+
+        // Add piece style button label
+        if let Some(buffer) = &self.piece_style_buffer {
+            let button_y = screen_height * 0.95; // Matches PIECE_STYLE_BUTTON_Y = -0.9 in NDC
+            let button_left = screen_width * 0.815; // Matches THEME_BUTTON_LEFT = 0.63 in NDC
+            text_areas.push(TextArea {
+                buffer,
+                left: button_left + 10.0,
+                top: button_y - 10.0,
+                scale: 1.0,
+                bounds: TextBounds {
+                    left: button_left as i32,
+                    top: (button_y - 20.0) as i32,
+                    right: screen_width as i32,
+                    bottom: (button_y + 20.0) as i32,
                 },
+                default_color: glyphon::Color::rgb(220, 220, 220),
             });
         }
 
-        // Add redo button label
-        if let Some(buffer) = &self.redo_buffer {
-            let button_y = screen_height * 0.325;
+        // Add display toggle labels
+        let toggle_y = screen_height * 0.23; // Matches TOGGLE_Y = 0.54 in NDC
+        let toggle_lefts = [0.8325, 0.9225]; // Matches TOGGLES plus the box width in NDC
+        for (buffer, left) in self.toggle_buffers.iter().zip(toggle_lefts) {
+            let left = screen_width * left + 4.0;
             text_areas.push(TextArea {
                 buffer,
-                left: screen_width * 0.91 - 12.0, // Center in button
+                left,
+                top: toggle_y - 9.0,
+                scale: 1.0,
+                bounds: TextBounds {
+                    left: left as i32,
+                    top: (toggle_y - 12.0) as i32,
+                    right: screen_width as i32,
+                    bottom: (toggle_y + 12.0) as i32,
+                },
+                default_color: glyphon::Color::rgb(220, 220, 220),
+            });
+        }
+
+        // Add navigation button labels
+        let button_y = screen_height * 0.325; // Matches NAV_BUTTON_Y = 0.35 in NDC
+        let button_centers = [0.8325, 0.8775, 0.9225, 0.9675]; // Matches NAV_BUTTONS in NDC
+        let enabled = [
+            ui_text.back_enabled,
+            ui_text.back_enabled,
+            ui_text.forward_enabled,
+            ui_text.forward_enabled,
+        ];
+        for ((buffer, center), enabled) in self.nav_buffers.iter().zip(button_centers).zip(enabled)
+        {
+            let center_x = screen_width * center;
+            text_areas.push(TextArea {
+                buffer,
+                left: center_x - 10.0, // Center in button
                 top: button_y - 8.0,
                 scale: 1.0,
                 bounds: TextBounds {
-                    left: (screen_width * 0.91 - 20.0) as i32,
+                    left: (center_x - 20.0) as i32,
                     top: (button_y - 20.0) as i32,
-                    right: (screen_width * 0.91 + 20.0) as i32,
+                    right: (center_x + 20.0) as i32,
                     bottom: (button_y + 20.0) as i32,
                 },
-                default_color: if ui_text.redo_enabled {
+                default_color: if enabled {
                     glyphon::Color::rgb(255, 255, 255)
                 } else {
                     glyphon::Color::rgb(100, 100, 100)
@@ -298,23 +501,26 @@ impl TextRenderer {
             let left = screen_x - square_size / 2.0;
             let top = screen_y - square_size / 2.0;
 
-            // Determine piece color from the stored piece data
-            let piece_color = if let Some(&(_, color, _, _)) = pieces.iter().find(|(_, _, x, y)| {
-                let sx = (*x + 1.0) * screen_width / 2.0;
-                let sy = (1.0 - *y) * screen_height / 2.0;
-                (sx as i32, sy as i32) == (screen_x as i32, screen_y as i32)
-            }) {
-                match color {
-                    Color::White => glyphon::Color::rgb(255, 255, 255), // White fill for white pieces
-                    Color::Black => glyphon::Color::rgb(0, 0, 0), // Black fill for black pieces
-                }
-            } else {
-                glyphon::Color::rgb(255, 255, 255) // Default to white
+            // Determine piece color and opacity from the stored piece data
+            let (color, alpha) = pieces
+                .iter()
+                .find(|(_, _, x, y, _)| {
+                    let sx = (*x + 1.0) * screen_width / 2.0;
+                    let sy = (1.0 - *y) * screen_height / 2.0;
+                    (sx as i32, sy as i32) == (screen_x as i32, screen_y as i32)
+                })
+                .map_or((Color::White, 1.0), |&(_, color, _, _, alpha)| {
+                    (color, alpha)
+                });
+            let alpha = (alpha.clamp(0.0, 1.0) * 255.0) as u8;
+            let outline_color = glyphon::Color::rgba(0, 0, 0, alpha);
+            let piece_color = match color {
+                Color::White => glyphon::Color::rgba(255, 255, 255, alpha), // White fill for white pieces
+                Color::Black => glyphon::Color::rgba(0, 0, 0, alpha), // Black fill for black pieces
             };
 
             // For white pieces, we need to render multiple layers
-            let is_white_piece = piece_color == glyphon::Color::rgb(255, 255, 255);
-            if is_white_piece {
+            if color == Color::White {
                 // First add thick black outline
                 for offset in &[
                     (2.0, 0.0),
@@ -337,7 +543,7 @@ impl TextRenderer {
                             right: (left + square_size + offset.0) as i32,
                             bottom: (top + square_size + offset.1) as i32,
                         },
-                        default_color: glyphon::Color::rgb(0, 0, 0),
+                        default_color: outline_color,
                     });
                 }
 
@@ -353,7 +559,7 @@ impl TextRenderer {
                         right: (left + square_size) as i32,
                         bottom: (top + square_size) as i32,
                     },
-                    default_color: glyphon::Color::rgb(255, 255, 255),
+                    default_color: piece_color,
                 });
             } else {
                 // For black pieces, just add outline and piece
@@ -368,7 +574,7 @@ impl TextRenderer {
                         right: (left + square_size + 1.5) as i32,
                         bottom: (top + square_size + 1.5) as i32,
                     },
-                    default_color: glyphon::Color::rgb(0, 0, 0),
+                    default_color: outline_color,
                 });
 
                 text_areas.push(TextArea {
@@ -409,6 +615,7 @@ impl TextRenderer {
         queue: &Queue,
         screen_width: f32,
         screen_height: f32,
+        time_control: &str,
     ) {
         // Clear previous buffers
         self.piece_buffers.clear();
@@ -476,6 +683,38 @@ impl TextRenderer {
             self.piece_buffers.insert(key, buffer);
         }
 
+        // Time control button text
+        {
+            let mut buffer = Buffer::new(&mut self.font_system, Metrics::new(20.0, 24.0));
+            buffer.set_size(&mut self.font_system, 200.0, 50.0);
+            buffer.set_text(
+                &mut self.font_system,
+                &format!("Clock: {}", time_control),
+                Attrs::new().family(Family::SansSerif),
+                Shaping::Advanced,
+            );
+            buffer.shape_until_scroll(&mut self.font_system);
+
+            let key = (2, 0); // Dummy key for the time control button
+            self.piece_buffers.insert(key, buffer);
+        }
+
+        // Analysis button text
+        {
+            let mut buffer = Buffer::new(&mut self.font_system, Metrics::new(20.0, 24.0));
+            buffer.set_size(&mut self.font_system, 200.0, 50.0);
+            buffer.set_text(
+                &mut self.font_system,
+                "Analysis",
+                Attrs::new().family(Family::SansSerif),
+                Shaping::Advanced,
+            );
+            buffer.shape_until_scroll(&mut self.font_system);
+
+            let key = (3, 0); // Dummy key for the analysis button
+            self.piece_buffers.insert(key, buffer);
+        }
+
         // Now build text areas from stored buffers
         let mut text_areas = Vec::new();
 
@@ -547,6 +786,40 @@ impl TextRenderer {
             });
         }
 
+        // Time control button text area (button centered at NDC (0, -0.4))
+        if let Some(buffer) = self.piece_buffers.get(&(2, 0)) {
+            text_areas.push(TextArea {
+                buffer,
+                left: screen_width * 0.5 - 55.0,
+                top: screen_height * 0.7 - 12.0,
+                scale: 1.0,
+                bounds: TextBounds {
+                    left: (screen_width * 0.5 - 100.0) as i32,
+                    top: (screen_height * 0.7 - 25.0) as i32,
+                    right: (screen_width * 0.5 + 100.0) as i32,
+                    bottom: (screen_height * 0.7 + 25.0) as i32,
+                },
+                default_color: glyphon::Color::rgb(255, 255, 255),
+            });
+        }
+
+        // Analysis button text area (button centered at NDC (0, -0.7))
+        if let Some(buffer) = self.piece_buffers.get(&(3, 0)) {
+            text_areas.push(TextArea {
+                buffer,
+                left: screen_width * 0.5 - 40.0,
+                top: screen_height * 0.85 - 12.0,
+                scale: 1.0,
+                bounds: TextBounds {
+                    left: (screen_width * 0.5 - 100.0) as i32,
+                    top: (screen_height * 0.85 - 25.0) as i32,
+                    right: (screen_width * 0.5 + 100.0) as i32,
+                    bottom: (screen_height * 0.85 + 25.0) as i32,
+                },
+                default_color: glyphon::Color::rgb(255, 255, 255),
+            });
+        }
+
         self.renderer
             .prepare(
                 device,
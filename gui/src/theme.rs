@@ -0,0 +1,103 @@
+/// Named board color schemes, cycled from the side panel.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BoardTheme {
+    #[default]
+    Classic,
+    Green,
+    Blue,
+    Grayscale,
+}
+
+/// Colors used by `BoardRenderer` for squares and overlays.
+///
+/// Dark squares stay mid-toned in every theme: black pieces are drawn with a
+/// black outline and white pieces with a black outline around a white fill,
+/// so a near-black square would swallow the black pieces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThemeColors {
+    pub light: [f32; 4],
+    pub dark: [f32; 4],
+    pub selected: [f32; 4],
+    pub valid_move: [f32; 4],
+    pub last_move: [f32; 4],
+    pub arrow: [f32; 4],
+    pub threat: [f32; 4],
+    pub premove: [f32; 4],
+    pub annotation: [f32; 4],
+    /// Flash on the source square of a rejected move
+    pub rejected: [f32; 4],
+}
+
+impl BoardTheme {
+    pub fn name(self) -> &'static str {
+        match self {
+            BoardTheme::Classic => "Classic",
+            BoardTheme::Green => "Green",
+            BoardTheme::Blue => "Blue",
+            BoardTheme::Grayscale => "Grayscale",
+        }
+    }
+
+    /// The theme after this one, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            BoardTheme::Classic => BoardTheme::Green,
+            BoardTheme::Green => BoardTheme::Blue,
+            BoardTheme::Blue => BoardTheme::Grayscale,
+            BoardTheme::Grayscale => BoardTheme::Classic,
+        }
+    }
+
+    pub fn colors(self) -> ThemeColors {
+        match self {
+            BoardTheme::Classic => ThemeColors {
+                light: [0.93, 0.93, 0.82, 1.0],   // Light beige
+                dark: [0.54, 0.27, 0.07, 1.0],    // Dark brown
+                selected: [0.7, 0.7, 0.3, 1.0],   // Yellow highlight
+                valid_move: [0.3, 0.7, 0.3, 0.5], // Semi-transparent green
+                last_move: [0.5, 0.3, 0.7, 0.3],  // Semi-transparent purple
+                arrow: [0.1, 0.5, 0.9, 0.7],      // Semi-transparent blue
+                threat: [0.9, 0.1, 0.1, 0.25],    // Faint red
+                premove: [0.1, 0.6, 0.6, 0.5],    // Semi-transparent teal
+                annotation: [0.2, 0.7, 0.2, 0.6], // Semi-transparent green
+                rejected: [0.9, 0.1, 0.1, 0.6],   // Semi-transparent red
+            },
+            BoardTheme::Green => ThemeColors {
+                light: [0.93, 0.93, 0.82, 1.0],    // Cream
+                dark: [0.46, 0.59, 0.34, 1.0],     // Moss green
+                selected: [0.73, 0.79, 0.27, 1.0], // Yellow-green highlight
+                valid_move: [0.2, 0.3, 0.7, 0.4],  // Semi-transparent blue
+                last_move: [0.9, 0.9, 0.3, 0.35],  // Semi-transparent yellow
+                arrow: [0.9, 0.5, 0.1, 0.7],       // Semi-transparent orange
+                threat: [0.9, 0.1, 0.1, 0.25],     // Faint red
+                premove: [0.8, 0.3, 0.5, 0.45],    // Semi-transparent pink
+                annotation: [0.1, 0.5, 0.2, 0.6],  // Semi-transparent dark green
+                rejected: [0.9, 0.1, 0.1, 0.6],    // Semi-transparent red
+            },
+            BoardTheme::Blue => ThemeColors {
+                light: [0.87, 0.89, 0.9, 1.0],    // Pale blue-gray
+                dark: [0.55, 0.64, 0.68, 1.0],    // Steel blue
+                selected: [0.4, 0.7, 0.9, 1.0],   // Light blue highlight
+                valid_move: [0.3, 0.7, 0.3, 0.5], // Semi-transparent green
+                last_move: [0.9, 0.8, 0.3, 0.35], // Semi-transparent yellow
+                arrow: [0.9, 0.3, 0.2, 0.7],      // Semi-transparent red
+                threat: [0.9, 0.5, 0.1, 0.25],    // Faint orange
+                premove: [0.6, 0.3, 0.8, 0.45],   // Semi-transparent violet
+                annotation: [0.2, 0.7, 0.3, 0.6], // Semi-transparent green
+                rejected: [0.9, 0.1, 0.1, 0.6],   // Semi-transparent red
+            },
+            BoardTheme::Grayscale => ThemeColors {
+                light: [0.85, 0.85, 0.85, 1.0],   // Light gray
+                dark: [0.55, 0.55, 0.55, 1.0],    // Mid gray
+                selected: [0.7, 0.7, 0.4, 1.0],   // Muted yellow highlight
+                valid_move: [0.3, 0.6, 0.3, 0.5], // Semi-transparent green
+                last_move: [0.3, 0.4, 0.8, 0.3],  // Semi-transparent blue
+                arrow: [0.8, 0.2, 0.2, 0.7],      // Semi-transparent red
+                threat: [0.9, 0.1, 0.1, 0.25],    // Faint red
+                premove: [0.2, 0.6, 0.7, 0.5],    // Semi-transparent teal
+                annotation: [0.2, 0.6, 0.2, 0.6], // Semi-transparent green
+                rejected: [0.9, 0.1, 0.1, 0.6],   // Semi-transparent red
+            },
+        }
+    }
+}